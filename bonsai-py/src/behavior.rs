@@ -68,6 +68,8 @@ impl PyBehavior {
             Behavior::WhenAny(v) => format!("WhenAny({})", v.len()),
             Behavior::After(v) => format!("After({})", v.len()),
             Behavior::Race(v) => format!("Race({})", v.len()),
+            Behavior::Named(name, _) => format!("Named({name:?}, ...)"),
+            Behavior::Meta(meta, _) => format!("Meta({meta:?}, ...)"),
         }
     }
 }
@@ -93,8 +93,8 @@ impl PyBT {
         Ok(self.require_inner()?.is_finished())
     }
 
-    fn graphviz(&mut self) -> PyResult<String> {
-        Ok(self.require_inner_mut()?.get_graphviz())
+    fn graphviz(&self) -> PyResult<String> {
+        Ok(self.require_inner()?.get_graphviz())
     }
 
     #[pyo3(signature = (port, host = "127.0.0.1"))]
@@ -0,0 +1,376 @@
+//! Who may use a visualizer server, and what they may do.
+//!
+//! By default a server is read-only: anyone who can reach the port may watch
+//! the tree, but nobody may drive its [debugger](crate::debugger), which
+//! could pause or block the tree. An [`AccessPolicy`] changes that with
+//!
+//! - an allow-list of client IP ranges — everyone else gets `403`,
+//! - shared-secret tokens, each granting a [`Role`] — requests without a
+//!   valid token get `401`, and
+//! - the role of clients without a token; [`AccessPolicy::open`] gives them
+//!   [`Role::Control`], for a trusted network.
+//!
+//! Clients present a token as a `token` query parameter
+//! (`http://robot:8910/?token=s3cret`; the page passes it on to its
+//! WebSocket) or as an `Authorization: Bearer s3cret` header. Both HTTP
+//! requests and WebSocket upgrades are checked.
+//!
+//! Tokens travel in clear text: this keeps casual LAN visitors out, but is
+//! no substitute for TLS on an untrusted network.
+//!
+//! ```no_run
+//! use bonsai_bt::access::{AccessPolicy, Role};
+//! use bonsai_bt::{Action, BT};
+//!
+//! let policy = AccessPolicy::new()
+//!     .token("operator-secret", Role::Control)
+//!     .token("team-secret", Role::Viewer)
+//!     .allow("192.168.1.0/24".parse().unwrap())
+//!     .allow("127.0.0.1".parse().unwrap());
+//! let bt = BT::new(Action("step"), ()).with_telemetry_access("0.0.0.0", 8910, policy)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::error::Error;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// What a client may do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Role {
+    /// Watch the tree: the page, ticks, profiles, metrics.
+    Viewer,
+    /// Also pause, step and set breakpoints.
+    Control,
+}
+
+/// A range of IP addresses in CIDR notation, e.g. `10.0.0.0/8`, `::1/128`
+/// or a bare address (a range of one).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    /// The addresses sharing the first `prefix` bits with `addr`. An
+    /// IPv4-mapped `addr` (`::ffff:a.b.c.d`) makes the IPv4 range of its last
+    /// `prefix - 96` bits.
+    ///
+    /// # Panics
+    /// Panics if `prefix` exceeds the address width (32 or 128), or is below
+    /// 96 for an IPv4-mapped `addr`.
+    pub fn new(addr: IpAddr, prefix: u8) -> Self {
+        canonical(addr, prefix).unwrap_or_else(|| panic!("prefix /{prefix} does not fit {addr}"))
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 clients of a dual-stack listener show up as `::ffff:a.b.c.d`.
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                same_prefix(u32::from(net).into(), u32::from(ip).into(), 32, self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => same_prefix(net.into(), ip.into(), 128, self.prefix),
+            _ => false,
+        }
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(addr: IpAddr) -> Self {
+        Self::new(addr, max_prefix(addr))
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = ParseIpRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseIpRangeError(s.to_string());
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| err())?;
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().map_err(|_| err())?,
+            None => max_prefix(addr),
+        };
+        canonical(addr, prefix).ok_or_else(err)
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Error parsing an [`IpRange`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseIpRangeError(String);
+
+impl fmt::Display for ParseIpRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid IP range {:?}: expected an address or address/prefix",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseIpRangeError {}
+
+fn max_prefix(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+/// The range of `addr/prefix`, an IPv4-mapped one as IPv4; `None` if
+/// `prefix` doesn't fit.
+fn canonical(addr: IpAddr, prefix: u8) -> Option<IpRange> {
+    if prefix > max_prefix(addr) {
+        return None;
+    }
+    match addr.to_canonical() {
+        IpAddr::V4(v4) if addr.is_ipv6() => Some(IpRange {
+            addr: IpAddr::V4(v4),
+            prefix: prefix.checked_sub(96)?,
+        }),
+        _ => Some(IpRange { addr, prefix }),
+    }
+}
+
+fn same_prefix(a: u128, b: u128, width: u32, prefix: u8) -> bool {
+    let prefix = u32::from(prefix);
+    prefix == 0 || (a ^ b) >> (width - prefix) == 0
+}
+
+/// Access rules of a visualizer server. See the [module docs](self).
+#[derive(Clone, Default)]
+pub struct AccessPolicy {
+    tokens: Vec<(String, Role)>,
+    allow: Vec<IpRange>,
+    /// `None` until set with [`anonymous`](Self::anonymous).
+    anonymous: Option<Option<Role>>,
+}
+
+/// Why a request was refused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Denied {
+    /// The client's address is not on the allow-list.
+    Forbidden,
+    /// No valid token was presented.
+    Unauthorized,
+}
+
+impl AccessPolicy {
+    /// A read-only policy: every client has [`Role::Viewer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An open policy: every client has [`Role::Control`], so anyone who can
+    /// reach the port may pause the tree. Shorthand for
+    /// `anonymous(Some(Role::Control))`.
+    pub fn open() -> Self {
+        Self::new().anonymous(Some(Role::Control))
+    }
+
+    /// Grant `role` to clients presenting `token`. Once any token is set,
+    /// clients without one are refused, unless [`anonymous`](Self::anonymous)
+    /// says otherwise.
+    pub fn token(mut self, token: impl Into<String>, role: Role) -> Self {
+        self.tokens.push((token.into(), role));
+        self
+    }
+
+    /// Only serve clients whose address is in `range`. Call repeatedly to
+    /// allow several ranges; with none, every address is allowed.
+    pub fn allow(mut self, range: IpRange) -> Self {
+        self.allow.push(range);
+        self
+    }
+
+    /// The role of clients presenting no token: `None` refuses them. Defaults
+    /// to [`Role::Viewer`] without tokens and `None` with.
+    pub fn anonymous(mut self, role: Option<Role>) -> Self {
+        self.anonymous = Some(role);
+        self
+    }
+
+    /// Shorthand for `anonymous(Some(Role::Viewer))`: anyone may watch, only
+    /// token holders may control.
+    pub fn read_only(self) -> Self {
+        self.anonymous(Some(Role::Viewer))
+    }
+
+    /// The role of a client at `peer` whose request starts with `head` (the
+    /// request line and headers).
+    pub(crate) fn authorize(&self, peer: Option<IpAddr>, head: &str) -> Result<Role, Denied> {
+        if !self.allow.is_empty() {
+            match peer {
+                Some(ip) if self.allow.iter().any(|range| range.contains(ip)) => {}
+                _ => return Err(Denied::Forbidden),
+            }
+        }
+        if let Some(presented) = presented_token(head) {
+            // Check every token, so timing doesn't reveal which one matched.
+            let mut role = None;
+            for (token, granted) in &self.tokens {
+                if constant_time_eq(token.as_bytes(), presented.as_bytes()) {
+                    role = role.max(Some(*granted));
+                }
+            }
+            if let Some(role) = role {
+                return Ok(role);
+            }
+        }
+        let anonymous = self.anonymous.unwrap_or(if self.tokens.is_empty() {
+            Some(Role::Viewer)
+        } else {
+            None
+        });
+        anonymous.ok_or(Denied::Unauthorized)
+    }
+}
+
+impl fmt::Debug for AccessPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print the secrets themselves.
+        let roles: Vec<Role> = self.tokens.iter().map(|(_, role)| *role).collect();
+        f.debug_struct("AccessPolicy")
+            .field("tokens", &roles)
+            .field("allow", &self.allow)
+            .field("anonymous", &self.anonymous)
+            .finish()
+    }
+}
+
+/// The token from the `token` query parameter or an `Authorization: Bearer`
+/// header of the request in `head`.
+fn presented_token(head: &str) -> Option<String> {
+    let mut lines = head.lines();
+    let target = lines.next()?.split_whitespace().nth(1).unwrap_or("");
+    let query = target.split_once('?').map_or("", |(_, q)| q);
+    for pair in query.split('&') {
+        if let Some(("token", value)) = pair.split_once('=') {
+            return Some(percent_decode(value));
+        }
+    }
+    lines.find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if !name.trim().eq_ignore_ascii_case("authorization") {
+            return None;
+        }
+        let (scheme, token) = value.trim().split_once(' ')?;
+        scheme.eq_ignore_ascii_case("bearer").then(|| token.trim().to_string())
+    })
+}
+
+/// Decode `%XX` escapes and `+` (as sent by `encodeURIComponent` and forms).
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = |b: Option<&u8>| b.and_then(|b| (*b as char).to_digit(16));
+        match (bytes[i], hex(bytes.get(i + 1)), hex(bytes.get(i + 2))) {
+            (b'%', Some(hi), Some(lo)) => {
+                out.push((hi * 16 + lo) as u8);
+                i += 3;
+                continue;
+            }
+            (b'+', ..) => out.push(b' '),
+            (byte, ..) => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(target: &str) -> String {
+        format!("GET {target} HTTP/1.1\r\nHost: x\r\n\r\n")
+    }
+
+    #[test]
+    fn ranges_match_by_prefix() {
+        let lan: IpRange = "192.168.1.0/24".parse().unwrap();
+        assert!(lan.contains("192.168.1.77".parse().unwrap()));
+        assert!(!lan.contains("192.168.2.1".parse().unwrap()));
+        assert!(lan.contains("::ffff:192.168.1.5".parse().unwrap()), "v4-mapped peers");
+        let any: IpRange = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains("8.8.8.8".parse().unwrap()));
+        assert!("::1".parse::<IpRange>().unwrap().contains("::1".parse().unwrap()));
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn tokens_come_from_query_or_bearer_header() {
+        assert_eq!(
+            presented_token(&get("/?proto=binary&token=a%2Fb")).as_deref(),
+            Some("a/b")
+        );
+        let head = "GET / HTTP/1.1\r\nauthorization: Bearer s3cret\r\n\r\n";
+        assert_eq!(presented_token(head).as_deref(), Some("s3cret"));
+        assert_eq!(presented_token(&get("/")), None);
+    }
+
+    #[test]
+    fn roles_follow_tokens_and_anonymous_default() {
+        assert_eq!(AccessPolicy::new().authorize(None, &get("/")), Ok(Role::Viewer));
+        assert_eq!(AccessPolicy::open().authorize(None, &get("/")), Ok(Role::Control));
+
+        let policy = AccessPolicy::new().token("c", Role::Control).token("v", Role::Viewer);
+        assert_eq!(policy.authorize(None, &get("/?token=c")), Ok(Role::Control));
+        assert_eq!(policy.authorize(None, &get("/?token=v")), Ok(Role::Viewer));
+        assert_eq!(policy.authorize(None, &get("/?token=x")), Err(Denied::Unauthorized));
+        assert_eq!(policy.authorize(None, &get("/")), Err(Denied::Unauthorized));
+        assert_eq!(policy.read_only().authorize(None, &get("/")), Ok(Role::Viewer));
+    }
+
+    #[test]
+    fn allow_list_is_checked_first() {
+        let policy = AccessPolicy::new().allow("10.0.0.0/8".parse().unwrap());
+        assert_eq!(
+            policy.authorize(Some("10.1.2.3".parse().unwrap()), &get("/")),
+            Ok(Role::Viewer)
+        );
+        assert_eq!(
+            policy.authorize(Some("11.0.0.1".parse().unwrap()), &get("/")),
+            Err(Denied::Forbidden)
+        );
+
+        // IPv4-mapped ranges are IPv4 ranges.
+        let mapped: IpRange = "::ffff:10.0.0.0/104".parse().unwrap();
+        assert_eq!(mapped, "10.0.0.0/8".parse().unwrap());
+        let policy = AccessPolicy::new()
+            .allow(mapped)
+            .allow(IpRange::from("::ffff:192.168.1.7".parse::<IpAddr>().unwrap()));
+        for client in ["10.1.2.3", "::ffff:10.1.2.3", "192.168.1.7"] {
+            assert_eq!(
+                policy.authorize(Some(client.parse().unwrap()), &get("/")),
+                Ok(Role::Viewer),
+                "{client}"
+            );
+        }
+        assert_eq!(
+            policy.authorize(Some("192.168.1.8".parse().unwrap()), &get("/")),
+            Err(Denied::Forbidden)
+        );
+        assert!("::ffff:10.0.0.0/95".parse::<IpRange>().is_err());
+    }
+}
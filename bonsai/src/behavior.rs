@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
@@ -9,6 +11,7 @@ use crate::Float;
 /// Can also be used for game AI.
 #[derive(Clone, PartialEq, Debug)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum Behavior<A> {
     /// Waits an amount of time before continuing
     ///
@@ -65,7 +68,10 @@ pub enum Behavior<A> {
     /// # Panics
     ///
     /// Panics if the given behavior sequence is empty.
-    While(Box<Behavior<A>>, Vec<Behavior<A>>),
+    While(
+        Box<Behavior<A>>,
+        #[cfg_attr(feature = "schemars", schemars(length(min = 1)))] Vec<Behavior<A>>,
+    ),
 
     /// Runs a sequence on repeat as long as a conditional behavior
     /// that precedes the sequence is running.
@@ -124,7 +130,18 @@ pub enum Behavior<A> {
     ///});
     ///assert!(i == 4);
     /// ```
-    WhileAll(Box<Behavior<A>>, Vec<Behavior<A>>),
+    // The example above is left out of the JSON Schema description.
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(description = "Runs a sequence on repeat as long as a conditional behavior \
+                                that precedes the sequence is running.\n\n\
+                                Conditional behavior is **only** checked before the sequence runs and \
+                                not during the sequence.")
+    )]
+    WhileAll(
+        Box<Behavior<A>>,
+        #[cfg_attr(feature = "schemars", schemars(length(min = 1)))] Vec<Behavior<A>>,
+    ),
     /// Runs all behaviors in parallel until all succeeded.
     ///
     /// Succeeds if all behaviors succeed.
@@ -146,6 +163,21 @@ pub enum Behavior<A> {
     /// whether that is `Success` or `Failure`.
     /// If all behaviors remain `Running`, returns `Running`.
     Race(Vec<Behavior<A>>),
+    /// Gives the wrapped behavior a name, for addressing it with a
+    /// [`NodePath`](crate::path::NodePath) that survives edits elsewhere in
+    /// the tree. Built via `.named(..)`.
+    ///
+    /// Runs exactly as the wrapped behavior. It is not a node of its own: it
+    /// has no preorder id and no entry in a `TickTrace`, so naming a node
+    /// leaves every id unchanged.
+    Named(String, Box<Behavior<A>>),
+    /// Annotates the wrapped behavior with free-form key/value metadata —
+    /// a `description`, an `owner`, the blackboard keys it `reads` or
+    /// `writes` — for tools to show. Built via `.meta(..)`.
+    ///
+    /// Like `Named`, runs exactly as the wrapped behavior and is not a node
+    /// of its own.
+    Meta(BTreeMap<String, String>, Box<Behavior<A>>),
 }
 
 impl<A> Behavior<A> {
@@ -163,6 +195,82 @@ impl<A> Behavior<A> {
             (other, _) => other,
         }
     }
+
+    /// Name this behavior, wrapping it in [`Named`](Behavior::Named).
+    #[must_use]
+    pub fn named(self, name: impl Into<String>) -> Self {
+        Behavior::Named(name.into(), Box::new(self))
+    }
+
+    /// Set metadata `key` to `value`, wrapping the behavior in
+    /// [`Meta`](Behavior::Meta) unless it already is one.
+    ///
+    /// ```
+    /// use bonsai_bt::Action;
+    ///
+    /// let aim = Action("aim").meta("description", "Turn to the target").meta("reads", "target");
+    /// assert_eq!(aim.metadata()["reads"], "target");
+    /// ```
+    #[must_use]
+    pub fn meta(self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        match self {
+            Behavior::Meta(mut meta, inner) => {
+                meta.insert(key.into(), value.into());
+                Behavior::Meta(meta, inner)
+            }
+            other => Behavior::Meta(BTreeMap::from([(key.into(), value.into())]), Box::new(other)),
+        }
+    }
+
+    /// The name given with [`named`](Self::named), if any; the outermost
+    /// one if the node was named more than once.
+    pub fn name(&self) -> Option<&str> {
+        let mut behavior = self;
+        loop {
+            match behavior {
+                Behavior::Named(name, _) => return Some(name),
+                Behavior::Meta(_, inner) => behavior = inner,
+                _ => return None,
+            }
+        }
+    }
+
+    /// The metadata set with [`meta`](Self::meta), merged over all of the
+    /// node's wrappers; the outermost value of a key wins.
+    pub fn metadata(&self) -> BTreeMap<String, String> {
+        let mut metadata = BTreeMap::new();
+        let mut behavior = self;
+        loop {
+            match behavior {
+                Behavior::Named(_, inner) => behavior = inner,
+                Behavior::Meta(meta, inner) => {
+                    for (key, value) in meta {
+                        metadata.entry(key.clone()).or_insert_with(|| value.clone());
+                    }
+                    behavior = inner;
+                }
+                _ => return metadata,
+            }
+        }
+    }
+
+    /// The behavior without its [`Named`](Behavior::Named) and
+    /// [`Meta`](Behavior::Meta) wrappers.
+    pub fn unnamed(&self) -> &Behavior<A> {
+        let mut behavior = self;
+        while let Behavior::Named(_, inner) | Behavior::Meta(_, inner) = behavior {
+            behavior = inner;
+        }
+        behavior
+    }
+
+    /// Like [`unnamed`](Self::unnamed), mutably.
+    pub fn unnamed_mut(&mut self) -> &mut Behavior<A> {
+        match self {
+            Behavior::Named(_, inner) | Behavior::Meta(_, inner) => inner.unnamed_mut(),
+            behavior => behavior,
+        }
+    }
 }
 
 #[cfg(test)]
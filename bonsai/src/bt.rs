@@ -1,14 +1,12 @@
+use crate::tracer::{build_node_metas, NodeMeta, NoopTracer, Tracer};
 use crate::{state::State, ActionArgs, Behavior, Float, Status, UpdateEvent};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// Result of [`BT::try_route_recording`]: whether the recording helper consumed
-/// the tick. `Handled` carries the value `tick` should return; `NotHandled`
-/// tells the caller to continue with the no-op path. Under
-/// `not(feature = "visualize")` the helper unconditionally returns
-/// `NotHandled`, so the `Handled` variant is unconstructed.
-#[allow(dead_code)]
+/// the tick. `Handled` carries the value `tick_with_tracer` should return;
+/// `NotHandled` tells the caller to continue with its own tracer only.
 enum TickRoute {
     NotHandled,
     Handled(Option<(Status, Float)>),
@@ -33,31 +31,37 @@ pub struct BT<A, B> {
     /// `tick`/`tick_recording` call increments to 1. Survives `reset_bt`
     /// (the counter is global to the BT instance, not the current run).
     pub(crate) tick_count: u64,
-    /// Bundle of visualize-only state: preorder node metadata, telemetry
-    /// channel sender, dropped-trace counter, and the per-tick recording
-    /// buffer. See [`crate::telemetry_state::TelemetryState`].
+    /// Preorder node metadata, computed once at `BT::new`. Used by recording
+    /// tracers to advance past unvisited subtrees in O(1). Skipped by serde;
+    /// rebuilt on the first traced tick after deserialization.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) node_metas: Vec<NodeMeta>,
+    /// Bundle of visualize-only state: telemetry channel sender,
+    /// dropped-trace counter, and the per-tick recording buffer. See [`crate::telemetry_state::TelemetryState`].
     #[cfg(feature = "visualize")]
     #[cfg_attr(feature = "serde", serde(skip))]
-    pub(crate) telemetry: crate::telemetry_state::TelemetryState,
+    pub(crate) telemetry: crate::telemetry_state::TelemetryState<B>,
+    /// In-process tick subscribers; see [`crate::subscriber`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) subscribers: crate::subscriber::Subscribers,
 }
 
 impl<A: Clone, B> BT<A, B> {
     pub fn new(behavior: Behavior<A>, blackboard: B) -> Self {
         let backup_behavior = behavior.clone();
+        let node_metas = build_node_metas(&backup_behavior);
         let bt = State::new(behavior);
 
-        #[cfg(feature = "visualize")]
-        let telemetry =
-            crate::telemetry_state::TelemetryState::new(crate::telemetry::build_node_metas(&backup_behavior));
-
         Self {
             state: bt,
             initial_behavior: backup_behavior,
             bb: blackboard,
             finished: false,
             tick_count: 0,
+            node_metas,
             #[cfg(feature = "visualize")]
-            telemetry,
+            telemetry: crate::telemetry_state::TelemetryState::default(),
+            subscribers: Default::default(),
         }
     }
 
@@ -80,67 +84,76 @@ impl<A: Clone, B> BT<A, B> {
     where
         E: UpdateEvent,
         F: FnMut(ActionArgs<E, A>, &mut B) -> (Status, Float),
+    {
+        self.tick_with_tracer(e, f, &mut NoopTracer)
+    }
+
+    /// Like [`tick`](Self::tick), but reports every node visited this tick to
+    /// `tracer` — see [`Tracer`] for the available hooks and the node-id
+    /// scheme. Use it to plug in logging, profiling or metrics without the
+    /// `visualize` feature.
+    ///
+    /// If a visualizer is attached (`with_telemetry`), the tick is still
+    /// recorded and shipped as usual, with `tracer` observing alongside.
+    ///
+    /// Returns [`None`] if the tree has already finished (mirroring
+    /// [`tick`](Self::tick)).
+    #[inline]
+    pub fn tick_with_tracer<E, F, T>(&mut self, e: &E, f: &mut F, tracer: &mut T) -> Option<(Status, Float)>
+    where
+        E: UpdateEvent,
+        F: FnMut(ActionArgs<E, A>, &mut B) -> (Status, Float),
+        T: Tracer,
     {
         if self.finished {
             return None;
         }
-        if let TickRoute::Handled(out) = self.try_route_recording(e, f) {
+        if let TickRoute::Handled(out) = self.try_route_recording(e, f, tracer) {
             return out;
         }
         self.tick_count += 1;
-        let result = self.dispatch_noop_tick(e, f);
+        if T::IS_RECORDING {
+            self.ensure_node_metas();
+        }
+        let result = self.state.tick(0, &self.node_metas, e, &mut self.bb, f, tracer);
         if matches!(result, (Status::Success | Status::Failure, _)) {
             self.finished = true;
         }
         Some(result)
     }
 
-    /// Run `State::tick` with a [`NoopTracer`](crate::tracer::NoopTracer) (the
-    /// non-recording path). The cfg-gated `metas` binding lives inside this
-    /// helper so `tick`'s body can stay free of `#[cfg]` directives.
-    ///
-    /// Disjoint-field borrows: `&self.telemetry.node_metas` (immutable) and
-    /// `&mut self.state` / `&mut self.bb` (mutable) target distinct fields,
-    /// so the borrow checker accepts the simultaneous borrows.
-    ///
-    /// `#[inline(always)]` ensures the cfg branches constant-fold at the
-    /// monomorphization site, leaving identical generated code to the prior
-    /// inlined-in-`tick` version.
-    #[inline(always)]
-    fn dispatch_noop_tick<E, F>(&mut self, e: &E, f: &mut F) -> (Status, Float)
-    where
-        E: UpdateEvent,
-        F: FnMut(ActionArgs<E, A>, &mut B) -> (Status, Float),
-    {
-        let mut tracer = crate::tracer::NoopTracer;
-        #[cfg(feature = "visualize")]
-        let metas: &[crate::tracer::NodeMeta] = &self.telemetry.node_metas;
-        #[cfg(not(feature = "visualize"))]
-        let metas: &[crate::tracer::NodeMeta] = &[];
-        self.state.tick(0, metas, e, &mut self.bb, f, &mut tracer)
+    /// Rebuild `node_metas` if it is missing, which only happens for a BT
+    /// obtained through deserialization. Every tree has at least one node, so
+    /// an empty vector is never a valid table.
+    #[inline]
+    pub(crate) fn ensure_node_metas(&mut self) {
+        if self.node_metas.is_empty() {
+            self.node_metas = build_node_metas(&self.initial_behavior);
+        }
     }
 
-    /// If telemetry is attached, dispatch to `tick_recording` and return its
-    /// result as [`TickRoute::Handled`]. Returns [`TickRoute::NotHandled`]
-    /// otherwise — the caller (`tick`) should proceed with the no-op path.
+    /// If telemetry is attached, dispatch to `tick_recording_with`, else if
+    /// anyone [subscribed](Self::subscribe), to `tick_subscribed`, and return
+    /// the result as [`TickRoute::Handled`]. Returns [`TickRoute::NotHandled`]
+    /// otherwise — the caller (`tick_with_tracer`) should proceed with the
+    /// caller's tracer alone.
     ///
-    /// `#[inline(always)]` lets the optimizer constant-fold the no-op path:
-    /// under `not(feature = "visualize")` the body unconditionally returns
-    /// `TickRoute::NotHandled`, so the `if let TickRoute::Handled(_) = ...`
-    /// branch in `tick` becomes unreachable and disappears.
+    /// `#[inline(always)]` keeps the untraced path down to a couple of
+    /// inlined emptiness checks in `tick_with_tracer`.
     #[inline(always)]
-    fn try_route_recording<E, F>(&mut self, e: &E, f: &mut F) -> TickRoute
+    fn try_route_recording<E, F, T>(&mut self, e: &E, f: &mut F, tracer: &mut T) -> TickRoute
     where
         E: UpdateEvent,
         F: FnMut(ActionArgs<E, A>, &mut B) -> (Status, Float),
+        T: Tracer,
     {
         #[cfg(feature = "visualize")]
-        if self.telemetry.sender.is_some() {
-            return TickRoute::Handled(self.tick_recording(e, f).map(|(result, _)| result));
+        if self.telemetry.is_active() {
+            return TickRoute::Handled(self.tick_recording_with(e, f, tracer));
+        }
+        if !self.subscribers.is_empty() {
+            return TickRoute::Handled(self.tick_subscribed(e, f, tracer));
         }
-        // Suppress unused-variable warnings on the no-op path (visualize off,
-        // or visualize on but no sender attached).
-        let _ = (e, f);
         TickRoute::NotHandled
     }
 
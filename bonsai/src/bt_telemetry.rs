@@ -3,8 +3,22 @@
 //! are unnecessary.
 
 use std::fmt::Debug;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
 
-use crate::telemetry::{RecordingTracer, TickTrace, TreeDefinition};
+use crate::access::AccessPolicy;
+use crate::debugger::Debugger;
+use crate::hub::TelemetryHub;
+use crate::metrics::TreeMetrics;
+use crate::profiler::ProfileReport;
+use crate::recording::{FlightRecorder, TraceRecorder};
+use crate::telemetry::{PackedTracer, TelemetryFrame, TickTrace, TreeDefinition};
+use crate::telemetry_state::{AcceptorGuard, BlackboardTap};
+use crate::tracer::{NoopTracer, Tracer};
+use crate::visualizer_server::ServerExtras;
+use crate::wire::StatusVec;
 use crate::{ActionArgs, Float, Status, UpdateEvent, BT};
 
 impl<A: Clone, B> BT<A, B> {
@@ -25,43 +39,274 @@ impl<A: Clone, B> BT<A, B> {
     where
         E: UpdateEvent,
         F: FnMut(ActionArgs<E, A>, &mut B) -> (Status, Float),
+    {
+        let result = self.tick_recording_with(e, f, &mut NoopTracer)?;
+        Some((result, self.telemetry.status_buffer.to_trace(self.tick_count)))
+    }
+
+    /// Record the tick into the reusable trace buffer while `extra` observes
+    /// it too, then ship the trace to the broadcaster, the on-disk recorder
+    /// and the flight recorder (whichever are attached). Backs
+    /// both [`tick_recording`](Self::tick_recording) and the telemetry route
+    /// of [`tick_with_tracer`](Self::tick_with_tracer).
+    pub(crate) fn tick_recording_with<E, F, T>(&mut self, e: &E, f: &mut F, extra: &mut T) -> Option<(Status, Float)>
+    where
+        E: UpdateEvent,
+        F: FnMut(ActionArgs<E, A>, &mut B) -> (Status, Float),
+        T: Tracer,
     {
         if self.finished {
             return None;
         }
+        if let Some(debugger) = &self.telemetry.debugger {
+            if !debugger.gate() {
+                // Paused in `PauseMode::Skip`: nothing ran, no time consumed.
+                return Some((Status::Running, e.update(|args| args.dt).unwrap_or(0.0)));
+            }
+        }
         self.tick_count += 1;
-        // Reuse the long-lived buffer instead of fresh-allocating per tick.
-        // `clear()` preserves the HashMap's capacity, so once warmed up the
-        // fill phase doesn't reallocate.
-        self.telemetry.trace_buffer.tick_id = self.tick_count;
-        self.telemetry.trace_buffer.states.clear();
+        self.ensure_node_metas();
+        // Reuse the long-lived packed buffer instead of fresh-allocating per
+        // tick; it is sized once to the tree and cleared in place.
+        if self.telemetry.status_buffer.len() != self.node_metas.len() {
+            self.telemetry.status_buffer = StatusVec::new(self.node_metas.len());
+        }
+        self.telemetry.status_buffer.clear();
         let result = {
-            let mut tracer = RecordingTracer {
-                trace: &mut self.telemetry.trace_buffer,
-                metas: &self.telemetry.node_metas,
+            let packed = PackedTracer {
+                states: &mut self.telemetry.status_buffer,
             };
-            self.state
-                .tick(0, &self.telemetry.node_metas, e, &mut self.bb, f, &mut tracer)
+            let mut tracer = (packed, extra);
+            self.state.tick(0, &self.node_metas, e, &mut self.bb, f, &mut tracer)
         };
         if matches!(result, (Status::Success | Status::Failure, _)) {
             self.finished = true;
         }
+        if let Some(debugger) = &self.telemetry.debugger {
+            debugger.observe(self.tick_count, &self.telemetry.status_buffer);
+        }
         // Try to ship the trace to the broadcaster thread. Uses as_ref().map() to
         // release the immutable borrow before the match arms take mutable borrows.
-        if let Some(outcome) = self
-            .telemetry
-            .sender
-            .as_ref()
-            .map(|tx| tx.try_send(self.telemetry.trace_buffer.clone()))
-        {
-            use std::sync::mpsc::TrySendError;
+        use std::sync::mpsc::TrySendError;
+        let blackboard = self.telemetry.blackboard.as_ref().map(|tap| tap.snapshot(&self.bb));
+        if let Some(outcome) = self.telemetry.sender.as_ref().map(|tx| {
+            let update = match (&mut self.telemetry.blackboard, &blackboard) {
+                (Some(tap), Some(current)) => tap.update(current.clone()),
+                _ => None,
+            };
+            tx.try_send(TelemetryFrame::Packed {
+                tick_id: self.tick_count,
+                states: self.telemetry.status_buffer.clone(),
+                blackboard: update,
+            })
+            // The unsent frame is of no use here; don't carry it around.
+            .map_err(|e| match e {
+                TrySendError::Full(_) => TrySendError::Full(()),
+                TrySendError::Disconnected(_) => TrySendError::Disconnected(()),
+            })
+        }) {
             match outcome {
                 Ok(()) => {}
-                Err(TrySendError::Full(_)) => self.telemetry.dropped_traces += 1,
-                Err(TrySendError::Disconnected(_)) => self.telemetry.sender = None,
+                Err(TrySendError::Full(())) => {
+                    self.telemetry.dropped_traces += 1;
+                    // Clients missed a patch; resync them with a keyframe.
+                    if let Some(tap) = &mut self.telemetry.blackboard {
+                        tap.restart();
+                    }
+                    if let Some(metrics) = &self.telemetry.metrics {
+                        metrics.record_dropped();
+                    }
+                }
+                Err(TrySendError::Disconnected(())) => self.telemetry.sender = None,
+            }
+        }
+        if let Some(metrics) = &self.telemetry.metrics {
+            metrics.observe(&self.telemetry.status_buffer);
+        }
+        if self.telemetry.flight_recorder.is_none() && self.telemetry.recorder.is_none() && self.subscribers.is_empty()
+        {
+            return Some(result);
+        }
+        // Recorders and subscribers take `TickTrace`s; unpack into the reusable buffer,
+        // whose `clear()` keeps the HashMap's capacity.
+        let trace = &mut self.telemetry.trace_buffer;
+        trace.tick_id = self.tick_count;
+        trace.states.clear();
+        trace.states.extend(self.telemetry.status_buffer.iter());
+        let dt = e.update(|args| args.dt);
+        if let Some(flight) = &self.telemetry.flight_recorder {
+            flight.push(&self.telemetry.trace_buffer, dt, blackboard.clone(), result.0);
+        }
+        if let Some(recorder) = &self.telemetry.recorder {
+            // Errors are sticky inside the recorder and surface from
+            // `stop_recording`; the tick itself never fails on I/O.
+            let _ = recorder
+                .lock()
+                .expect("recorder mutex poisoned")
+                .record_with_blackboard(&self.telemetry.trace_buffer, dt, blackboard.as_ref());
+        }
+        self.subscribers.publish(&self.telemetry.trace_buffer);
+        Some(result)
+    }
+
+    /// Record every tick to the file at `path` (created or truncated), in the
+    /// format read back by [`Recording::load`](crate::recording::Recording::load)
+    /// for offline replay.
+    ///
+    /// Builder method, like [`with_telemetry`](Self::with_telemetry), and
+    /// independent of it: a tree can record without a live visualizer, or
+    /// both. After calling this, [`tick`](Self::tick) records automatically.
+    /// Each tick costs one JSON line written through a `BufWriter`; call
+    /// [`stop_recording`](Self::stop_recording) to flush and close the file.
+    ///
+    /// Replaces any recorder attached earlier, dropping it without flushing
+    /// its buffered ticks — stop it first to keep them.
+    ///
+    /// # Errors
+    /// Returns `io::Error` if the file cannot be created or the header
+    /// cannot be written.
+    pub fn with_recording(self, path: impl AsRef<Path>) -> io::Result<Self>
+    where
+        A: Debug,
+    {
+        let recorder = TraceRecorder::create(path, &TreeDefinition::build(&self.initial_behavior))?;
+        Ok(self.with_recorder(recorder))
+    }
+
+    /// Like [`with_recording`](Self::with_recording), but records into a
+    /// caller-built [`TraceRecorder`] over any writer (a socket, an
+    /// in-memory buffer, a compressor, ...).
+    pub fn with_recorder<W: Write + Send + 'static>(mut self, recorder: TraceRecorder<W>) -> Self {
+        self.telemetry.recorder = Some(Arc::new(Mutex::new(recorder.boxed())));
+        self
+    }
+
+    /// Detach the recorder, flush it, and report the first write error it
+    /// hit, if any. A no-op returning `Ok` when nothing is recording.
+    ///
+    /// If the `BT` was cloned while recording, the clones keep the file open
+    /// and this only detaches the recorder from `self`.
+    pub fn stop_recording(&mut self) -> io::Result<()> {
+        let Some(recorder) = self.telemetry.recorder.take() else {
+            return Ok(());
+        };
+        match Arc::try_unwrap(recorder) {
+            Ok(recorder) => recorder
+                .into_inner()
+                .expect("recorder mutex poisoned")
+                .finish()
+                .map(drop),
+            Err(shared) => shared.lock().expect("recorder mutex poisoned").flush(),
+        }
+    }
+
+    /// The tree's [`Debugger`]: pause, single-step and breakpoints, shared
+    /// with the visualizer's control channel. Created on first use (or by
+    /// [`with_telemetry`](Self::with_telemetry)); from then on every tick
+    /// takes the recording path so breakpoints can see node statuses.
+    ///
+    /// Returns a handle; clone it freely, e.g. to resume a
+    /// [`PauseMode::Block`](crate::debugger::PauseMode::Block)ed tree from
+    /// another thread.
+    pub fn debugger(&mut self) -> Debugger {
+        self.telemetry.debugger.get_or_insert_with(Debugger::new).clone()
+    }
+
+    /// Stream the blackboard alongside every tick, so the visualizer's
+    /// inspector panel can show *why* a condition failed.
+    ///
+    /// Builder method, combinable with [`with_telemetry`](Self::with_telemetry),
+    /// [`with_recording`](Self::with_recording) and
+    /// [`with_flight_recorder`](Self::with_flight_recorder) in any order.
+    /// Opt-in because the blackboard is serialized to JSON on every tick.
+    /// The live stream carries a full snapshot every 50 ticks and a JSON
+    /// merge patch (only the keys that changed) on the ticks in between;
+    /// recordings store a full snapshot per tick so they can be seeked.
+    ///
+    /// A blackboard that fails to serialize (e.g. a map with non-string
+    /// keys) shows up as a string describing the error.
+    pub fn with_telemetry_blackboard(mut self) -> Self
+    where
+        B: serde::Serialize,
+    {
+        self.telemetry.blackboard = Some(BlackboardTap::new(|bb| {
+            serde_json::to_value(bb)
+                .unwrap_or_else(|e| serde_json::Value::String(format!("<blackboard not serializable: {e}>")))
+        }));
+        self
+    }
+
+    /// Keep the most recent ticks in `recorder`, a bounded in-memory ring
+    /// buffer, for post-mortem dumps with [`dump_recent`](Self::dump_recent).
+    ///
+    /// Builder method; cheap enough for production, as each tick only clones
+    /// its [`TickTrace`] into the ring. Replaces any flight recorder attached
+    /// earlier. `recorder` is a shared handle — keep a clone to dump from a
+    /// panic hook, where the `BT` itself is out of reach.
+    pub fn with_flight_recorder(mut self, recorder: FlightRecorder) -> Self
+    where
+        A: Debug,
+    {
+        recorder.set_tree(&TreeDefinition::build(&self.initial_behavior));
+        self.telemetry.flight_recorder = Some(recorder);
+        self
+    }
+
+    /// The flight recorder attached with
+    /// [`with_flight_recorder`](Self::with_flight_recorder), if any.
+    pub fn flight_recorder(&self) -> Option<&FlightRecorder> {
+        self.telemetry.flight_recorder.as_ref()
+    }
+
+    /// Write the flight recorder's buffered ticks to `out`, oldest first, in
+    /// the format read by [`Recording::read`](crate::recording::Recording::read).
+    ///
+    /// # Errors
+    /// Returns `io::ErrorKind::NotFound` if no flight recorder is attached,
+    /// or the error from writing to `out`.
+    pub fn dump_recent<W: Write>(&self, out: W) -> io::Result<()> {
+        match &self.telemetry.flight_recorder {
+            Some(recorder) => recorder.dump(out),
+            None => Err(io::Error::new(io::ErrorKind::NotFound, "no flight recorder attached")),
+        }
+    }
+
+    /// Send a [`ProfileReport`] to connected visualizer clients, which then
+    /// color every node by its p99 exclusive time (a heat map) and list the
+    /// timings in the node tooltip. Label the report first with
+    /// [`ProfileReport::with_labels`] to name the hottest node in the
+    /// status bar.
+    ///
+    /// Best-effort, like tick traces: returns `false` if no visualizer is
+    /// attached or the channel is full. Publishing every few hundred ticks is
+    /// plenty — each report replaces the previous one in the browser.
+    ///
+    /// ```no_run
+    /// # use bonsai_bt::{Action, BT, Event, Success, UpdateArgs};
+    /// use bonsai_bt::profiler::Profiler;
+    /// let behavior = Action("work");
+    /// let mut bt = BT::new(behavior.clone(), ()).with_telemetry(8910)?;
+    /// let mut profiler = Profiler::new();
+    /// let e: Event = UpdateArgs { dt: 0.1 }.into();
+    /// bt.tick_with_tracer(&e, &mut |args, _| (Success, args.dt), &mut profiler);
+    /// bt.publish_profile(&profiler.report().with_labels(&behavior));
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn publish_profile(&mut self, report: &ProfileReport) -> bool {
+        use std::sync::mpsc::TrySendError;
+        let Some(tx) = self.telemetry.sender.as_ref() else {
+            return false;
+        };
+        match tx.try_send(TelemetryFrame::Profile {
+            profile: report.clone(),
+        }) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => false,
+            Err(TrySendError::Disconnected(_)) => {
+                self.telemetry.sender = None;
+                false
             }
         }
-        Some((result, self.telemetry.trace_buffer.clone()))
     }
 
     /// Attach a live visualizer at `http://127.0.0.1:{port}/`.
@@ -114,6 +359,13 @@ impl<A: Clone, B> BT<A, B> {
     /// After calling this method, [`tick`](Self::tick) automatically records and
     /// ships a trace on every call — no API change required.
     ///
+    /// The page is read-only. To let it pause, single-step and set
+    /// breakpoints on the tree, through the same [`Debugger`] that
+    /// [`debugger`](Self::debugger) returns, serve it with
+    /// [`with_telemetry_access`](Self::with_telemetry_access) and a policy
+    /// granting [`Role::Control`](crate::access::Role::Control), e.g.
+    /// [`AccessPolicy::open`].
+    ///
     /// Calling either telemetry method a second time replaces both the
     /// sender and the acceptor guard: the previous broadcaster exits on its
     /// next `recv` (sees `Disconnected`), and the previous acceptor is woken
@@ -155,17 +407,38 @@ impl<A: Clone, B> BT<A, B> {
     /// # use std::collections::HashMap;
     /// let behavior = Sequence(vec![Action("step")]);
     /// let bb: HashMap<String, i32> = HashMap::new();
-    /// // Expose to the LAN — anyone who can reach this host can view the tree.
+    /// // Expose to the LAN — anyone who can reach this host can view the tree,
+    /// // but not pause it: see `with_telemetry_access` for that.
     /// let mut bt = BT::new(behavior, bb).with_telemetry_at("0.0.0.0", 8910)?;
     /// # Ok::<(), std::io::Error>(())
     /// ```
-    pub fn with_telemetry_at(mut self, addr: &str, port: u16) -> std::io::Result<Self>
+    pub fn with_telemetry_at(self, addr: &str, port: u16) -> std::io::Result<Self>
     where
         A: std::fmt::Debug,
+    {
+        self.with_telemetry_access(addr, port, AccessPolicy::new())
+    }
+
+    /// Like [`with_telemetry_at`](Self::with_telemetry_at), but only serve
+    /// clients `access` lets in: by IP range, by token, and with the
+    /// debugger controls reserved for [`Role::Control`](crate::access::Role::Control),
+    /// which [`with_telemetry_at`](Self::with_telemetry_at) grants nobody.
+    /// See [`crate::access`].
+    ///
+    /// ```no_run
+    /// # use bonsai_bt::{Action, BT};
+    /// use bonsai_bt::access::{AccessPolicy, Role};
+    ///
+    /// // Anyone on the LAN may watch; changing anything needs the token.
+    /// let policy = AccessPolicy::new().token("s3cret", Role::Control).read_only();
+    /// let bt = BT::new(Action("step"), ()).with_telemetry_access("0.0.0.0", 8910, policy)?;
+    /// # Ok::<(), std::io::Error>(())
+    /// ```
+    pub fn with_telemetry_access(mut self, addr: &str, port: u16, access: AccessPolicy) -> io::Result<Self>
+    where
+        A: Debug,
     {
         use std::net::TcpListener;
-        use std::sync::mpsc::sync_channel;
-        use std::sync::Arc;
 
         // Drop any prior acceptor guard before binding so re-attaching on the
         // same `(addr, port)` releases the old port first. Replacing the
@@ -174,23 +447,90 @@ impl<A: Clone, B> BT<A, B> {
         self.telemetry.acceptor_guard = None;
 
         let listener = TcpListener::bind((addr, port))?;
-        let definition = serde_json::to_string(&TreeDefinition::build(&self.initial_behavior))
-            .expect("TreeDefinition is always serializable");
-        let (tx, rx) = sync_channel::<TickTrace>(1024);
-        let (acceptor_handle, shutdown, bound_addr) = crate::visualizer_server::spawn_server(listener, definition, rx)?;
-        self.telemetry.sender = Some(tx);
-        self.telemetry.acceptor_guard = Some(Arc::new(crate::telemetry_state::AcceptorGuard::new(
-            shutdown,
-            bound_addr,
-            acceptor_handle,
-        )));
+        self.serve_telemetry(|greeting, rx, extras| {
+            let (acceptor_handle, shutdown, bound_addr) =
+                crate::visualizer_server::spawn_server_with_greeting(listener, greeting, rx, extras, access)?;
+            Ok(Arc::new(AcceptorGuard::new(shutdown, bound_addr, acceptor_handle)))
+        })?;
+        Ok(self)
+    }
+
+    /// Attach a live visualizer served by `hub` at `/tree/{name}`, alongside
+    /// the hub's other trees. See [`TelemetryHub`] for the routes.
+    ///
+    /// Behaves like [`with_telemetry_at`](Self::with_telemetry_at) otherwise:
+    /// frames go through a channel and broadcaster of this tree's own, the
+    /// page can drive its [`debugger`](Self::debugger) if the hub's policy
+    /// allows, and calling it again
+    /// re-attaches. A tree registered under a name already in use replaces
+    /// the older one; a tree is unlisted once its `BT` is dropped.
+    ///
+    /// # Errors
+    /// Returns [`io::ErrorKind::InvalidInput`] unless `name` is non-empty and
+    /// made of ASCII letters, digits, `-`, `_`, `.` and `~`.
+    pub fn with_telemetry_hub(mut self, hub: &TelemetryHub, name: &str) -> io::Result<Self>
+    where
+        A: Debug,
+    {
+        self.telemetry.acceptor_guard = None;
+        self.serve_telemetry(|greeting, rx, extras| hub.register(name, greeting, rx, extras))?;
         Ok(self)
     }
+
+    /// Set up the telemetry channel, metrics and debugger, and hand the
+    /// server side to `serve`. Telemetry is only switched on if it succeeds.
+    fn serve_telemetry<F>(&mut self, serve: F) -> io::Result<()>
+    where
+        A: Debug,
+        F: FnOnce(Vec<String>, Receiver<TelemetryFrame>, ServerExtras) -> io::Result<Arc<AcceptorGuard>>,
+    {
+        let tree = TreeDefinition::build(&self.initial_behavior);
+        let definition = serde_json::to_string(&tree).expect("TreeDefinition is always serializable");
+        let metrics = Arc::new(TreeMetrics::new(&tree));
+        let (tx, rx) = sync_channel::<TelemetryFrame>(1024);
+        let extras = ServerExtras {
+            metrics: Some(Arc::clone(&metrics)),
+            debugger: Some(self.debugger()),
+        };
+        let guard = serve(vec![definition], rx, extras)?;
+        self.telemetry.sender = Some(tx);
+        self.telemetry.metrics = Some(metrics);
+        self.telemetry.acceptor_guard = Some(guard);
+        Ok(())
+    }
+
+    /// Tell the visualizer and `/metrics` that `initial_behavior` was
+    /// replaced. Blocks if the broadcaster channel is full: unlike a tick
+    /// trace, a tree definition must not be dropped.
+    pub(crate) fn announce_tree(&mut self)
+    where
+        A: Debug,
+    {
+        if self.telemetry.sender.is_none() && self.telemetry.metrics.is_none() {
+            return;
+        }
+        let tree = TreeDefinition::build(&self.initial_behavior);
+        if let Some(metrics) = &self.telemetry.metrics {
+            metrics.retarget(&tree);
+        }
+        if let Some(tx) = &self.telemetry.sender {
+            if tx.send(TelemetryFrame::Tree(tree)).is_err() {
+                self.telemetry.sender = None;
+            }
+        }
+        // Clients redraw from scratch; resync their blackboard too.
+        if let Some(tap) = &mut self.telemetry.blackboard {
+            tap.restart();
+        }
+    }
 }
 
 impl<A: Clone + Debug, B: Debug> BT<A, B> {
     /// Compile the behavior tree into a [graphviz](https://graphviz.org/) compatible [DiGraph](https://docs.rs/petgraph/latest/petgraph/graph/type.DiGraph.html).
     ///
+    /// Nodes with [metadata](Behavior::meta) carry it as a `tooltip`, one
+    /// `key: value` line per entry, shown on hover in SVG output.
+    ///
     /// ```rust
     /// use std::collections::HashMap;
     /// use bonsai_bt::{
@@ -218,15 +558,66 @@ impl<A: Clone + Debug, B: Debug> BT<A, B> {
     /// let g = bt.get_graphviz();
     /// println!("{}", g);
     /// ```
-    pub fn get_graphviz(&mut self) -> String {
+    pub fn get_graphviz(&self) -> String {
         self.get_graphviz_with_graph_instance().0
     }
 
+    /// Like [`get_graphviz`](Self::get_graphviz), with every node filled by
+    /// the status it returned in `trace` — green for success, red for
+    /// failure, yellow for running — and nodes `trace` didn't visit greyed
+    /// out.
+    ///
+    /// ```rust
+    /// use bonsai_bt::{Action, Event, Sequence, Success, UpdateArgs, BT};
+    ///
+    /// let mut bt = BT::new(Sequence(vec![Action("a"), Action("b")]), ());
+    /// let e: Event = UpdateArgs { dt: 0.1 }.into();
+    /// let (_, trace) = bt.tick_recording(&e, &mut |_, _| (Success, 0.0)).unwrap();
+    ///
+    /// let dot = bt.get_graphviz_with_trace(&trace);
+    /// assert!(dot.contains("fillcolor = \"#28a745\""));
+    /// ```
+    pub fn get_graphviz_with_trace(&self, trace: &TickTrace) -> String {
+        use crate::visualizer::NodeType;
+        use petgraph::dot::{Config, Dot};
+        use petgraph::graph::NodeIndex;
+
+        let graph = self.graphviz_graph();
+        let ids = crate::visualizer::graph_preorder_ids(&self.initial_behavior);
+        let tooltips = crate::visualizer::graph_tooltips(&self.initial_behavior);
+        let node_attrs = |_, (index, _): (NodeIndex, &NodeType<A>)| {
+            let Some(Some(id)) = ids.get(index.index()) else {
+                return String::new();
+            };
+            let style = match trace.states.get(id) {
+                Some(Status::Success) => "style = filled, fillcolor = \"#28a745\"",
+                Some(Status::Failure) => "style = filled, fillcolor = \"#c0392b\"",
+                Some(Status::Running) => "style = filled, fillcolor = \"#d4a017\"",
+                None => "style = filled, fillcolor = \"#eeeeee\", color = \"#bbbbbb\", fontcolor = \"#999999\"",
+            };
+            match &tooltips[index.index()] {
+                tooltip if tooltip.is_empty() => style.to_string(),
+                tooltip => format!("{style}, {tooltip}"),
+            }
+        };
+        let digraph = Dot::with_attr_getters(&graph, &[Config::EdgeNoLabel], &|_, _| String::new(), &node_attrs);
+        format!("{:?}", digraph)
+    }
+
     pub(crate) fn get_graphviz_with_graph_instance(
-        &mut self,
+        &self,
     ) -> (String, petgraph::Graph<crate::visualizer::NodeType<A>, u32>) {
-        use crate::visualizer::NodeType;
         use petgraph::dot::{Config, Dot};
+
+        let graph = self.graphviz_graph();
+        let tooltips = crate::visualizer::graph_tooltips(&self.initial_behavior);
+        let node_attrs = |_, (index, _): (petgraph::graph::NodeIndex, _)| tooltips[index.index()].clone();
+        let digraph = Dot::with_attr_getters(&graph, &[Config::EdgeNoLabel], &|_, _| String::new(), &node_attrs);
+        (format!("{:?}", digraph), graph)
+    }
+
+    fn graphviz_graph(&self) -> petgraph::Graph<crate::visualizer::NodeType<A>, u32> {
+        use crate::visualizer::NodeType;
         use petgraph::Graph;
 
         let behavior = self.initial_behavior.to_owned();
@@ -235,9 +626,7 @@ impl<A: Clone + Debug, B: Debug> BT<A, B> {
         let root_id = graph.add_node(NodeType::Root);
 
         Self::dfs_recursive(&mut graph, behavior, root_id);
-
-        let digraph = Dot::with_config(&graph, &[Config::EdgeNoLabel]);
-        (format!("{:?}", digraph), graph)
+        graph
     }
 
     /// Compiles the behavior tree into a JSON string representing the static hierarchy.
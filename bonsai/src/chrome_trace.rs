@@ -0,0 +1,211 @@
+//! Export recorded ticks as [Chrome Trace Event] JSON, loadable in
+//! [Perfetto](https://ui.perfetto.dev) and `chrome://tracing`.
+//!
+//! Each tree becomes one track (a trace "thread") in a shared `bonsai`
+//! process. A node gets a span from the first tick it reports `Running` until
+//! the tick it returns `Success`/`Failure` — or stops being ticked because
+//! its parent halted it or finished. Spans nest like the tree: a child's span
+//! always lies within its parent's.
+//!
+//! The branches of a `WhenAll`, `WhenAny`, `Race` or `After` run at the same
+//! time, so their spans would overlap on one track without nesting. Each
+//! such branch gets a track of its own instead, named after the tree and the
+//! branch's first node, e.g. `navigation / "scan" #4`.
+//!
+//! Timestamps are microseconds since the Unix epoch, taken from the
+//! recording's start time plus each tick's offset, so tree activity lines up
+//! with other epoch-clocked traces. A tick's timestamp is when it finished,
+//! hence spans are quantized to tick boundaries.
+//!
+//! ```no_run
+//! use bonsai_bt::chrome_trace::ChromeTrace;
+//! use bonsai_bt::recording::Recording;
+//!
+//! let nav = Recording::load("nav.bonsai.jsonl")?;
+//! let arm = Recording::load("arm.bonsai.jsonl")?;
+//! ChromeTrace::new()
+//!     .add_tree("navigation", &nav)
+//!     .add_tree("manipulator", &arm)
+//!     .save("trees.trace.json")?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+//!
+//! [Chrome Trace Event]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::recording::Recording;
+use crate::Status;
+
+/// Process id shared by every bonsai track.
+const PID: u64 = 1;
+
+/// Builder for a Chrome trace-event file covering one or more trees.
+#[derive(Clone, Debug, Default)]
+pub struct ChromeTrace {
+    events: Vec<Value>,
+    tracks: u64,
+}
+
+/// What the trace needs to know about a node: its span name and type, and
+/// the track it goes on, counted from the tree's own track.
+struct NodeInfo {
+    name: String,
+    node_type: String,
+    lane: u64,
+}
+
+/// Node types whose children run at the same time.
+const PARALLEL: &[&str] = &["WhenAll", "WhenAny", "Race", "After"];
+
+impl ChromeTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `recording` as a new track called `name`, plus one more per
+    /// parallel branch.
+    pub fn add_tree(&mut self, name: &str, recording: &Recording) -> &mut Self {
+        if self.tracks == 0 {
+            self.events.push(json!({
+                "name": "process_name", "ph": "M", "pid": PID, "tid": 0,
+                "args": { "name": "bonsai" },
+            }));
+        }
+        let base = self.tracks + 1;
+
+        let mut nodes = BTreeMap::new();
+        let mut lanes = vec![name.to_string()];
+        collect_nodes(&recording.tree["root"], 0, false, &mut nodes, &mut |id, label| {
+            lanes.push(format!("{name} / {label} #{id}"));
+            lanes.len() as u64 - 1
+        });
+        for (lane, lane_name) in lanes.iter().enumerate() {
+            let tid = base + lane as u64;
+            self.events.push(json!({
+                "name": "thread_name", "ph": "M", "pid": PID, "tid": tid,
+                "args": { "name": lane_name },
+            }));
+            self.events.push(json!({
+                "name": "thread_sort_index", "ph": "M", "pid": PID, "tid": tid,
+                "args": { "sort_index": tid },
+            }));
+        }
+        self.tracks += lanes.len() as u64;
+        let tid_of = |id: &usize| base + nodes.get(id).map_or(0, |n: &NodeInfo| n.lane);
+
+        let origin_us = recording.start_unix_ms as f64 * 1000.0;
+        // Open spans, keyed by preorder id. Parallel branches have tracks of
+        // their own, so the open spans of one track are a chain of ancestors,
+        // and descendants have larger ids than their ancestors: closing in
+        // descending and opening in ascending id order keeps the B/E events
+        // of every track properly nested.
+        let mut open = BTreeSet::new();
+        let mut last_ts = origin_us;
+        for tick in &recording.ticks {
+            let ts = origin_us + tick.t * 1e6;
+            last_ts = ts;
+            let states = &tick.trace.states;
+
+            let closing: Vec<(usize, &str)> = open
+                .iter()
+                .rev()
+                .filter_map(|id| match states.get(id) {
+                    Some(Status::Running) => None,
+                    Some(Status::Success) => Some((*id, "success")),
+                    Some(Status::Failure) => Some((*id, "failure")),
+                    None => Some((*id, "halted")),
+                })
+                .collect();
+            for (id, status) in closing {
+                open.remove(&id);
+                self.events.push(end_event(tid_of(&id), ts, status));
+            }
+
+            let mut opening: Vec<usize> = states
+                .iter()
+                .filter(|(id, status)| **status == Status::Running && !open.contains(*id))
+                .map(|(id, _)| *id)
+                .collect();
+            opening.sort_unstable();
+            for id in opening {
+                open.insert(id);
+                self.events.push(begin_event(tid_of(&id), ts, id, nodes.get(&id)));
+            }
+        }
+        // Close whatever was still running when the recording ended.
+        for id in open.iter().rev() {
+            self.events.push(end_event(tid_of(id), last_ts, "running"));
+        }
+        self
+    }
+
+    /// The trace as a JSON object with a `traceEvents` array.
+    pub fn to_json(&self) -> Value {
+        json!({
+            "traceEvents": self.events,
+            "displayTimeUnit": "ms",
+        })
+    }
+
+    /// Write the trace JSON to `out`.
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        serde_json::to_writer(&mut out, &self.to_json())?;
+        out.flush()
+    }
+
+    /// Write the trace JSON to the file at `path`, created or truncated.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Flatten the serialized `TreeDefinition` into an id → node table, on
+/// track `lane` unless the node is a `parallel` branch, which gets a new one
+/// from `new_lane(id, name)`.
+fn collect_nodes(
+    node: &Value,
+    lane: u64,
+    parallel: bool,
+    out: &mut BTreeMap<usize, NodeInfo>,
+    new_lane: &mut impl FnMut(usize, &str) -> u64,
+) {
+    let Some(id) = node["id"].as_u64() else {
+        return;
+    };
+    let id = id as usize;
+    let node_type = node["node_type"].as_str().unwrap_or("Node").to_string();
+    let name = node["label"].as_str().map_or_else(|| node_type.clone(), str::to_string);
+    let lane = if parallel { new_lane(id, &name) } else { lane };
+    let parallel = PARALLEL.contains(&node_type.as_str());
+    out.insert(id, NodeInfo { name, node_type, lane });
+    if let Some(children) = node["children"].as_array() {
+        for child in children {
+            collect_nodes(child, lane, parallel, out, new_lane);
+        }
+    }
+}
+
+fn begin_event(tid: u64, ts: f64, id: usize, node: Option<&NodeInfo>) -> Value {
+    let (name, node_type) = node.map_or_else(
+        || (format!("#{id}"), "Node".to_string()),
+        |n| (n.name.clone(), n.node_type.clone()),
+    );
+    json!({
+        "name": name, "cat": "bonsai", "ph": "B", "ts": ts, "pid": PID, "tid": tid,
+        "args": { "id": id, "node_type": node_type },
+    })
+}
+
+fn end_event(tid: u64, ts: f64, status: &str) -> Value {
+    json!({
+        "ph": "E", "ts": ts, "pid": PID, "tid": tid,
+        "args": { "status": status },
+    })
+}
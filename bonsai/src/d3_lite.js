@@ -0,0 +1,261 @@
+// A minimal stand-in for the part of d3 v7 the visualizer page uses, inlined
+// into exported reports (`bonsai_bt::report`) so they render without network
+// access. Same names and call shapes as d3; the tree layout is simpler
+// (leaves evenly spaced, parents centered over their children).
+(function () {
+  'use strict';
+
+  const XHTML = 'http://www.w3.org/1999/xhtml';
+
+  class Selection {
+    constructor(nodes, parents) {
+      this._nodes = nodes;
+      this._parents = parents || [];
+    }
+    node() { return this._nodes[0] || null; }
+    datum() { const n = this.node(); return n ? n.__data__ : undefined; }
+    each(fn) {
+      this._nodes.forEach((el, i) => fn.call(el, el.__data__, i, this._nodes));
+      return this;
+    }
+    call(fn, ...args) { fn(this, ...args); return this; }
+    attr(name, value) {
+      return this.each(function (d, i) {
+        const v = typeof value === 'function' ? value.call(this, d, i) : value;
+        if (v === null || v === undefined) this.removeAttribute(name);
+        else this.setAttribute(name, String(v));
+      });
+    }
+    classed(names, value) {
+      return this.each(function (d, i) {
+        const on = typeof value === 'function' ? value.call(this, d, i) : value;
+        for (const name of names.split(/\s+/).filter(Boolean)) this.classList.toggle(name, !!on);
+      });
+    }
+    text(value) {
+      return this.each(function (d, i) {
+        const v = typeof value === 'function' ? value.call(this, d, i) : value;
+        this.textContent = v === null || v === undefined ? '' : String(v);
+      });
+    }
+    on(typename, listener) {
+      return this.each(function () {
+        const handlers = this.__on || (this.__on = {});
+        const type = typename.split('.')[0];
+        if (handlers[typename]) {
+          this.removeEventListener(type, handlers[typename]);
+          delete handlers[typename];
+        }
+        if (listener) {
+          const el = this;
+          handlers[typename] = (event) => listener.call(el, event, el.__data__);
+          this.addEventListener(type, handlers[typename], { passive: false });
+        }
+      });
+    }
+    append(name) {
+      return new Selection(this._nodes.map(parent => {
+        const child = parent.namespaceURI && parent.namespaceURI !== XHTML
+          ? document.createElementNS(parent.namespaceURI, name)
+          : document.createElement(name);
+        child.__data__ = parent.__data__;
+        return parent.appendChild(child);
+      }), this._parents);
+    }
+    select(selector) {
+      return new Selection(this._nodes.map(el => el.querySelector(selector)).filter(Boolean), this._parents);
+    }
+    selectAll(selector) {
+      const nodes = [];
+      for (const el of this._nodes) nodes.push(...el.querySelectorAll(selector));
+      return new Selection(nodes, this._nodes);
+    }
+    remove() {
+      return this.each(function () { this.remove(); });
+    }
+    // Index-keyed data join, as d3's default: `data(values).join(name)`.
+    data(values) {
+      const joined = new Selection(this._nodes, this._parents);
+      joined._data = Array.from(values);
+      return joined;
+    }
+    join(name) {
+      const values = this._data || [];
+      const parent = this._parents[0];
+      const nodes = [];
+      values.forEach((value, i) => {
+        let el = this._nodes[i];
+        if (!el && parent) el = new Selection([parent]).append(name).node();
+        if (el) {
+          el.__data__ = value;
+          nodes.push(el);
+        }
+      });
+      this._nodes.slice(values.length).forEach(el => el.remove());
+      return new Selection(nodes, this._parents);
+    }
+    // Reports have no animations: transitions apply at once.
+    transition() { return this; }
+    duration() { return this; }
+  }
+
+  function select(target) {
+    const el = typeof target === 'string' ? document.querySelector(target) : target;
+    return new Selection(el ? [el] : [], [document.documentElement]);
+  }
+
+  class Transform {
+    constructor(k, x, y) { this.k = k; this.x = x; this.y = y; }
+    toString() { return `translate(${this.x},${this.y}) scale(${this.k})`; }
+  }
+  const zoomIdentity = new Transform(1, 0, 0);
+
+  // `event`'s position in `el`'s user space (its viewBox, for an <svg>).
+  function pointer(event, el) {
+    const svg = el.ownerSVGElement || el;
+    if (svg.createSVGPoint && svg.getScreenCTM && svg.getScreenCTM()) {
+      const p = svg.createSVGPoint();
+      p.x = event.clientX;
+      p.y = event.clientY;
+      const q = p.matrixTransform(svg.getScreenCTM().inverse());
+      return [q.x, q.y];
+    }
+    const rect = el.getBoundingClientRect();
+    return [event.clientX - rect.left, event.clientY - rect.top];
+  }
+
+  // Wheel to zoom about the pointer, drag to pan, double-click to zoom in.
+  function zoom() {
+    let extent = [0, Infinity];
+    const listeners = {};
+    const clamp = k => Math.max(extent[0], Math.min(extent[1], k));
+
+    function emit(el, t) {
+      el.__zoom = t;
+      if (listeners.zoom) listeners.zoom.call(el, { transform: t });
+    }
+    function scaleAbout(el, k, p) {
+      const t = el.__zoom || zoomIdentity;
+      k = clamp(k);
+      emit(el, new Transform(k, p[0] - (p[0] - t.x) * k / t.k, p[1] - (p[1] - t.y) * k / t.k));
+    }
+
+    function behavior(selection) {
+      selection
+        .on('wheel.zoom', function (event) {
+          event.preventDefault();
+          const t = this.__zoom || zoomIdentity;
+          const step = event.deltaMode === 1 ? 0.05 : event.deltaMode ? 1 : 0.002;
+          const k = t.k * Math.pow(2, -event.deltaY * step * (event.ctrlKey ? 10 : 1));
+          scaleAbout(this, k, pointer(event, this));
+        })
+        .on('pointerdown.zoom', function (event) {
+          if (event.button !== 0) return;
+          const el = this;
+          const start = pointer(event, el);
+          const from = el.__zoom || zoomIdentity;
+          el.setPointerCapture(event.pointerId);
+          const move = (e) => {
+            const p = pointer(e, el);
+            emit(el, new Transform(from.k, from.x + p[0] - start[0], from.y + p[1] - start[1]));
+          };
+          const end = () => {
+            el.removeEventListener('pointermove', move);
+            el.removeEventListener('pointerup', end);
+            el.removeEventListener('pointercancel', end);
+          };
+          el.addEventListener('pointermove', move);
+          el.addEventListener('pointerup', end);
+          el.addEventListener('pointercancel', end);
+        })
+        .on('dblclick.zoom', function (event) {
+          const t = this.__zoom || zoomIdentity;
+          scaleAbout(this, t.k * (event.shiftKey ? 0.5 : 2), pointer(event, this));
+        });
+    }
+    behavior.scaleExtent = (e) => { extent = e; return behavior; };
+    behavior.on = (type, listener) => { listeners[type.split('.')[0]] = listener; return behavior; };
+    behavior.transform = (selection, t) => { selection.each(function () { emit(this, t); }); };
+    return behavior;
+  }
+
+  class Node {
+    constructor(data, parent) {
+      this.data = data;
+      this.parent = parent;
+      this.depth = parent ? parent.depth + 1 : 0;
+    }
+    // Breadth-first, like d3.
+    descendants() {
+      const out = [];
+      for (let queue = [this]; queue.length;) {
+        const node = queue.shift();
+        out.push(node);
+        if (node.children) queue.push(...node.children);
+      }
+      return out;
+    }
+    links() {
+      return this.descendants().slice(1).map(target => ({ source: target.parent, target }));
+    }
+  }
+
+  function hierarchy(data, children) {
+    const build = (d, parent) => {
+      const node = new Node(d, parent);
+      const kids = children(d);
+      if (kids && kids.length) node.children = Array.from(kids, k => build(k, node));
+      return node;
+    };
+    return build(data, null);
+  }
+
+  // Lay out `root` with depth along y and siblings along x, `dx` apart.
+  function tree() {
+    let size = [1, 1];
+    function layout(root) {
+      let next = 0;
+      (function place(node) {
+        node.y = node.depth * size[1];
+        if (node.children) {
+          node.children.forEach(place);
+          node.x = (node.children[0].x + node.children[node.children.length - 1].x) / 2;
+        } else {
+          node.x = next;
+          next += size[0];
+        }
+      })(root);
+      const shift = root.x;
+      for (const node of root.descendants()) node.x -= shift;
+      return root;
+    }
+    layout.nodeSize = (s) => { size = s; return layout; };
+    return layout;
+  }
+
+  function linkHorizontal() {
+    let x = d => d[0];
+    let y = d => d[1];
+    function link(d) {
+      const [x0, y0, x1, y1] = [x(d.source), y(d.source), x(d.target), y(d.target)];
+      const mx = (x0 + x1) / 2;
+      return `M${x0},${y0}C${mx},${y0},${mx},${y1},${x1},${y1}`;
+    }
+    link.x = (f) => { x = f; return link; };
+    link.y = (f) => { y = f; return link; };
+    return link;
+  }
+
+  // ColorBrewer's YlOrRd, interpolated linearly in RGB.
+  const YL_OR_RD = ['ffffcc', 'ffeda0', 'fed976', 'feb24c', 'fd8d3c', 'fc4e2a', 'e31a1c', 'bd0026', '800026']
+    .map(hex => [0, 2, 4].map(i => parseInt(hex.slice(i, i + 2), 16)));
+  function interpolateYlOrRd(t) {
+    const at = Math.max(0, Math.min(1, t)) * (YL_OR_RD.length - 1);
+    const i = Math.min(Math.floor(at), YL_OR_RD.length - 2);
+    const f = at - i;
+    const [r, g, b] = YL_OR_RD[i].map((c, j) => Math.round(c + (YL_OR_RD[i + 1][j] - c) * f));
+    return `rgb(${r}, ${g}, ${b})`;
+  }
+
+  window.d3 = { select, zoom, zoomIdentity, hierarchy, tree, linkHorizontal, interpolateYlOrRd };
+})();
@@ -0,0 +1,308 @@
+//! Pause, single-step and breakpoints for a [`BT`](crate::BT), driven from
+//! the web visualizer or from Rust.
+//!
+//! Get the tree's [`Debugger`] with [`BT::debugger`](crate::BT::debugger).
+//! It is a shared handle: the visualizer's control channel, the tick path
+//! and any clones you hold (e.g. on another thread) all see one state.
+//!
+//! While paused, [`tick`](crate::BT::tick) does not advance the tree. What it
+//! does instead depends on the [`PauseMode`]: return at once
+//! ([`PauseMode::Skip`], the default, so a game loop keeps spinning) or block
+//! until resumed or stepped ([`PauseMode::Block`]).
+//!
+//! ```
+//! use bonsai_bt::debugger::Breakpoint;
+//! use bonsai_bt::{Action, Event, Failure, Running, Sequence, Success, UpdateArgs, BT};
+//!
+//! let mut bt = BT::new(Sequence(vec![Action(1), Action(2)]), ());
+//! let debugger = bt.debugger();
+//! debugger.add_breakpoint(Breakpoint::on_status(2, Failure));
+//!
+//! let e: Event = UpdateArgs { dt: 0.1 }.into();
+//! bt.tick(&e, &mut |args, _| if *args.action == 1 { (Success, 0.0) } else { (Running, 0.0) });
+//! assert!(!debugger.is_paused());
+//!
+//! // Node 2 fails: the breakpoint pauses the tree before the next tick.
+//! bt.reset_bt();
+//! bt.tick(&e, &mut |args, _| if *args.action == 1 { (Success, 0.0) } else { (Failure, 0.0) });
+//! assert!(debugger.is_paused());
+//! assert_eq!(debugger.last_hit().unwrap().node, 2);
+//! ```
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use serde::{Deserialize, Serialize};
+
+use crate::path::NodeIndex;
+use crate::wire::StatusVec;
+use crate::Status;
+
+/// What [`tick`](crate::BT::tick) does while the tree is paused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseMode {
+    /// Return immediately with `Some((Running, dt))` — the tree is still
+    /// running and none of `dt` was consumed — without ticking any node.
+    #[default]
+    Skip,
+    /// Block the ticking thread until the tree is resumed or stepped.
+    Block,
+}
+
+/// Pause when node `node` returns `status` (or any status, if `None`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    /// Preorder node id, as in the visualizer and `TreeDefinition`.
+    pub node: usize,
+    #[serde(default)]
+    pub status: Option<Status>,
+}
+
+impl Breakpoint {
+    /// Break whenever `node` is ticked.
+    pub fn on_visit(node: usize) -> Self {
+        Self { node, status: None }
+    }
+
+    /// Break when `node` returns `status`.
+    pub fn on_status(node: usize, status: Status) -> Self {
+        Self {
+            node,
+            status: Some(status),
+        }
+    }
+
+    fn matches(&self, states: &StatusVec) -> Option<Status> {
+        let got = states.get(self.node)?;
+        match self.status {
+            Some(want) if want != got => None,
+            _ => Some(got),
+        }
+    }
+}
+
+/// The breakpoint that paused the tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct BreakpointHit {
+    /// The tick in which the breakpoint matched.
+    pub tick_id: u64,
+    pub node: usize,
+    /// The status `node` returned in that tick.
+    pub status: Status,
+}
+
+/// Snapshot of the debugger state, as shown in the visualizer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DebugStatus {
+    pub paused: bool,
+    pub mode: PauseMode,
+    pub breakpoints: Vec<Breakpoint>,
+    pub last_hit: Option<BreakpointHit>,
+    /// Id of the last tick that ran.
+    pub tick_id: u64,
+}
+
+/// A command on the visualizer's control channel: a JSON text frame such
+/// as `{"cmd":"pause"}` or
+/// `{"cmd":"set_breakpoints","breakpoints":[{"node":17,"status":"failure"}]}`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum DebugCommand {
+    Pause,
+    Resume,
+    Step,
+    SetMode { mode: PauseMode },
+    SetBreakpoints { breakpoints: Vec<Breakpoint> },
+}
+
+#[derive(Debug, Default)]
+struct State {
+    paused: bool,
+    mode: PauseMode,
+    /// Ticks allowed to run while paused.
+    steps: u64,
+    breakpoints: Vec<Breakpoint>,
+    last_hit: Option<BreakpointHit>,
+    tick_id: u64,
+    /// Bumped on every change, so observers can poll for updates.
+    version: u64,
+}
+
+/// Shared pause/step/breakpoint state of one tree. Cheap to clone; clones
+/// share state.
+#[derive(Clone, Debug, Default)]
+pub struct Debugger {
+    inner: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop advancing the tree from the next tick on.
+    pub fn pause(&self) {
+        self.apply(DebugCommand::Pause);
+    }
+
+    /// Advance normally again. Clears the last breakpoint hit.
+    pub fn resume(&self) {
+        self.apply(DebugCommand::Resume);
+    }
+
+    /// Let exactly one more tick run, then stay paused. Pauses first if the
+    /// tree is running.
+    pub fn step(&self) {
+        self.apply(DebugCommand::Step);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused
+    }
+
+    pub fn set_pause_mode(&self, mode: PauseMode) {
+        self.apply(DebugCommand::SetMode { mode });
+    }
+
+    pub fn pause_mode(&self) -> PauseMode {
+        self.lock().mode
+    }
+
+    /// Add `breakpoint`, unless an identical one is already set.
+    pub fn add_breakpoint(&self, breakpoint: Breakpoint) {
+        self.update(|s| {
+            if !s.breakpoints.contains(&breakpoint) {
+                s.breakpoints.push(breakpoint);
+            }
+        });
+    }
+
+    /// Remove every breakpoint on `node`.
+    pub fn remove_breakpoints(&self, node: usize) {
+        self.update(|s| s.breakpoints.retain(|b| b.node != node));
+    }
+
+    /// Move every breakpoint set on the tree of `from` to the node at the
+    /// same [`NodePath`](crate::path::NodePath) in the tree of `to`, dropping
+    /// those whose node is gone. See [`NodeIndex::translate`].
+    pub fn remap_breakpoints(&self, from: &NodeIndex, to: &NodeIndex) {
+        // Every update is broadcast to the visualizer; skip the no-op one.
+        if self.lock().breakpoints.is_empty() {
+            return;
+        }
+        self.update(|s| {
+            let breakpoints = std::mem::take(&mut s.breakpoints);
+            for breakpoint in breakpoints {
+                if let Some(node) = from.translate(breakpoint.node, to) {
+                    let breakpoint = Breakpoint { node, ..breakpoint };
+                    if !s.breakpoints.contains(&breakpoint) {
+                        s.breakpoints.push(breakpoint);
+                    }
+                }
+            }
+        });
+    }
+
+    pub fn clear_breakpoints(&self) {
+        self.update(|s| s.breakpoints.clear());
+    }
+
+    pub fn breakpoints(&self) -> Vec<Breakpoint> {
+        self.lock().breakpoints.clone()
+    }
+
+    /// The breakpoint that caused the current pause, if any.
+    pub fn last_hit(&self) -> Option<BreakpointHit> {
+        self.lock().last_hit
+    }
+
+    pub fn status(&self) -> DebugStatus {
+        let s = self.lock();
+        DebugStatus {
+            paused: s.paused,
+            mode: s.mode,
+            breakpoints: s.breakpoints.clone(),
+            last_hit: s.last_hit,
+            tick_id: s.tick_id,
+        }
+    }
+
+    /// Apply a control-channel command.
+    pub fn apply(&self, command: DebugCommand) {
+        self.update(|s| match command {
+            DebugCommand::Pause => s.paused = true,
+            DebugCommand::Resume => {
+                s.paused = false;
+                s.steps = 0;
+                s.last_hit = None;
+            }
+            DebugCommand::Step => {
+                s.paused = true;
+                s.steps += 1;
+            }
+            DebugCommand::SetMode { mode } => s.mode = mode,
+            DebugCommand::SetBreakpoints { breakpoints } => s.breakpoints = breakpoints,
+        });
+    }
+
+    /// Change counter, bumped by every state change.
+    pub(crate) fn version(&self) -> u64 {
+        self.lock().version
+    }
+
+    /// Called before each tick: whether the tick may run. Consumes a step
+    /// while paused; in [`PauseMode::Block`] waits for one (or a resume).
+    pub(crate) fn gate(&self) -> bool {
+        let (_, wake) = &*self.inner;
+        let mut s = self.lock();
+        loop {
+            if !s.paused {
+                return true;
+            }
+            if s.steps > 0 {
+                s.steps -= 1;
+                s.version += 1;
+                return true;
+            }
+            if s.mode == PauseMode::Skip {
+                return false;
+            }
+            s = wake.wait(s).unwrap_or_else(|p| p.into_inner());
+        }
+    }
+
+    /// Called after each tick with its statuses: records the tick and pauses
+    /// if a breakpoint matches.
+    pub(crate) fn observe(&self, tick_id: u64, states: &StatusVec) {
+        let mut s = self.lock();
+        s.tick_id = tick_id;
+        let hit = s.breakpoints.iter().find_map(|b| {
+            b.matches(states).map(|status| BreakpointHit {
+                tick_id,
+                node: b.node,
+                status,
+            })
+        });
+        if let Some(hit) = hit {
+            s.paused = true;
+            s.steps = 0;
+            s.last_hit = Some(hit);
+            // Only hits bump the version: observers needn't hear of every tick.
+            s.version += 1;
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut State)) {
+        let (_, wake) = &*self.inner;
+        let mut s = self.lock();
+        f(&mut s);
+        s.version += 1;
+        drop(s);
+        wake.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.inner.0.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
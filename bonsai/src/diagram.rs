@@ -0,0 +1,282 @@
+//! Text-diagram exporters for [`Behavior`]: [Mermaid] flowcharts and
+//! [PlantUML] diagrams, for docs platforms that render those instead of DOT.
+//!
+//! Nodes are numbered in preorder like everywhere else (`n0` is the root), so
+//! the statuses of a [`TickTrace`] can color them. Shapes follow the node
+//! type:
+//!
+//! | Node type                                          | Mermaid    | PlantUML    |
+//! |----------------------------------------------------|------------|-------------|
+//! | `Action`                                           | rectangle  | `rectangle` |
+//! | `Wait`, `WaitForever`                              | stadium    | `storage`   |
+//! | `Sequence`, `Select`, `When*`, `After`, `Race`     | subroutine | `node`      |
+//! | `Invert`, `AlwaysSucceed`                          | hexagon    | `hexagon`   |
+//! | `If`, `While`, `WhileAll`                          | rhombus    | `usecase`   |
+//!
+//! Edges out of `If` are labelled `cond`, `then` and `else`; edges out of
+//! `While`/`WhileAll` are labelled `cond` and `body`.
+//!
+//! [Mermaid]: https://mermaid.js.org/syntax/flowchart.html
+//! [PlantUML]: https://plantuml.com/deployment-diagram
+
+use std::fmt::{Debug, Write as _};
+
+use crate::tracer::{children_of, display_label, TickTrace};
+use crate::{Behavior, Status};
+
+/// Fill and stroke per status, as in the web visualizer.
+fn status_colors(status: Status) -> (&'static str, &'static str) {
+    match status {
+        Status::Running => ("#d4a017", "#f1c40f"),
+        Status::Success => ("#28a745", "#5cd271"),
+        Status::Failure => ("#c0392b", "#e74c3c"),
+    }
+}
+
+fn status_class(status: Status) -> &'static str {
+    match status {
+        Status::Running => "running",
+        Status::Success => "success",
+        Status::Failure => "failure",
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Shape {
+    Leaf,
+    Wait,
+    Composite,
+    Decorator,
+    Conditional,
+}
+
+impl Shape {
+    pub(crate) fn of<A>(behavior: &Behavior<A>) -> Self {
+        use Behavior::*;
+        match behavior {
+            Action(_) => Shape::Leaf,
+            Wait(_) | WaitForever => Shape::Wait,
+            Invert(_) | AlwaysSucceed(_) => Shape::Decorator,
+            If(..) | While(..) | WhileAll(..) => Shape::Conditional,
+            Select(_)
+            | Sequence(_)
+            | MemorylessSequence(_)
+            | MemorylessSelector(_)
+            | WhenAll(_)
+            | WhenAny(_)
+            | After(_)
+            | Race(_) => Shape::Composite,
+            Named(_, behavior) | Meta(_, behavior) => Shape::of(behavior),
+        }
+    }
+
+    pub(crate) fn mermaid(self) -> (&'static str, &'static str) {
+        match self {
+            Shape::Leaf => ("[", "]"),
+            Shape::Wait => ("([", "])"),
+            Shape::Composite => ("[[", "]]"),
+            Shape::Decorator => ("{{", "}}"),
+            Shape::Conditional => ("{", "}"),
+        }
+    }
+
+    fn plantuml(self) -> &'static str {
+        match self {
+            Shape::Leaf => "rectangle",
+            Shape::Wait => "storage",
+            Shape::Composite => "node",
+            Shape::Decorator => "hexagon",
+            Shape::Conditional => "usecase",
+        }
+    }
+}
+
+/// One node of the flattened tree.
+struct Node {
+    id: usize,
+    shape: Shape,
+    label: String,
+    /// Parent id and the label of the edge from it.
+    parent: Option<(usize, Option<&'static str>)>,
+}
+
+/// Flatten `behavior` in preorder, the id order of `TreeDefinition`.
+fn flatten<A: Debug>(behavior: &Behavior<A>, parent: Option<(usize, Option<&'static str>)>, out: &mut Vec<Node>) {
+    let id = out.len();
+    out.push(Node {
+        id,
+        shape: Shape::of(behavior),
+        label: display_label(behavior),
+        parent,
+    });
+    for (i, child) in children_of(behavior).into_iter().enumerate() {
+        flatten(child, Some((id, edge_label(behavior, i))), out);
+    }
+}
+
+/// The label of the edge from `parent` to its `i`th child, if it has one.
+pub(crate) fn edge_label<A>(parent: &Behavior<A>, i: usize) -> Option<&'static str> {
+    match parent.unnamed() {
+        Behavior::If(..) => Some(["cond", "then", "else"][i]),
+        Behavior::While(..) | Behavior::WhileAll(..) => Some(if i == 0 { "cond" } else { "body" }),
+        _ => None,
+    }
+}
+
+impl<A: Debug> Behavior<A> {
+    /// The tree as a Mermaid flowchart, top to bottom. See the
+    /// [module docs](crate::diagram) for the node shapes.
+    ///
+    /// ```
+    /// use bonsai_bt::{Action, Sequence};
+    ///
+    /// let chart = Sequence(vec![Action("a"), Action("b")]).to_mermaid();
+    /// assert!(chart.starts_with("flowchart TD\n"));
+    /// assert!(chart.contains("n0 --> n1"));
+    /// ```
+    pub fn to_mermaid(&self) -> String {
+        self.render_mermaid(None)
+    }
+
+    /// Like [`to_mermaid`](Self::to_mermaid), with every node `trace` has a
+    /// status for colored by that status.
+    pub fn to_mermaid_with_trace(&self, trace: &TickTrace) -> String {
+        self.render_mermaid(Some(trace))
+    }
+
+    /// The tree as a PlantUML diagram. See the [module docs](crate::diagram)
+    /// for the node shapes.
+    ///
+    /// ```
+    /// use bonsai_bt::{Action, Invert};
+    ///
+    /// let uml = Invert(Box::new(Action("a"))).to_plantuml();
+    /// assert!(uml.starts_with("@startuml\n"));
+    /// assert!(uml.contains("n0 --> n1"));
+    /// ```
+    pub fn to_plantuml(&self) -> String {
+        self.render_plantuml(None)
+    }
+
+    /// Like [`to_plantuml`](Self::to_plantuml), with every node `trace` has a
+    /// status for colored by that status.
+    pub fn to_plantuml_with_trace(&self, trace: &TickTrace) -> String {
+        self.render_plantuml(Some(trace))
+    }
+
+    fn render_mermaid(&self, trace: Option<&TickTrace>) -> String {
+        let mut nodes = Vec::new();
+        flatten(self, None, &mut nodes);
+
+        let mut out = String::from("flowchart TD\n");
+        for node in &nodes {
+            let (open, close) = node.shape.mermaid();
+            let _ = writeln!(out, "    n{}{open}\"{}\"{close}", node.id, mermaid_escape(&node.label));
+        }
+        for node in &nodes {
+            match node.parent {
+                Some((parent, Some(edge))) => {
+                    let _ = writeln!(out, "    n{parent} -->|{edge}| n{}", node.id);
+                }
+                Some((parent, None)) => {
+                    let _ = writeln!(out, "    n{parent} --> n{}", node.id);
+                }
+                None => {}
+            }
+        }
+        if let Some(trace) = trace {
+            for status in [Status::Running, Status::Success, Status::Failure] {
+                let ids: Vec<String> = nodes
+                    .iter()
+                    .filter(|n| trace.states.get(&n.id) == Some(&status))
+                    .map(|n| format!("n{}", n.id))
+                    .collect();
+                if ids.is_empty() {
+                    continue;
+                }
+                let (fill, stroke) = status_colors(status);
+                let class = status_class(status);
+                let _ = writeln!(out, "    classDef {class} fill:{fill},stroke:{stroke},color:#fff");
+                let _ = writeln!(out, "    class {} {class}", ids.join(","));
+            }
+        }
+        out
+    }
+
+    fn render_plantuml(&self, trace: Option<&TickTrace>) -> String {
+        let mut nodes = Vec::new();
+        flatten(self, None, &mut nodes);
+
+        let mut out = String::from("@startuml\n");
+        for node in &nodes {
+            let color = trace
+                .and_then(|t| t.states.get(&node.id))
+                .map(|s| format!(" {}", status_colors(*s).0))
+                .unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{} \"{}\" as n{}{color}",
+                node.shape.plantuml(),
+                plantuml_escape(&node.label),
+                node.id
+            );
+        }
+        for node in &nodes {
+            match node.parent {
+                Some((parent, Some(edge))) => {
+                    let _ = writeln!(out, "n{parent} --> n{} : {edge}", node.id);
+                }
+                Some((parent, None)) => {
+                    let _ = writeln!(out, "n{parent} --> n{}", node.id);
+                }
+                None => {}
+            }
+        }
+        out.push_str("@enduml\n");
+        out
+    }
+}
+
+/// Mermaid labels are quoted; quotes and markup use its `#…;` entity codes.
+pub(crate) fn mermaid_escape(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '#' => out.push_str("#35;"),
+            '"' => out.push_str("#quot;"),
+            '<' => out.push_str("#lt;"),
+            '>' => out.push_str("#gt;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// DOT quoted strings escape `"` and `\`; newlines become `\n`.
+pub(crate) fn dot_escape(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// PlantUML has no escape inside quoted names; quotes and creole markup use
+/// its `<U+XXXX>` code points instead.
+fn plantuml_escape(label: &str) -> String {
+    let mut out = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => out.push_str("<U+0022>"),
+            '<' => out.push_str("<U+003C>"),
+            '>' => out.push_str("<U+003E>"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,634 @@
+//! Structural diff between two trees, for reviewing edits to a tree file
+//! when a textual diff of its JSON is unreadable.
+//!
+//! [`diff`] lines up the children of every pair of matching nodes, keeping
+//! their order where it can, and reports what differs as a list of
+//! [`Edit`]s: subtrees inserted, removed or moved, and nodes modified in
+//! place (a changed action, duration, name, metadata or node type). Nodes are
+//! addressed by their path of child indices from the root, `/` being the
+//! root and `/1/0` the first child of its second child; the children of
+//! `If` are its condition, `then` and `else` branches, and those of `While`
+//! its condition then its body.
+//!
+//! [`TreeDiff`] prints one edit per line, and renders the new tree with the
+//! removed subtrees added back as a [DOT](TreeDiff::to_dot) or
+//! [Mermaid](TreeDiff::to_mermaid) graph:
+//!
+//! | Edit       | Color  | Line    |
+//! |------------|--------|---------|
+//! | inserted   | green  | solid   |
+//! | removed    | red    | dashed  |
+//! | modified   | yellow | solid   |
+//! | moved      | blue   | solid   |
+//!
+//! ```
+//! use bonsai_bt::diff::diff;
+//! use bonsai_bt::{Action, Sequence, Wait};
+//!
+//! let old = Sequence(vec![Action("open"), Wait(1.0), Action("enter")]);
+//! let new = Sequence(vec![Action("unlock"), Action("open"), Action("enter")]);
+//! assert_eq!(
+//!     diff(&old, &new).to_string(),
+//!     "+ /0 \"unlock\"\n- /1 Wait(1.00s)\n"
+//! );
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Write as _};
+use std::mem::discriminant;
+
+use crate::diagram::{dot_escape, edge_label, mermaid_escape, Shape};
+use crate::tracer::{children_of, display_label};
+use crate::Behavior;
+
+/// One difference between two trees. Paths index children from the root;
+/// `from` paths are in the old tree, `to` paths in the new one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Edit {
+    /// A subtree of `size` nodes that only the new tree has.
+    Inserted { to: Vec<usize>, node: String, size: usize },
+    /// A subtree of `size` nodes that only the old tree has.
+    Removed {
+        from: Vec<usize>,
+        node: String,
+        size: usize,
+    },
+    /// A subtree now at another place: out of order among its siblings, or
+    /// unchanged under another parent. Edits inside it are listed too.
+    Moved {
+        from: Vec<usize>,
+        to: Vec<usize>,
+        node: String,
+    },
+    /// A node whose action, duration or type changed. Its children are
+    /// diffed in turn.
+    Modified {
+        from: Vec<usize>,
+        to: Vec<usize>,
+        old: String,
+        new: String,
+    },
+}
+
+/// The result of [`diff`].
+#[derive(Clone, Debug)]
+pub struct TreeDiff {
+    edits: Vec<Edit>,
+    /// The new tree with the removed subtrees, in preorder of the former.
+    graph: Vec<GraphNode>,
+}
+
+/// What happened to a node of the rendered graph.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Change {
+    Same,
+    Inserted,
+    Removed,
+    Modified,
+    Moved,
+}
+
+#[derive(Clone, Debug)]
+struct GraphNode {
+    /// `n<id>` for a node of the new tree, `r<id>` for one of the old tree.
+    key: String,
+    label: String,
+    shape: Shape,
+    change: Change,
+    /// Parent key and the label of the edge from it.
+    parent: Option<(String, Option<&'static str>)>,
+}
+
+/// Compute the edits that turn `old` into `new`.
+pub fn diff<A: Debug + PartialEq>(old: &Behavior<A>, new: &Behavior<A>) -> TreeDiff {
+    let mut differ = Differ {
+        old: flatten(old),
+        new: flatten(new),
+        old_to_new: Vec::new(),
+        changes: Vec::new(),
+        pending: Vec::new(),
+    };
+    differ.old_to_new = vec![None; differ.old.len()];
+    differ.changes = vec![Change::Same; differ.new.len()];
+    differ.pair(0, 0);
+    differ.find_moves();
+    differ.finish()
+}
+
+impl TreeDiff {
+    /// The edits, in the order their nodes appear in the trees.
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    /// Whether the trees are equal.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// The new tree as a graphviz digraph, with the removed subtrees added
+    /// back and every edit highlighted.
+    pub fn to_dot(&self) -> String {
+        let mut out =
+            String::from("digraph {\n    node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\"]\n");
+        for node in &self.graph {
+            let attrs = match node.change {
+                Change::Same => String::new(),
+                Change::Removed => format!(
+                    ", fillcolor=\"{}\", fontcolor=\"#fff\", style=\"rounded,filled,dashed\"",
+                    color(node.change)
+                ),
+                change => format!(", fillcolor=\"{}\", fontcolor=\"#fff\"", color(change)),
+            };
+            let _ = writeln!(out, "    {} [label=\"{}\"{attrs}]", node.key, dot_escape(&node.label));
+        }
+        for node in &self.graph {
+            let Some((parent, edge)) = &node.parent else {
+                continue;
+            };
+            let mut attrs = Vec::new();
+            if let Some(edge) = edge {
+                attrs.push(format!("label=\"{edge}\""));
+            }
+            if node.change == Change::Removed {
+                attrs.push("style=dashed".to_string());
+            }
+            if attrs.is_empty() {
+                let _ = writeln!(out, "    {parent} -> {}", node.key);
+            } else {
+                let _ = writeln!(out, "    {parent} -> {} [{}]", node.key, attrs.join(", "));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The new tree as a Mermaid flowchart, with the removed subtrees added
+    /// back and every edit highlighted. Node shapes are those of
+    /// [`Behavior::to_mermaid`].
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart TD\n");
+        for node in &self.graph {
+            let (open, close) = node.shape.mermaid();
+            let _ = writeln!(out, "    {}{open}\"{}\"{close}", node.key, mermaid_escape(&node.label));
+        }
+        for node in &self.graph {
+            let Some((parent, edge)) = &node.parent else {
+                continue;
+            };
+            let arrow = if node.change == Change::Removed { "-.->" } else { "-->" };
+            match edge {
+                Some(edge) => {
+                    let _ = writeln!(out, "    {parent} {arrow}|{edge}| {}", node.key);
+                }
+                None => {
+                    let _ = writeln!(out, "    {parent} {arrow} {}", node.key);
+                }
+            }
+        }
+        for change in [Change::Inserted, Change::Removed, Change::Modified, Change::Moved] {
+            let keys: Vec<&str> = self
+                .graph
+                .iter()
+                .filter(|n| n.change == change)
+                .map(|n| n.key.as_str())
+                .collect();
+            if keys.is_empty() {
+                continue;
+            }
+            let class = format!("{change:?}").to_lowercase();
+            let dash = if change == Change::Removed {
+                ",stroke-dasharray:4"
+            } else {
+                ""
+            };
+            let _ = writeln!(out, "    classDef {class} fill:{},color:#fff{dash}", color(change));
+            let _ = writeln!(out, "    class {} {class}", keys.join(","));
+        }
+        out
+    }
+}
+
+impl fmt::Display for TreeDiff {
+    /// One edit per line.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for edit in &self.edits {
+            writeln!(f, "{edit}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Edit {
+    /// `+ /path node`, `- /path node`, `> /from -> /to node` or
+    /// `~ /path old -> new`, with the size of subtrees of several nodes.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Edit::Inserted { to, node, size } => write!(f, "+ {} {node}{}", Path(to), Size(*size)),
+            Edit::Removed { from, node, size } => write!(f, "- {} {node}{}", Path(from), Size(*size)),
+            Edit::Moved { from, to, node } => write!(f, "> {} -> {} {node}", Path(from), Path(to)),
+            Edit::Modified { from, to, old, new } if from == to => write!(f, "~ {} {old} -> {new}", Path(to)),
+            Edit::Modified { from, to, old, new } => {
+                write!(f, "~ {} (was {}) {old} -> {new}", Path(to), Path(from))
+            }
+        }
+    }
+}
+
+struct Path<'a>(&'a [usize]);
+
+impl fmt::Display for Path<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("/");
+        }
+        for index in self.0 {
+            write!(f, "/{index}")?;
+        }
+        Ok(())
+    }
+}
+
+struct Size(usize);
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            1 => Ok(()),
+            n => write!(f, " ({n} nodes)"),
+        }
+    }
+}
+
+/// One node of a flattened tree; ids are preorder, so a subtree is the id
+/// range `id..id + size`.
+struct Flat<'a, A> {
+    behavior: &'a Behavior<A>,
+    label: String,
+    path: Vec<usize>,
+    parent: Option<usize>,
+    edge: Option<&'static str>,
+    children: Vec<usize>,
+    size: usize,
+}
+
+fn flatten<A: Debug>(root: &Behavior<A>) -> Vec<Flat<'_, A>> {
+    fn walk<'a, A: Debug>(
+        behavior: &'a Behavior<A>,
+        parent: Option<usize>,
+        edge: Option<&'static str>,
+        path: Vec<usize>,
+        out: &mut Vec<Flat<'a, A>>,
+    ) -> usize {
+        let id = out.len();
+        out.push(Flat {
+            behavior,
+            label: display_label(behavior),
+            path: path.clone(),
+            parent,
+            edge,
+            children: Vec::new(),
+            size: 0,
+        });
+        for (i, child) in children_of(behavior).into_iter().enumerate() {
+            let mut child_path = path.clone();
+            child_path.push(i);
+            let child_id = walk(child, Some(id), edge_label(behavior, i), child_path, out);
+            out[id].children.push(child_id);
+        }
+        out[id].size = out.len() - id;
+        id
+    }
+    let mut out = Vec::new();
+    walk(root, None, None, Vec::new(), &mut out);
+    out
+}
+
+/// An edit by node ids, before moves are told apart.
+enum Pending {
+    Inserted(usize),
+    Removed(usize),
+    Modified(usize, usize),
+    Moved(usize, usize),
+}
+
+struct Differ<'a, A> {
+    old: Vec<Flat<'a, A>>,
+    new: Vec<Flat<'a, A>>,
+    /// The new node each old node was matched to.
+    old_to_new: Vec<Option<usize>>,
+    /// By new id.
+    changes: Vec<Change>,
+    pending: Vec<Pending>,
+}
+
+impl<A: Debug + PartialEq> Differ<'_, A> {
+    /// Match old node `o` to new node `n` and diff what lies below.
+    fn pair(&mut self, o: usize, n: usize) {
+        let (old, new) = (self.old[o].behavior, self.new[n].behavior);
+        if old == new {
+            self.map_subtree(o, n);
+            return;
+        }
+        self.old_to_new[o] = Some(n);
+        if !same_node(old, new) {
+            self.changes[n] = Change::Modified;
+            self.pending.push(Pending::Modified(o, n));
+        }
+        let old_children = self.old[o].children.clone();
+        let new_children = self.new[n].children.clone();
+        let anchors = self.common_children(&old_children, &new_children);
+        let (mut i, mut j) = (0, 0);
+        for (ai, aj) in anchors.into_iter().chain([(old_children.len(), new_children.len())]) {
+            self.pair_gap(&old_children[i..ai], &new_children[j..aj]);
+            if ai < old_children.len() {
+                self.map_subtree(old_children[ai], new_children[aj]);
+            }
+            (i, j) = (ai + 1, aj + 1);
+        }
+    }
+
+    /// Between two unchanged children: pair the remaining children that
+    /// look alike, report pairs out of order as moved, and the rest as
+    /// removed or inserted.
+    fn pair_gap(&mut self, old: &[usize], new: &[usize]) {
+        let kind = |flat: &Flat<'_, A>| (discriminant(flat.behavior.unnamed()), flat.children.is_empty());
+        let mut old_paired = vec![false; old.len()];
+        let mut new_paired = vec![false; new.len()];
+        let mut pairs = Vec::new();
+        // Composites pair with the most alike composite of their type...
+        let mut candidates = Vec::new();
+        for (i, &o) in old
+            .iter()
+            .enumerate()
+            .filter(|(_, &o)| !self.old[o].children.is_empty())
+        {
+            for (j, &n) in new.iter().enumerate() {
+                if kind(&self.old[o]) == kind(&self.new[n]) {
+                    let score = self.similarity(o, n);
+                    if score >= 0.5 {
+                        candidates.push((score, i, j));
+                    }
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        for (_, i, j) in candidates {
+            if !old_paired[i] && !new_paired[j] {
+                (old_paired[i], new_paired[j]) = (true, true);
+                pairs.push((i, j));
+            }
+        }
+        // ...and leaves with the next leaf of their type.
+        let mut next = 0;
+        for (i, &o) in old.iter().enumerate() {
+            if old_paired[i] {
+                continue;
+            }
+            let found = (next..new.len()).find(|&j| !new_paired[j] && kind(&self.new[new[j]]) == kind(&self.old[o]));
+            if let Some(j) = found {
+                (old_paired[i], new_paired[j]) = (true, true);
+                pairs.push((i, j));
+                next = j + 1;
+            }
+        }
+        pairs.sort_unstable();
+        let keep = in_order(&pairs);
+        let mut by_new: Vec<_> = pairs.into_iter().zip(keep).collect();
+        by_new.sort_unstable_by_key(|((_, j), _)| *j);
+        let mut by_new = by_new.into_iter().peekable();
+        for (j, &n) in new.iter().enumerate() {
+            match by_new.next_if(|((_, pj), _)| *pj == j) {
+                Some(((i, _), in_order)) => {
+                    if !in_order {
+                        self.changes[n] = Change::Moved;
+                        self.pending.push(Pending::Moved(old[i], n));
+                    }
+                    self.pair(old[i], n);
+                }
+                None => self.insert(n),
+            }
+        }
+        for (i, &o) in old.iter().enumerate() {
+            if !old_paired[i] {
+                self.pending.push(Pending::Removed(o));
+            }
+        }
+    }
+
+    /// How alike two subtrees are, from 0 to 1: the share of node labels
+    /// they have in common.
+    fn similarity(&self, o: usize, n: usize) -> f64 {
+        let (old, new) = (&self.old[o..o + self.old[o].size], &self.new[n..n + self.new[n].size]);
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for node in old {
+            *counts.entry(&node.label).or_default() += 1;
+        }
+        let mut common = 0;
+        for node in new {
+            if let Some(count) = counts.get_mut(node.label.as_str()).filter(|c| **c > 0) {
+                *count -= 1;
+                common += 1;
+            }
+        }
+        2.0 * common as f64 / (old.len() + new.len()) as f64
+    }
+
+    fn insert(&mut self, n: usize) {
+        for change in &mut self.changes[n..n + self.new[n].size] {
+            *change = Change::Inserted;
+        }
+        self.pending.push(Pending::Inserted(n));
+    }
+
+    fn map_subtree(&mut self, o: usize, n: usize) {
+        for k in 0..self.old[o].size {
+            self.old_to_new[o + k] = Some(n + k);
+        }
+    }
+
+    /// The longest common subsequence of equal children, as index pairs.
+    fn common_children(&self, old: &[usize], new: &[usize]) -> Vec<(usize, usize)> {
+        let (rows, cols) = (old.len(), new.len());
+        // lengths[i][j]: LCS length of old[i..] and new[j..].
+        let mut lengths = vec![vec![0usize; cols + 1]; rows + 1];
+        for i in (0..rows).rev() {
+            for j in (0..cols).rev() {
+                lengths[i][j] = if self.old[old[i]].behavior == self.new[new[j]].behavior {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+        let mut anchors = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < rows && j < cols {
+            if self.old[old[i]].behavior == self.new[new[j]].behavior {
+                anchors.push((i, j));
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        anchors
+    }
+
+    /// Turn each removed subtree that was inserted unchanged elsewhere into
+    /// a move.
+    fn find_moves(&mut self) {
+        let mut k = 0;
+        while k < self.pending.len() {
+            if let Pending::Removed(o) = self.pending[k] {
+                let inserted = self
+                    .pending
+                    .iter()
+                    .position(|p| matches!(*p, Pending::Inserted(n) if self.new[n].behavior == self.old[o].behavior));
+                if let Some(m) = inserted {
+                    let Pending::Inserted(n) = self.pending.remove(m) else {
+                        unreachable!()
+                    };
+                    // The edit goes where the node now is.
+                    let k_now = if m < k { k - 1 } else { k };
+                    self.pending.remove(k_now);
+                    let at = self
+                        .pending
+                        .iter()
+                        .position(|p| self.new_id(p).is_some_and(|id| id > n))
+                        .unwrap_or(self.pending.len());
+                    self.pending.insert(at, Pending::Moved(o, n));
+                    self.map_subtree(o, n);
+                    for change in &mut self.changes[n..n + self.new[n].size] {
+                        *change = Change::Same;
+                    }
+                    self.changes[n] = Change::Moved;
+                    if m < k {
+                        k -= 1;
+                    }
+                    continue;
+                }
+            }
+            k += 1;
+        }
+    }
+
+    fn new_id(&self, pending: &Pending) -> Option<usize> {
+        match *pending {
+            Pending::Inserted(n) | Pending::Modified(_, n) | Pending::Moved(_, n) => Some(n),
+            Pending::Removed(_) => None,
+        }
+    }
+
+    fn finish(self) -> TreeDiff {
+        let edits = self
+            .pending
+            .iter()
+            .map(|p| match *p {
+                Pending::Inserted(n) => Edit::Inserted {
+                    to: self.new[n].path.clone(),
+                    node: self.new[n].label.clone(),
+                    size: self.new[n].size,
+                },
+                Pending::Removed(o) => Edit::Removed {
+                    from: self.old[o].path.clone(),
+                    node: self.old[o].label.clone(),
+                    size: self.old[o].size,
+                },
+                Pending::Moved(o, n) => Edit::Moved {
+                    from: self.old[o].path.clone(),
+                    to: self.new[n].path.clone(),
+                    node: self.new[n].label.clone(),
+                },
+                Pending::Modified(o, n) => Edit::Modified {
+                    from: self.old[o].path.clone(),
+                    to: self.new[n].path.clone(),
+                    old: self.old[o].label.clone(),
+                    new: self.new[n].label.clone(),
+                },
+            })
+            .collect();
+
+        let mut graph: Vec<GraphNode> = self
+            .new
+            .iter()
+            .enumerate()
+            .map(|(id, node)| GraphNode {
+                key: format!("n{id}"),
+                label: node.label.clone(),
+                shape: Shape::of(node.behavior),
+                change: self.changes[id],
+                parent: node.parent.map(|p| (format!("n{p}"), node.edge)),
+            })
+            .collect();
+        for pending in &self.pending {
+            let Pending::Removed(o) = *pending else {
+                continue;
+            };
+            for id in o..o + self.old[o].size {
+                let node = &self.old[id];
+                // The removed root hangs off its parent's match in the new tree.
+                let parent = match node.parent {
+                    Some(p) if id == o => self.old_to_new[p].map(|n| format!("n{n}")),
+                    Some(p) => Some(format!("r{p}")),
+                    None => None,
+                };
+                graph.push(GraphNode {
+                    key: format!("r{id}"),
+                    label: node.label.clone(),
+                    shape: Shape::of(node.behavior),
+                    change: Change::Removed,
+                    parent: parent.map(|p| (p, node.edge)),
+                });
+            }
+        }
+        TreeDiff { edits, graph }
+    }
+}
+
+/// Which of `pairs`, sorted by old index, make up a longest run also in
+/// new order; the others moved.
+fn in_order(pairs: &[(usize, usize)]) -> Vec<bool> {
+    let mut run = vec![1; pairs.len()];
+    let mut prev = vec![None; pairs.len()];
+    for i in 0..pairs.len() {
+        for k in 0..i {
+            if pairs[k].1 < pairs[i].1 && run[k] + 1 > run[i] {
+                run[i] = run[k] + 1;
+                prev[i] = Some(k);
+            }
+        }
+    }
+    let mut keep = vec![false; pairs.len()];
+    let mut at = (0..pairs.len()).max_by_key(|&i| run[i]);
+    while let Some(i) = at {
+        keep[i] = true;
+        at = prev[i];
+    }
+    keep
+}
+
+/// Whether two nodes are the same but for their children.
+fn same_node<A: PartialEq>(old: &Behavior<A>, new: &Behavior<A>) -> bool {
+    if old.name() != new.name() || old.metadata() != new.metadata() {
+        return false;
+    }
+    let (old, new) = (old.unnamed(), new.unnamed());
+    match (old, new) {
+        (Behavior::Action(old), Behavior::Action(new)) => old == new,
+        (Behavior::Wait(old), Behavior::Wait(new)) => old == new,
+        _ => discriminant(old) == discriminant(new),
+    }
+}
+
+fn color(change: Change) -> &'static str {
+    match change {
+        Change::Same => "#ffffff",
+        Change::Inserted => "#28a745",
+        Change::Removed => "#c0392b",
+        Change::Modified => "#d4a017",
+        Change::Moved => "#2f80ed",
+    }
+}
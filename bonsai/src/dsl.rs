@@ -0,0 +1,631 @@
+//! A compact text format for behavior trees, for writing trees by hand and
+//! for reviewing them in diffs.
+//!
+//! ```text
+//! // Comments run to the end of the line.
+//! while wait_forever {
+//!     select {
+//!         action PlayerWithinDistance(10.0);
+//!         sequence {
+//!             wait 0.5;
+//!             invert action "Weird; action {text}";
+//!         }
+//!     }
+//!     if action Ready {
+//!         action Go;
+//!     } else {
+//!         reactive_sequence {}
+//!     }
+//! }
+//! ```
+//!
+//! | Syntax                                     | Behavior                        |
+//! |--------------------------------------------|---------------------------------|
+//! | `action <text>`                            | `Action`                        |
+//! | `wait <seconds>`                           | `Wait`                          |
+//! | `wait_forever`                             | `WaitForever`                   |
+//! | `invert <node>`                            | `Invert`                        |
+//! | `always_succeed <node>`                    | `AlwaysSucceed`                 |
+//! | `sequence { .. }`, `select { .. }`         | `Sequence`, `Select`            |
+//! | `reactive_sequence { .. }`, `reactive_select { .. }` | `.memory(false)` variants |
+//! | `when_all`, `when_any`, `after`, `race` `{ .. }` | same names                |
+//! | `while <cond> { .. }`, `while_all <cond> { .. }` | `While`, `WhileAll`       |
+//! | `if <cond> { <node> } else { <node> }`     | `If`                            |
+//! | `@<name> <node>`                           | `<node>.named(name)`            |
+//! | `#<key>=<value> <node>`                    | `<node>.meta(key, value)`       |
+//!
+//! Action text runs to the next `;`, `{`, `}`, `//` or line break and is
+//! parsed by [`FromStr`] (or your own function with
+//! [`from_str_with`]). Text containing any of those goes in double quotes,
+//! with `\"`, `\\`, `\n` and `\t` escapes. Semicolons between nodes are
+//! optional. Names, metadata keys and metadata values are letters, digits
+//! and `_`, or a quoted string like action text:
+//! `#description="Turn to the target" action aim;`.
+//!
+//! [`to_string`] prints a behavior back in a canonical layout — one node per
+//! line, four-space indents — that [`from_str`] parses to the same behavior.
+//!
+//! ```
+//! use bonsai_bt::dsl;
+//! use bonsai_bt::{Action, Sequence, Wait};
+//!
+//! let tree: bonsai_bt::Behavior<String> = dsl::from_str("sequence { action open door; wait 0.5 }").unwrap();
+//! assert_eq!(tree, Sequence(vec![Action("open door".to_string()), Wait(0.5)]));
+//! assert_eq!(dsl::to_string(&tree), "sequence {\n    action open door;\n    wait 0.5;\n}\n");
+//! ```
+
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use crate::{Behavior, Float};
+
+const INDENT: &str = "    ";
+
+/// Where and why a text failed to parse. Lines and columns are 1-based;
+/// columns count characters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DslError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DslError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for DslError {}
+
+/// Parse `src`, reading actions with [`FromStr`].
+pub fn from_str<A>(src: &str) -> Result<Behavior<A>, DslError>
+where
+    A: FromStr,
+    A::Err: Display,
+{
+    from_str_with(src, str::parse)
+}
+
+/// Parse `src`, reading actions with `action`. Its error becomes the
+/// message of a [`DslError`] pointing at the action text.
+pub fn from_str_with<A, E, F>(src: &str, action: F) -> Result<Behavior<A>, DslError>
+where
+    E: Display,
+    F: FnMut(&str) -> Result<A, E>,
+{
+    let mut parser = Parser {
+        src,
+        pos: 0,
+        line: 1,
+        column: 1,
+        action,
+    };
+    let behavior = parser.node()?;
+    parser.skip_separators();
+    match parser.peek() {
+        None => Ok(behavior),
+        Some(_) => Err(parser.error(format!("expected end of input, found {}", parser.found()))),
+    }
+}
+
+/// Print `behavior`, writing actions with [`Display`].
+pub fn to_string<A: Display>(behavior: &Behavior<A>) -> String {
+    to_string_with(behavior, |a| a.to_string())
+}
+
+/// Print `behavior`, writing actions with `action`.
+pub fn to_string_with<A, F>(behavior: &Behavior<A>, mut action: F) -> String
+where
+    F: FnMut(&A) -> String,
+{
+    let mut out = String::new();
+    print_item(behavior, 0, &mut action, &mut out);
+    out
+}
+
+const LIST_KEYWORDS: [&str; 8] = [
+    "sequence",
+    "select",
+    "reactive_sequence",
+    "reactive_select",
+    "when_all",
+    "when_any",
+    "after",
+    "race",
+];
+
+/// Composites whose children are a plain `{ .. }` list.
+fn list_keyword<A>(behavior: &Behavior<A>) -> Option<(&'static str, &[Behavior<A>])> {
+    use Behavior::*;
+    Some(match behavior {
+        Sequence(xs) => ("sequence", xs),
+        Select(xs) => ("select", xs),
+        MemorylessSequence(xs) => ("reactive_sequence", xs),
+        MemorylessSelector(xs) => ("reactive_select", xs),
+        WhenAll(xs) => ("when_all", xs),
+        WhenAny(xs) => ("when_any", xs),
+        After(xs) => ("after", xs),
+        Race(xs) => ("race", xs),
+        _ => return None,
+    })
+}
+
+fn list_node<A>(keyword: &str, children: Vec<Behavior<A>>) -> Option<Behavior<A>> {
+    use Behavior::*;
+    Some(match keyword {
+        "sequence" => Sequence(children),
+        "select" => Select(children),
+        "reactive_sequence" => MemorylessSequence(children),
+        "reactive_select" => MemorylessSelector(children),
+        "when_all" => WhenAll(children),
+        "when_any" => WhenAny(children),
+        "after" => After(children),
+        "race" => Race(children),
+        _ => return None,
+    })
+}
+
+/// Print one list item: indent, node, `;` after a leaf, newline.
+fn print_item<A, F>(behavior: &Behavior<A>, depth: usize, action: &mut F, out: &mut String)
+where
+    F: FnMut(&A) -> String,
+{
+    out.push_str(&INDENT.repeat(depth));
+    if print_node(behavior, depth, action, out) {
+        out.push(';');
+    }
+    out.push('\n');
+}
+
+/// Print `behavior` inline at nesting `depth`. Returns whether it ended in
+/// a leaf (and wants a `;`).
+fn print_node<A, F>(behavior: &Behavior<A>, depth: usize, action: &mut F, out: &mut String) -> bool
+where
+    F: FnMut(&A) -> String,
+{
+    use Behavior::*;
+    if let Named(name, inner) = behavior {
+        out.push('@');
+        push_word(name, out);
+        out.push(' ');
+        return print_node(inner, depth, action, out);
+    }
+    if let Meta(meta, inner) = behavior {
+        for (key, value) in meta {
+            out.push('#');
+            push_word(key, out);
+            out.push('=');
+            push_word(value, out);
+            out.push(' ');
+        }
+        return print_node(inner, depth, action, out);
+    }
+    if let Some((keyword, children)) = list_keyword(behavior) {
+        out.push_str(keyword);
+        print_block(children.iter(), depth, action, out);
+        return false;
+    }
+    match behavior {
+        Action(a) => {
+            out.push_str("action ");
+            push_action_text(&action(a), out);
+            true
+        }
+        Wait(t) => {
+            out.push_str(&format!("wait {t}"));
+            true
+        }
+        WaitForever => {
+            out.push_str("wait_forever");
+            true
+        }
+        Invert(inner) | AlwaysSucceed(inner) => {
+            out.push_str(if matches!(behavior, Invert(_)) {
+                "invert "
+            } else {
+                "always_succeed "
+            });
+            print_node(inner, depth, action, out)
+        }
+        While(cond, body) | WhileAll(cond, body) => {
+            out.push_str(if matches!(behavior, While(..)) {
+                "while "
+            } else {
+                "while_all "
+            });
+            print_node(cond, depth, action, out);
+            print_block(body.iter(), depth, action, out);
+            false
+        }
+        If(cond, ok, ko) => {
+            out.push_str("if ");
+            print_node(cond, depth, action, out);
+            print_block(std::iter::once(ok.as_ref()), depth, action, out);
+            out.push_str(" else");
+            print_block(std::iter::once(ko.as_ref()), depth, action, out);
+            false
+        }
+        _ => unreachable!("lists are handled above"),
+    }
+}
+
+/// Print ` { children }`, one per line; `{}` when empty.
+fn print_block<'b, A: 'b, F>(
+    children: impl ExactSizeIterator<Item = &'b Behavior<A>>,
+    depth: usize,
+    action: &mut F,
+    out: &mut String,
+) where
+    F: FnMut(&A) -> String,
+{
+    if children.len() == 0 {
+        out.push_str(" {}");
+        return;
+    }
+    out.push_str(" {\n");
+    for child in children {
+        print_item(child, depth + 1, action, out);
+    }
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+}
+
+/// Write action text bare if it reads back unchanged, quoted otherwise.
+fn push_action_text(text: &str, out: &mut String) {
+    let bare = !text.is_empty()
+        && !text.starts_with(char::is_whitespace)
+        && !text.ends_with(char::is_whitespace)
+        && !text.starts_with('"')
+        && !text.contains(['\n', '\r', ';', '{', '}'])
+        && !text.contains("//");
+    if bare {
+        out.push_str(text);
+    } else {
+        push_quoted(text, out);
+    }
+}
+
+/// Push `text` bare if it is a word, otherwise quoted.
+fn push_word(text: &str, out: &mut String) {
+    if !text.is_empty() && text.chars().all(is_word_char) {
+        out.push_str(text);
+    } else {
+        push_quoted(text, out);
+    }
+}
+
+/// Write `text` in double quotes, escaped.
+fn push_quoted(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+struct Parser<'s, F> {
+    src: &'s str,
+    /// Byte offset of the next character.
+    pos: usize,
+    line: usize,
+    column: usize,
+    action: F,
+}
+
+/// A saved position, for errors about text already consumed.
+#[derive(Clone, Copy)]
+struct Mark {
+    line: usize,
+    column: usize,
+}
+
+impl<'s, F> Parser<'s, F> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.src[self.pos..]
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn mark(&self) -> Mark {
+        Mark {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn error(&self, message: String) -> DslError {
+        self.error_at(self.mark(), message)
+    }
+
+    fn error_at(&self, at: Mark, message: String) -> DslError {
+        DslError {
+            line: at.line,
+            column: at.column,
+            message,
+        }
+    }
+
+    /// What the next token looks like, for "expected .., found .." messages.
+    fn found(&self) -> String {
+        match self.peek() {
+            None => "end of input".to_string(),
+            Some(c) if is_word_char(c) => {
+                let word: String = self.rest().chars().take_while(|c| is_word_char(*c)).collect();
+                format!("`{word}`")
+            }
+            Some(c) => format!("`{c}`"),
+        }
+    }
+
+    /// Skip whitespace and `//` comments.
+    fn skip_space(&mut self) {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('/') if self.rest().starts_with("//") => {
+                    while !matches!(self.peek(), None | Some('\n')) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Skip whitespace, comments and the optional `;` between nodes.
+    fn skip_separators(&mut self) {
+        loop {
+            self.skip_space();
+            if self.peek() != Some(';') {
+                return;
+            }
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, c: char, context: &str) -> Result<(), DslError> {
+        self.skip_space();
+        if self.peek() == Some(c) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected `{c}` {context}, found {}", self.found())))
+        }
+    }
+
+    fn node<A, E>(&mut self) -> Result<Behavior<A>, DslError>
+    where
+        E: Display,
+        F: FnMut(&str) -> Result<A, E>,
+    {
+        self.skip_space();
+        if self.peek() == Some('@') {
+            self.bump();
+            let name = self.word("a name", '@')?;
+            return Ok(self.node()?.named(name));
+        }
+        if self.peek() == Some('#') {
+            self.bump();
+            let key = self.word("a metadata key", '#')?;
+            if self.peek() != Some('=') {
+                return Err(self.error(format!("expected `=` after the metadata key, found {}", self.found())));
+            }
+            self.bump();
+            let value = self.word("a metadata value", '=')?;
+            return Ok(self.node()?.meta(key, value));
+        }
+        let at = self.mark();
+        let keyword: String = self.rest().chars().take_while(|c| is_word_char(*c)).collect();
+        if keyword.is_empty() {
+            return Err(self.error(format!("expected a node, found {}", self.found())));
+        }
+        for _ in keyword.chars() {
+            self.bump();
+        }
+        match keyword.as_str() {
+            "action" => self.action(),
+            "wait" => self.wait(),
+            "wait_forever" => Ok(Behavior::WaitForever),
+            "invert" => Ok(Behavior::Invert(Box::new(self.node()?))),
+            "always_succeed" => Ok(Behavior::AlwaysSucceed(Box::new(self.node()?))),
+            "while" | "while_all" => {
+                let cond = Box::new(self.node()?);
+                let body = self.block(&format!("after the `{keyword}` condition"))?;
+                if body.is_empty() {
+                    return Err(self.error_at(at, format!("`{keyword}` needs at least one node in its body")));
+                }
+                Ok(if keyword == "while" {
+                    Behavior::While(cond, body)
+                } else {
+                    Behavior::WhileAll(cond, body)
+                })
+            }
+            "if" => {
+                let cond = Box::new(self.node()?);
+                let ok = self.single("after the `if` condition")?;
+                self.skip_space();
+                let else_at = self.mark();
+                if !self.rest().starts_with("else") || self.rest()[4..].starts_with(is_word_char) {
+                    return Err(self.error_at(else_at, format!("expected `else`, found {}", self.found())));
+                }
+                for _ in 0..4 {
+                    self.bump();
+                }
+                let ko = self.single("after `else`")?;
+                Ok(Behavior::If(cond, Box::new(ok), Box::new(ko)))
+            }
+            _ if LIST_KEYWORDS.contains(&keyword.as_str()) => {
+                let children = self.block(&format!("after `{keyword}`"))?;
+                Ok(list_node(&keyword, children).expect("a list keyword"))
+            }
+            _ => Err(self.error_at(at, format!("unknown node `{keyword}`"))),
+        }
+    }
+
+    /// `{ node* }`.
+    fn block<A, E>(&mut self, context: &str) -> Result<Vec<Behavior<A>>, DslError>
+    where
+        E: Display,
+        F: FnMut(&str) -> Result<A, E>,
+    {
+        self.expect('{', context)?;
+        let mut children = Vec::new();
+        loop {
+            self.skip_separators();
+            match self.peek() {
+                Some('}') => {
+                    self.bump();
+                    return Ok(children);
+                }
+                None => return Err(self.error("expected `}`, found end of input".to_string())),
+                Some(_) => children.push(self.node()?),
+            }
+        }
+    }
+
+    /// `{ node }`, for the branches of `if`.
+    fn single<A, E>(&mut self, context: &str) -> Result<Behavior<A>, DslError>
+    where
+        E: Display,
+        F: FnMut(&str) -> Result<A, E>,
+    {
+        self.skip_space();
+        let at = self.mark();
+        let mut children = self.block(context)?;
+        if children.len() != 1 {
+            return Err(self.error_at(
+                at,
+                format!(
+                    "an `if` branch holds exactly one node, found {}; wrap several in a `sequence`",
+                    children.len()
+                ),
+            ));
+        }
+        Ok(children.remove(0))
+    }
+
+    fn action<A, E>(&mut self) -> Result<Behavior<A>, DslError>
+    where
+        E: Display,
+        F: FnMut(&str) -> Result<A, E>,
+    {
+        while matches!(self.peek(), Some(c) if c.is_whitespace() && c != '\n') {
+            self.bump();
+        }
+        let at = self.mark();
+        let text = if self.peek() == Some('"') {
+            self.quoted()?
+        } else {
+            let end = self
+                .rest()
+                .find(['\n', ';', '{', '}'])
+                .unwrap_or(self.rest().len())
+                .min(self.rest().find("//").unwrap_or(usize::MAX));
+            let text = self.rest()[..end].trim_end().to_string();
+            for _ in text.chars() {
+                self.bump();
+            }
+            text
+        };
+        if text.is_empty() {
+            return Err(self.error_at(at, format!("expected action text, found {}", self.found())));
+        }
+        match (self.action)(&text) {
+            Ok(action) => Ok(Behavior::Action(action)),
+            Err(e) => Err(self.error_at(at, format!("invalid action `{text}`: {e}"))),
+        }
+    }
+
+    /// A word or a quoted string: the name after an `@`, or a metadata key
+    /// or value. `what` and `after` describe it for the error.
+    fn word(&mut self, what: &str, after: char) -> Result<String, DslError> {
+        if self.peek() == Some('"') {
+            return self.quoted();
+        }
+        let word: String = self.rest().chars().take_while(|c| is_word_char(*c)).collect();
+        if word.is_empty() {
+            return Err(self.error(format!("expected {what} after `{after}`, found {}", self.found())));
+        }
+        for _ in word.chars() {
+            self.bump();
+        }
+        Ok(word)
+    }
+
+    /// A double-quoted string with `\"`, `\\`, `\n` and `\t` escapes.
+    fn quoted(&mut self) -> Result<String, DslError> {
+        let start = self.mark();
+        self.bump();
+        let mut text = String::new();
+        loop {
+            let at = self.mark();
+            match self.bump() {
+                None => return Err(self.error_at(start, "unterminated string".to_string())),
+                Some('"') => return Ok(text),
+                Some('\\') => match self.bump() {
+                    Some('"') => text.push('"'),
+                    Some('\\') => text.push('\\'),
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    other => {
+                        let found = other.map_or("end of input".to_string(), |c| format!("`\\{c}`"));
+                        return Err(self.error_at(at, format!("unknown escape {found}")));
+                    }
+                },
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    fn wait<A>(&mut self) -> Result<Behavior<A>, DslError> {
+        self.skip_space();
+        let at = self.mark();
+        let token: String = self
+            .rest()
+            .chars()
+            .take_while(|c| !c.is_whitespace() && !matches!(c, ';' | '{' | '}'))
+            .collect();
+        match token.parse::<Float>() {
+            Ok(t) if t.is_finite() && t >= 0.0 => {
+                for _ in token.chars() {
+                    self.bump();
+                }
+                Ok(Behavior::Wait(t))
+            }
+            _ if token.is_empty() => {
+                Err(self.error_at(at, format!("expected seconds after `wait`, found {}", self.found())))
+            }
+            _ => Err(self.error_at(
+                at,
+                format!("invalid wait `{token}`: expected a non-negative number of seconds"),
+            )),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
@@ -0,0 +1,143 @@
+//! One visualizer server for many behavior trees.
+//!
+//! Each [`BT`](crate::BT) attached with
+//! [`with_telemetry`](crate::BT::with_telemetry) gets a port of its own. A
+//! process running many trees can instead bind one [`TelemetryHub`] and
+//! register every tree with it under a name:
+//!
+//! | Path                    | Serves                                          |
+//! |-------------------------|-------------------------------------------------|
+//! | `/`                     | The visualizer, with a picker of the trees      |
+//! | `/tree/<name>`          | The visualizer for one tree (HTTP and WebSocket)|
+//! | `/tree/<name>/metrics`  | That tree's Prometheus metrics                  |
+//! | `/trees`                | `{"trees": [<name>, ..]}`                       |
+//!
+//! Every tree has its own trace channel and broadcaster thread, so a busy
+//! tree can't make another drop frames.
+//!
+//! ```no_run
+//! use bonsai_bt::hub::TelemetryHub;
+//! use bonsai_bt::{Action, BT};
+//!
+//! let hub = TelemetryHub::bind("127.0.0.1", 8910)?;
+//! let nav = BT::new(Action("drive"), ()).with_telemetry_hub(&hub, "navigation")?;
+//! let arm = BT::new(Action("grasp"), ()).with_telemetry_hub(&hub, "manipulation")?;
+//! // Browse to http://127.0.0.1:8910/ and pick a tree.
+//! # Ok::<(), std::io::Error>(())
+//! ```
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, MutexGuard};
+
+use crate::access::AccessPolicy;
+use crate::telemetry::TelemetryFrame;
+use crate::telemetry_state::AcceptorGuard;
+use crate::visualizer_server::{self, Endpoint, HubTrees, Routes, ServerExtras};
+
+/// A visualizer server shared by any number of named trees.
+///
+/// Cheap to clone; clones share the server. It keeps running while the hub
+/// or any tree registered with it is alive.
+#[derive(Clone)]
+pub struct TelemetryHub {
+    trees: HubTrees,
+    addr: SocketAddr,
+    guard: Arc<AcceptorGuard>,
+}
+
+impl TelemetryHub {
+    /// Bind `{addr}:{port}` and start serving, read-only to anyone who can
+    /// reach it. Port `0` picks a free port; see [`local_addr`](Self::local_addr).
+    ///
+    /// # Errors
+    /// Returns `io::Error` if `{addr}:{port}` cannot be bound.
+    pub fn bind(addr: &str, port: u16) -> io::Result<Self> {
+        Self::bind_with_access(addr, port, AccessPolicy::new())
+    }
+
+    /// Like [`bind`](Self::bind), but only serve clients `access` lets in —
+    /// for every tree alike.
+    pub fn bind_with_access(addr: &str, port: u16, access: AccessPolicy) -> io::Result<Self> {
+        let listener = TcpListener::bind((addr, port))?;
+        let trees = HubTrees::default();
+        let routes = Routes::Hub(Arc::clone(&trees));
+        let (acceptor, shutdown, bound) = visualizer_server::spawn_acceptor(listener, routes, access)?;
+        Ok(Self {
+            trees,
+            addr: bound,
+            guard: Arc::new(AcceptorGuard::new(shutdown, bound, acceptor)),
+        })
+    }
+
+    /// The address the hub is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Names of the registered trees, sorted.
+    pub fn trees(&self) -> Vec<String> {
+        self.lock().keys().cloned().collect()
+    }
+
+    /// Serve frames from `rx` under `/tree/{name}`, replacing any tree of the
+    /// same name. The tree is unlisted again once every sender for `rx` drops.
+    ///
+    /// Returns the guard keeping the server alive for the tree's owner.
+    pub(crate) fn register(
+        &self,
+        name: &str,
+        greeting: Vec<String>,
+        rx: Receiver<TelemetryFrame>,
+        extras: ServerExtras,
+    ) -> io::Result<Arc<AcceptorGuard>> {
+        validate_name(name)?;
+        let endpoint = Arc::new(Endpoint::new(greeting, extras));
+        self.lock().insert(name.to_string(), Arc::clone(&endpoint));
+
+        let trees = Arc::clone(&self.trees);
+        let name = name.to_string();
+        let registered = Arc::clone(&endpoint);
+        visualizer_server::spawn_broadcaster(endpoint, rx, move || {
+            let mut trees = trees.lock().unwrap_or_else(|p| p.into_inner());
+            // A newer tree may have taken the name meanwhile; leave it be.
+            if trees.get(&name).is_some_and(|e| Arc::ptr_eq(e, &registered)) {
+                trees.remove(&name);
+            }
+        })?;
+        Ok(Arc::clone(&self.guard))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, Arc<Endpoint>>> {
+        self.trees.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+impl fmt::Debug for TelemetryHub {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TelemetryHub")
+            .field("addr", &self.addr)
+            .field("trees", &self.trees())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tree names appear verbatim in URLs, so keep them to unreserved characters.
+fn validate_name(name: &str) -> io::Result<()> {
+    let ok = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'));
+    if ok {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("tree name {name:?} must be non-empty ASCII letters, digits, '-', '_', '.' or '~'"),
+        ))
+    }
+}
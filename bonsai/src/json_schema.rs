@@ -0,0 +1,48 @@
+//! [JSON Schemas](https://json-schema.org) for tree files, so editors can
+//! autocomplete and check them and CI can reject a bad file before it
+//! reaches [`BT::new`](crate::BT::new).
+//!
+//! The action type supplies its own schema by implementing
+//! [`schemars::JsonSchema`], usually with `#[derive(JsonSchema)]` next to its
+//! serde derives. Every node type is covered, the `.memory(false)` variants
+//! included, and `While`/`WhileAll` bodies must be non-empty.
+//!
+//! ```
+//! use bonsai_bt::json_schema;
+//! use schemars::JsonSchema;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, JsonSchema)]
+//! enum EnemyAction {
+//!     Circling,
+//!     Shoot { target: String },
+//! }
+//!
+//! let schema = json_schema::behavior_schema::<EnemyAction>();
+//! std::fs::write(
+//!     std::env::temp_dir().join("enemy.schema.json"),
+//!     serde_json::to_string_pretty(&schema).unwrap(),
+//! )
+//! .unwrap();
+//! ```
+//!
+//! In VS Code, point `json.schemas` in the workspace settings at the written
+//! file, e.g. `{ "fileMatch": ["trees/*.json"], "url": "./enemy.schema.json" }`.
+// The whole module is gated on the `schemars` feature in [`lib.rs`](crate).
+
+use schemars::{schema_for, JsonSchema, Schema};
+
+use crate::readable::Readable;
+use crate::Behavior;
+
+/// The schema of `Behavior<A>` in its derived serde representation, e.g.
+/// `{"Sequence": [{"Action": ..}, {"Wait": 1.0}]}`.
+pub fn behavior_schema<A: JsonSchema>() -> Schema {
+    schema_for!(Behavior<A>)
+}
+
+/// The schema of `Behavior<A>` in the [readable](crate::readable)
+/// representation, e.g. `{"type": "sequence", "children": [..]}`.
+pub fn readable_schema<A: JsonSchema>() -> Schema {
+    schema_for!(Readable<A>)
+}
@@ -133,11 +133,33 @@ mod behavior;
 mod bt;
 mod event;
 mod sequence;
+mod simplify;
 mod state;
 mod status;
-mod tracer;
+pub mod tracer;
+pub mod visit;
 mod when_all;
 
+pub mod profiler;
+
+pub mod subscriber;
+
+pub mod diagram;
+
+pub mod diff;
+
+pub mod dsl;
+
+pub mod reload;
+
+pub mod path;
+
+#[cfg(feature = "serde")]
+pub mod readable;
+
+#[cfg(feature = "schemars")]
+pub mod json_schema;
+
 #[cfg(feature = "visualize")]
 pub mod telemetry;
 
@@ -153,6 +175,33 @@ mod visualizer;
 #[cfg(feature = "visualize")]
 mod visualizer_server;
 
+#[cfg(feature = "visualize")]
+mod metrics;
+
+#[cfg(feature = "visualize")]
+pub mod wire;
+
+#[cfg(feature = "visualize")]
+pub mod debugger;
+
+#[cfg(feature = "visualize")]
+pub mod hub;
+
+#[cfg(feature = "visualize")]
+pub mod access;
+
+#[cfg(feature = "visualize")]
+pub mod recording;
+
+#[cfg(feature = "visualize")]
+pub mod chrome_trace;
+
+#[cfg(feature = "visualize")]
+pub mod report;
+
+#[cfg(feature = "xml")]
+pub mod xml;
+
 #[cfg(feature = "visualize")]
 #[doc(hidden)]
 pub use visualizer_server::spawn_server;
@@ -0,0 +1,307 @@
+//! Tick counters exported on the visualizer's `/metrics` endpoint in the
+//! Prometheus text exposition format.
+//!
+//! [`TreeMetrics`] is shared between the tick path, which bumps atomic
+//! counters, and the acceptor thread, which renders them on scrape. The
+//! per-node counters sit behind a read lock that is only written when
+//! [`BT::replace_behavior`](crate::BT::replace_behavior) swaps the tree.
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Instant;
+
+use crate::telemetry::{TreeDefinition, TreeNode};
+use crate::wire::StatusVec;
+use crate::Status;
+
+/// Content type of [`TreeMetrics::render`]'s output.
+pub(crate) const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Sentinel for "no timestamp" in the nanosecond atomics below.
+const NEVER: u64 = u64::MAX;
+
+/// Seconds the tick-rate gauge averages over.
+const RATE_WINDOW_SECS: u64 = 10;
+
+/// Counters for one tree.
+#[derive(Debug)]
+pub(crate) struct TreeMetrics {
+    nodes: RwLock<NodeCounters>,
+    ticks: AtomicU64,
+    dropped: AtomicU64,
+    epoch: Instant,
+    /// Nanoseconds since `epoch` of the last tick, or `NEVER`.
+    last_tick_ns: AtomicU64,
+    /// Nanoseconds since `epoch` when the root started its current `Running`
+    /// streak, or `NEVER` while it isn't running.
+    root_running_since_ns: AtomicU64,
+    /// Ticks per second over the last [`RATE_WINDOW_SECS`].
+    rate: TickRate,
+}
+
+/// Tick counts per whole second since `epoch`, in a ring of buckets, so the
+/// rate covers a fixed window however often, and by however many scrapers,
+/// it is read. Only the tick path writes.
+#[derive(Debug, Default)]
+struct TickRate {
+    /// Ticks counted in the second held by the bucket.
+    counts: [AtomicU64; RATE_WINDOW_SECS as usize + 1],
+    /// The second each bucket holds.
+    seconds: [AtomicU64; RATE_WINDOW_SECS as usize + 1],
+}
+
+impl TickRate {
+    fn record(&self, second: u64) {
+        let i = (second % self.counts.len() as u64) as usize;
+        if self.seconds[i].load(Ordering::Relaxed) != second {
+            self.counts[i].store(0, Ordering::Relaxed);
+            self.seconds[i].store(second, Ordering::Relaxed);
+        }
+        self.counts[i].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Ticks per second over the [`RATE_WINDOW_SECS`] whole seconds before
+    /// `second`, which is still being counted, or over as many as there have
+    /// been since second 1.
+    fn per_second(&self, second: u64) -> f64 {
+        let ticks: u64 = (second.saturating_sub(RATE_WINDOW_SECS)..second)
+            .map(|s| {
+                let i = (s % self.counts.len() as u64) as usize;
+                if self.seconds[i].load(Ordering::Relaxed) == s {
+                    self.counts[i].load(Ordering::Relaxed)
+                } else {
+                    0
+                }
+            })
+            .sum();
+        let window = RATE_WINDOW_SECS.min(second.saturating_sub(1)).max(1);
+        ticks as f64 / window as f64
+    }
+}
+
+/// Per-node counters, replaced wholesale when the tree changes shape.
+#[derive(Debug)]
+struct NodeCounters {
+    /// `(node_type, label)` by preorder id.
+    nodes: Vec<(&'static str, String)>,
+    /// `[running, success, failure]` counts by preorder id.
+    statuses: Vec<[AtomicU64; 3]>,
+}
+
+impl NodeCounters {
+    fn new(definition: &TreeDefinition) -> Self {
+        let mut nodes = Vec::new();
+        flatten(&definition.root, &mut nodes);
+        Self {
+            statuses: nodes.iter().map(|_| Default::default()).collect(),
+            nodes,
+        }
+    }
+}
+
+impl TreeMetrics {
+    pub(crate) fn new(definition: &TreeDefinition) -> Self {
+        let epoch = Instant::now();
+        Self {
+            nodes: RwLock::new(NodeCounters::new(definition)),
+            ticks: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            epoch,
+            last_tick_ns: AtomicU64::new(NEVER),
+            root_running_since_ns: AtomicU64::new(NEVER),
+            rate: TickRate::default(),
+        }
+    }
+
+    /// Start counting per-node statuses for a replaced tree. The old
+    /// per-node series disappear; tree-wide counters carry on.
+    pub(crate) fn retarget(&self, definition: &TreeDefinition) {
+        *self.nodes.write().unwrap_or_else(|p| p.into_inner()) = NodeCounters::new(definition);
+    }
+
+    /// Count one tick.
+    pub(crate) fn observe(&self, states: &StatusVec) {
+        let now = self.epoch.elapsed().as_nanos() as u64;
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+        self.last_tick_ns.store(now, Ordering::Relaxed);
+        // Second 0 marks empty buckets, so count from 1.
+        self.rate.record(now / 1_000_000_000 + 1);
+        let nodes = self.nodes.read().unwrap_or_else(|p| p.into_inner());
+        for (id, status) in states.iter() {
+            if let Some(counts) = nodes.statuses.get(id) {
+                counts[status_index(status)].fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        match states.get(0) {
+            Some(Status::Running) => {
+                // Only the first tick of a streak sets the start.
+                let _ = self
+                    .root_running_since_ns
+                    .compare_exchange(NEVER, now, Ordering::Relaxed, Ordering::Relaxed);
+            }
+            _ => self.root_running_since_ns.store(NEVER, Ordering::Relaxed),
+        }
+    }
+
+    /// Count one trace dropped because the broadcaster channel was full.
+    pub(crate) fn record_dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render every metric, given the current number of WebSocket clients.
+    pub(crate) fn render(&self, clients: usize) -> String {
+        let now_ns = self.epoch.elapsed().as_nanos() as u64;
+        let ticks = self.ticks.load(Ordering::Relaxed);
+        let rate = self.rate.per_second(now_ns / 1_000_000_000 + 1);
+        let since = |ns: u64| {
+            if ns == NEVER {
+                0.0
+            } else {
+                now_ns.saturating_sub(ns) as f64 / 1e9
+            }
+        };
+
+        let mut out = String::new();
+        let mut family = |name: &str, kind: &str, help: &str, samples: &[(String, String)]| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+        family(
+            "bonsai_ticks_total",
+            "counter",
+            "Ticks processed by the tree.",
+            &[(String::new(), ticks.to_string())],
+        );
+        family(
+            "bonsai_tick_rate_hz",
+            "gauge",
+            "Ticks per second over the last 10 seconds.",
+            &[(String::new(), format!("{rate}"))],
+        );
+        family(
+            "bonsai_seconds_since_last_tick",
+            "gauge",
+            "Seconds since the tree was last ticked; 0 before the first tick.",
+            &[(
+                String::new(),
+                format!("{}", since(self.last_tick_ns.load(Ordering::Relaxed))),
+            )],
+        );
+        family(
+            "bonsai_root_running_seconds",
+            "gauge",
+            "Seconds the root has been continuously Running; 0 when it is not.",
+            &[(
+                String::new(),
+                format!("{}", since(self.root_running_since_ns.load(Ordering::Relaxed))),
+            )],
+        );
+        family(
+            "bonsai_dropped_traces_total",
+            "counter",
+            "Tick traces dropped because the visualizer channel was full.",
+            &[(String::new(), self.dropped.load(Ordering::Relaxed).to_string())],
+        );
+        family(
+            "bonsai_ws_clients",
+            "gauge",
+            "Connected visualizer WebSocket clients.",
+            &[(String::new(), clients.to_string())],
+        );
+        let nodes = self.nodes.read().unwrap_or_else(|p| p.into_inner());
+        let mut samples = Vec::with_capacity(nodes.nodes.len() * 3);
+        for (id, ((node_type, label), counts)) in nodes.nodes.iter().zip(&nodes.statuses).enumerate() {
+            for (status, count) in ["running", "success", "failure"].iter().zip(counts) {
+                samples.push((
+                    format!(
+                        "{{node_id=\"{id}\",node_type=\"{node_type}\",label=\"{}\",status=\"{status}\"}}",
+                        escape_label(label)
+                    ),
+                    count.load(Ordering::Relaxed).to_string(),
+                ));
+            }
+        }
+        family(
+            "bonsai_node_status_total",
+            "counter",
+            "Ticks in which a node returned each status.",
+            &samples,
+        );
+        out
+    }
+}
+
+fn flatten(node: &TreeNode, out: &mut Vec<(&'static str, String)>) {
+    // Preorder, so the vector index is the node id.
+    debug_assert_eq!(node.id, out.len());
+    out.push((node.node_type, node.label.clone()));
+    for child in &node.children {
+        flatten(child, out);
+    }
+}
+
+fn status_index(status: Status) -> usize {
+    match status {
+        Status::Running => 0,
+        Status::Success => 1,
+        Status::Failure => 2,
+    }
+}
+
+/// Escape a label value per the exposition format: `\`, `"` and newlines.
+fn escape_label(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape_label, TickRate, RATE_WINDOW_SECS};
+
+    #[test]
+    fn tick_rate_covers_a_fixed_window_of_whole_seconds() {
+        let rate = TickRate::default();
+        for second in 1..=30 {
+            for _ in 0..second {
+                rate.record(second);
+            }
+        }
+        // Seconds 21..=30, the one being counted left out; reading twice
+        // gives the same answer.
+        let expected = (21..=30).sum::<u64>() as f64 / RATE_WINDOW_SECS as f64;
+        assert_eq!(rate.per_second(31), expected);
+        assert_eq!(rate.per_second(31), expected);
+        // Seconds without ticks count as zero, even where a bucket still
+        // holds an older second.
+        assert_eq!(rate.per_second(36), (26..=30).sum::<u64>() as f64 / 10.0);
+        assert_eq!(rate.per_second(100), 0.0);
+
+        // Before a whole window has passed, only the seconds so far count.
+        let rate = TickRate::default();
+        for second in 1..=3 {
+            rate.record(second);
+            rate.record(second);
+        }
+        assert_eq!(rate.per_second(1), 0.0);
+        assert_eq!(rate.per_second(2), 2.0);
+        assert_eq!(rate.per_second(4), 2.0);
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape_label("Move(\"a\\b\")\n"), "Move(\\\"a\\\\b\\\")\\n");
+    }
+}
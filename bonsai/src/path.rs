@@ -0,0 +1,325 @@
+//! Addressing nodes by name and position, for references to a node that
+//! outlive edits to the rest of the tree.
+//!
+//! Telemetry ids are preorder positions, so inserting one node shifts the
+//! id of every node after it. A [`NodePath`] reads like a file path instead:
+//! `/` is the root, `/1/0` the first child of its second child (children
+//! counted as in [`TreeDefinition`](crate::telemetry::TreeDefinition): the
+//! condition, `then` and `else` of an `If`, the condition then the body of a
+//! `While`), and a name segment, `attack`, the first node in preorder that
+//! was given that name with [`Behavior::named`], searching the subtree
+//! reached so far, the node itself included. So `/attack/1` is the second
+//! child of the node named `attack`, wherever it sits, and stays that as
+//! long as the edits leave that subtree alone.
+//!
+//! [`NodeIndex`] resolves paths against one tree, to preorder ids and back.
+//! Its [`path`](NodeIndex::path) of a node goes through the nearest named
+//! ancestor, so it keeps pointing at the node when the tree changes around
+//! it; [`TickTrace::remap`] uses that to carry a recorded trace over to an
+//! edited tree.
+//!
+//! ```
+//! use bonsai_bt::path::{NodeIndex, NodePath};
+//! use bonsai_bt::{Action, Sequence, Wait};
+//!
+//! let tree = Sequence(vec![
+//!     Wait(1.0),
+//!     Sequence(vec![Action("aim"), Action("shoot")]).named("attack"),
+//! ]);
+//! let index = NodeIndex::new(&tree);
+//! let shoot: NodePath = "/attack/1".parse().unwrap();
+//! assert_eq!(index.id(&shoot), Some(4));
+//! assert_eq!(index.path(4), Some(shoot.clone()));
+//! assert_eq!(index.path(1).unwrap().to_string(), "/0");
+//!
+//! // Inserting a node shifts the ids, but not the path.
+//! let edited = Sequence(vec![
+//!     Wait(1.0),
+//!     Action("reload"),
+//!     Sequence(vec![Action("aim"), Action("shoot")]).named("attack"),
+//! ]);
+//! assert_eq!(NodeIndex::new(&edited).id(&shoot), Some(5));
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::tracer::{children_of, TickTrace};
+use crate::{Behavior, Status};
+
+/// The address of a node: a list of [`Segment`]s from the root. See the
+/// [module docs](self).
+///
+/// Written with `/` between segments and a leading `/`; a segment of digits
+/// is a child index, anything else a name. Parsing accepts the leading `/`
+/// being left out. Names made of digits or holding a `/` can be used in
+/// paths built with [`name`](Self::name), but don't survive the text form.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "String", try_from = "String"))]
+pub struct NodePath {
+    segments: Vec<Segment>,
+}
+
+/// One step of a [`NodePath`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Segment {
+    /// The child at this index.
+    Index(usize),
+    /// The first node with this name, in preorder, in the subtree reached so
+    /// far.
+    Name(String),
+}
+
+impl NodePath {
+    /// `/`, the root.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// This path, then child `index`.
+    #[must_use]
+    pub fn child(mut self, index: usize) -> Self {
+        self.segments.push(Segment::Index(index));
+        self
+    }
+
+    /// This path, then the node named `name`.
+    #[must_use]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.segments.push(Segment::Name(name.into()));
+        self
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+}
+
+impl fmt::Display for NodePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return f.write_str("/");
+        }
+        for segment in &self.segments {
+            match segment {
+                Segment::Index(i) => write!(f, "/{i}")?,
+                Segment::Name(name) => write!(f, "/{name}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a string isn't a [`NodePath`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodePathError {
+    pub path: String,
+    pub message: &'static str,
+}
+
+impl fmt::Display for NodePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid node path `{}`: {}", self.path, self.message)
+    }
+}
+
+impl std::error::Error for NodePathError {}
+
+impl FromStr for NodePath {
+    type Err = NodePathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |message| NodePathError {
+            path: s.to_string(),
+            message,
+        };
+        if s.is_empty() {
+            return Err(error("empty path; the root is `/`"));
+        }
+        let rest = s.strip_prefix('/').unwrap_or(s);
+        if rest.is_empty() {
+            return Ok(Self::root());
+        }
+        let segments = rest
+            .split('/')
+            .map(|segment| match segment {
+                "" => Err(error("empty segment")),
+                _ if segment.bytes().all(|b| b.is_ascii_digit()) => segment
+                    .parse()
+                    .map(Segment::Index)
+                    .map_err(|_| error("child index out of range")),
+                name => Ok(Segment::Name(name.to_string())),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { segments })
+    }
+}
+
+impl From<NodePath> for String {
+    fn from(path: NodePath) -> Self {
+        path.to_string()
+    }
+}
+
+impl TryFrom<String> for NodePath {
+    type Error = NodePathError;
+
+    fn try_from(path: String) -> Result<Self, Self::Error> {
+        path.parse()
+    }
+}
+
+/// The shape and names of one tree, for turning [`NodePath`]s into preorder
+/// ids and back. Build one per tree and keep it while the tree is unchanged.
+#[derive(Clone, Debug)]
+pub struct NodeIndex {
+    /// By preorder id.
+    nodes: Vec<Entry>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    name: Option<String>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Number of nodes in the subtree, this one included.
+    size: usize,
+}
+
+impl NodeIndex {
+    /// Index `behavior`, numbering its nodes as `BT::new` does.
+    pub fn new<A>(behavior: &Behavior<A>) -> Self {
+        fn walk<A>(behavior: &Behavior<A>, parent: Option<usize>, nodes: &mut Vec<Entry>) -> usize {
+            let id = nodes.len();
+            nodes.push(Entry {
+                name: behavior.name().map(str::to_string),
+                parent,
+                children: Vec::new(),
+                size: 0,
+            });
+            for child in children_of(behavior) {
+                let child_id = walk(child, Some(id), nodes);
+                nodes[id].children.push(child_id);
+            }
+            nodes[id].size = nodes.len() - id;
+            id
+        }
+        let mut nodes = Vec::new();
+        walk(behavior, None, &mut nodes);
+        Self { nodes }
+    }
+
+    /// Build from names and parents, in preorder.
+    #[cfg(feature = "visualize")]
+    pub(crate) fn from_preorder(nodes: impl IntoIterator<Item = (Option<String>, Option<usize>)>) -> Self {
+        let mut entries: Vec<Entry> = Vec::new();
+        for (name, parent) in nodes {
+            let id = entries.len();
+            if let Some(parent) = parent {
+                entries[parent].children.push(id);
+            }
+            entries.push(Entry {
+                name,
+                parent,
+                children: Vec::new(),
+                size: 1,
+            });
+        }
+        for id in (0..entries.len()).rev() {
+            if let Some(parent) = entries[id].parent {
+                entries[parent].size += entries[id].size;
+            }
+        }
+        Self { nodes: entries }
+    }
+
+    /// Number of nodes in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// The name of node `id`, if it has one.
+    pub fn name(&self, id: usize) -> Option<&str> {
+        self.nodes.get(id)?.name.as_deref()
+    }
+
+    /// The preorder id of the node at `path`, or `None` if there is none.
+    pub fn id(&self, path: &NodePath) -> Option<usize> {
+        let mut at = 0;
+        self.nodes.first()?;
+        for segment in &path.segments {
+            let node = &self.nodes[at];
+            at = match segment {
+                Segment::Index(i) => *node.children.get(*i)?,
+                Segment::Name(name) => {
+                    (at..at + node.size).find(|&id| self.nodes[id].name.as_deref() == Some(name.as_str()))?
+                }
+            };
+        }
+        Some(at)
+    }
+
+    /// The path of node `id`: the name of its nearest ancestor (or itself)
+    /// whose name leads back to it, then child indices; from the root if no
+    /// such ancestor exists. `None` if there is no node `id`.
+    pub fn path(&self, id: usize) -> Option<NodePath> {
+        self.nodes.get(id)?;
+        let mut steps = Vec::new();
+        let mut at = id;
+        let mut segments = loop {
+            if let Some(name) = &self.nodes[at].name {
+                let named = NodePath::root().name(name.clone());
+                if self.id(&named) == Some(at) {
+                    break named.segments;
+                }
+            }
+            match self.nodes[at].parent {
+                Some(parent) => {
+                    let index = self.nodes[parent].children.iter().position(|&c| c == at);
+                    steps.push(Segment::Index(index.expect("a child of its parent")));
+                    at = parent;
+                }
+                None => break Vec::new(),
+            }
+        };
+        segments.extend(steps.into_iter().rev());
+        Some(NodePath { segments })
+    }
+
+    /// The id, in the tree of `to`, of the node at node `id`'s
+    /// [`path`](Self::path) in this one.
+    pub fn translate(&self, id: usize, to: &NodeIndex) -> Option<usize> {
+        to.id(&self.path(id)?)
+    }
+}
+
+impl TickTrace {
+    /// The status the node at `path` returned this tick, if it ran; `index`
+    /// is the tree the trace was recorded on.
+    pub fn status_at(&self, index: &NodeIndex, path: &NodePath) -> Option<Status> {
+        self.states.get(&index.id(path)?).copied()
+    }
+
+    /// This trace, recorded on the tree of `from`, renumbered for the tree
+    /// of `to` by [`NodeIndex::translate`]. Nodes with no counterpart are
+    /// left out.
+    #[must_use]
+    pub fn remap(&self, from: &NodeIndex, to: &NodeIndex) -> TickTrace {
+        TickTrace {
+            tick_id: self.tick_id,
+            states: self
+                .states
+                .iter()
+                .filter_map(|(&id, &status)| Some((from.translate(id, to)?, status)))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,389 @@
+//! Per-node wall-clock profiling of behavior tree ticks.
+//!
+//! [`Profiler`] is a [`Tracer`] that times every node the tick visits. Pass it
+//! to [`BT::tick_with_tracer`](crate::BT::tick_with_tracer) each tick and call
+//! [`Profiler::report`] whenever you want a [`ProfileReport`] to print, serialize
+//! or publish to the visualizer as a heat map.
+//!
+//! ```rust
+//! use bonsai_bt::profiler::Profiler;
+//! use bonsai_bt::{Action, Event, Sequence, Success, UpdateArgs, BT};
+//!
+//! let behavior = Sequence(vec![Action("plan"), Action("act")]);
+//! let mut bt = BT::new(behavior.clone(), ());
+//! let mut profiler = Profiler::new();
+//! let e: Event = UpdateArgs { dt: 0.1 }.into();
+//! bt.tick_with_tracer(&e, &mut |args, _| (Success, args.dt), &mut profiler);
+//!
+//! let report = profiler.report().with_labels(&behavior);
+//! assert_eq!(report.ticks, 1);
+//! println!("{report}");
+//! ```
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::tracer::{children_of, classify, Tracer};
+use crate::{Behavior, Float, Status};
+
+/// Linear buckets below this many nanoseconds; log-linear above.
+const LINEAR_NS: u64 = 16;
+/// Sub-buckets per power of two above [`LINEAR_NS`]. Four sub-buckets bound
+/// the relative error of a reported percentile to 25%.
+const SUB_BUCKETS_LOG2: u32 = 2;
+
+/// Log-linear latency histogram in nanoseconds. Buckets are allocated lazily
+/// up to the largest sample seen, so a node that only ever takes microseconds
+/// costs a few dozen counters.
+#[derive(Clone, Debug, Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    count: u64,
+    total_ns: u64,
+    max_ns: u64,
+}
+
+impl Histogram {
+    fn bucket_of(ns: u64) -> usize {
+        if ns < LINEAR_NS {
+            return ns as usize;
+        }
+        let octave = 63 - ns.leading_zeros(); // >= log2(LINEAR_NS)
+        let shift = octave - SUB_BUCKETS_LOG2;
+        let sub = (ns >> shift) as usize & ((1 << SUB_BUCKETS_LOG2) - 1);
+        let first_octave = LINEAR_NS.trailing_zeros();
+        LINEAR_NS as usize + ((octave - first_octave) as usize) * (1 << SUB_BUCKETS_LOG2) + sub
+    }
+
+    /// Largest value that falls into bucket `idx`.
+    fn upper_bound(idx: usize) -> u64 {
+        if idx < LINEAR_NS as usize {
+            return idx as u64;
+        }
+        let rel = idx - LINEAR_NS as usize;
+        let octave = (rel >> SUB_BUCKETS_LOG2) as u32 + LINEAR_NS.trailing_zeros();
+        let sub = (rel & ((1 << SUB_BUCKETS_LOG2) - 1)) as u64;
+        let shift = octave - SUB_BUCKETS_LOG2;
+        let lower = ((1 << SUB_BUCKETS_LOG2) + sub) << shift;
+        lower + (1 << shift) - 1
+    }
+
+    fn record(&mut self, d: Duration) {
+        let ns = u64::try_from(d.as_nanos()).unwrap_or(u64::MAX);
+        let idx = Self::bucket_of(ns);
+        if idx >= self.buckets.len() {
+            self.buckets.resize(idx + 1, 0);
+        }
+        self.buckets[idx] += 1;
+        self.count += 1;
+        self.total_ns = self.total_ns.saturating_add(ns);
+        self.max_ns = self.max_ns.max(ns);
+    }
+
+    /// Smallest bucket bound below which at least `q` of the samples fall,
+    /// clamped to the observed maximum.
+    fn percentile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((q * self.count as f64).ceil() as u64).clamp(1, self.count);
+        let mut seen = 0;
+        for (idx, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Self::upper_bound(idx).min(self.max_ns);
+            }
+        }
+        self.max_ns
+    }
+
+    fn stats(&self) -> TimingStats {
+        TimingStats {
+            total_ns: self.total_ns,
+            mean_ns: self.total_ns.checked_div(self.count).unwrap_or(0),
+            p50_ns: self.percentile(0.50),
+            p90_ns: self.percentile(0.90),
+            p99_ns: self.percentile(0.99),
+            max_ns: self.max_ns,
+        }
+    }
+}
+
+/// Accumulated samples for one node id.
+#[derive(Clone, Debug, Default)]
+struct NodeStats {
+    inclusive: Histogram,
+    exclusive: Histogram,
+}
+
+/// One node on the enter/exit stack.
+#[derive(Clone, Debug)]
+struct Frame {
+    id: usize,
+    start: Instant,
+    /// Inclusive time of the children that have exited so far.
+    children: Duration,
+}
+
+/// A [`Tracer`] that measures wall-clock time per node per tick.
+///
+/// For every node visited in a tick it records:
+/// - **inclusive** time: from `enter` to `exit`, children included;
+/// - **exclusive** time: inclusive time minus the children's inclusive time,
+///   i.e. the time spent in the node itself (for an `Action`, the callback).
+///
+/// A node ticked several times within one tick (e.g. a `While` body looping
+/// on leftover `dt`) contributes a single sample holding the sum, so each
+/// sample answers "how long did this node cost this tick".
+#[derive(Clone, Debug, Default)]
+pub struct Profiler {
+    nodes: Vec<NodeStats>,
+    stack: Vec<Frame>,
+    /// Per-tick `(inclusive, exclusive)` accumulators indexed by node id;
+    /// `Some` only for nodes visited this tick. Flushed when the root exits.
+    tick: Vec<Option<(Duration, Duration)>>,
+    /// Ids with a `Some` accumulator, so flushing is O(visited).
+    touched: Vec<usize>,
+    ticks: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of ticks profiled so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Discard every sample, keeping allocated capacity.
+    pub fn reset(&mut self) {
+        self.nodes.clear();
+        self.stack.clear();
+        self.tick.clear();
+        self.touched.clear();
+        self.ticks = 0;
+    }
+
+    /// Summarize the samples collected so far. Nodes never visited are
+    /// omitted; the rest are listed by ascending id.
+    pub fn report(&self) -> ProfileReport {
+        let nodes = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.inclusive.count > 0)
+            .map(|(id, s)| NodeProfile {
+                id,
+                label: None,
+                samples: s.inclusive.count,
+                inclusive: s.inclusive.stats(),
+                exclusive: s.exclusive.stats(),
+            })
+            .collect();
+        ProfileReport {
+            ticks: self.ticks,
+            nodes,
+        }
+    }
+
+    fn grow_to(&mut self, id: usize) {
+        if id >= self.nodes.len() {
+            self.nodes.resize_with(id + 1, NodeStats::default);
+            self.tick.resize(id + 1, None);
+        }
+    }
+
+    fn flush_tick(&mut self) {
+        for id in self.touched.drain(..) {
+            if let Some((inclusive, exclusive)) = self.tick[id].take() {
+                self.nodes[id].inclusive.record(inclusive);
+                self.nodes[id].exclusive.record(exclusive);
+            }
+        }
+        self.ticks += 1;
+    }
+}
+
+impl Tracer for Profiler {
+    const IS_RECORDING: bool = true;
+
+    #[inline]
+    fn enter(&mut self, id: usize) {
+        self.stack.push(Frame {
+            id,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    }
+
+    #[inline]
+    fn exit(&mut self, id: usize, _status: Status, _dt: Float) {
+        let now = Instant::now();
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+        debug_assert_eq!(frame.id, id, "Profiler: unbalanced enter/exit");
+        let inclusive = now - frame.start;
+        let exclusive = inclusive.saturating_sub(frame.children);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += inclusive;
+        }
+        self.grow_to(id);
+        match &mut self.tick[id] {
+            Some((incl, excl)) => {
+                *incl += inclusive;
+                *excl += exclusive;
+            }
+            slot @ None => {
+                *slot = Some((inclusive, exclusive));
+                self.touched.push(id);
+            }
+        }
+        if self.stack.is_empty() {
+            self.flush_tick();
+        }
+    }
+}
+
+/// Latency summary for one node, in nanoseconds. Percentiles come from a
+/// log-linear histogram and are accurate to within 25% of the true value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TimingStats {
+    pub total_ns: u64,
+    pub mean_ns: u64,
+    pub p50_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+    pub max_ns: u64,
+}
+
+/// Profile of a single node, identified by its preorder id.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeProfile {
+    pub id: usize,
+    /// Display label, filled in by [`ProfileReport::with_labels`].
+    pub label: Option<String>,
+    /// Number of ticks in which this node was visited.
+    pub samples: u64,
+    pub inclusive: TimingStats,
+    pub exclusive: TimingStats,
+}
+
+/// Snapshot of a [`Profiler`], produced by [`Profiler::report`].
+///
+/// `Display` renders a fixed-width table; with the `serde` feature the report
+/// serializes as plain JSON. With `visualize`, pass it to
+/// `BT::publish_profile` to color the web visualizer as a heat map.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ProfileReport {
+    /// Number of ticks the samples were collected over.
+    pub ticks: u64,
+    pub nodes: Vec<NodeProfile>,
+}
+
+impl ProfileReport {
+    /// Attach node labels (the same labels the visualizer shows) from the
+    /// behavior the profiled `BT` was built with.
+    #[must_use]
+    pub fn with_labels<A: fmt::Debug>(mut self, behavior: &Behavior<A>) -> Self {
+        fn walk<A: fmt::Debug>(b: &Behavior<A>, out: &mut Vec<String>) {
+            let (node_type, label) = classify(b);
+            out.push(label.unwrap_or_else(|| node_type.to_string()));
+            for c in children_of(b) {
+                walk(c, out);
+            }
+        }
+        let mut labels = Vec::new();
+        walk(behavior, &mut labels);
+        for node in &mut self.nodes {
+            node.label = labels.get(node.id).cloned();
+        }
+        self
+    }
+
+    /// Look up the profile of node `id`, if it was visited.
+    pub fn node(&self, id: usize) -> Option<&NodeProfile> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+}
+
+/// Format a nanosecond count with a unit that keeps 3–4 significant digits.
+fn fmt_ns(ns: u64) -> String {
+    match ns {
+        0..=999 => format!("{ns}ns"),
+        1_000..=999_999 => format!("{:.1}µs", ns as f64 / 1e3),
+        1_000_000..=999_999_999 => format!("{:.2}ms", ns as f64 / 1e6),
+        _ => format!("{:.2}s", ns as f64 / 1e9),
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "profile over {} ticks", self.ticks)?;
+        writeln!(
+            f,
+            "{:>5}  {:<28} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "id", "node", "samples", "incl p50", "incl p99", "incl max", "excl mean", "excl p99"
+        )?;
+        for n in &self.nodes {
+            let label = n.label.as_deref().unwrap_or("");
+            let label: String = if label.chars().count() > 28 {
+                label.chars().take(27).chain(std::iter::once('…')).collect()
+            } else {
+                label.to_string()
+            };
+            writeln!(
+                f,
+                "{:>5}  {:<28} {:>8} {:>10} {:>10} {:>10} {:>10} {:>10}",
+                n.id,
+                label,
+                n.samples,
+                fmt_ns(n.inclusive.p50_ns),
+                fmt_ns(n.inclusive.p99_ns),
+                fmt_ns(n.inclusive.max_ns),
+                fmt_ns(n.exclusive.mean_ns),
+                fmt_ns(n.exclusive.p99_ns),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Histogram;
+    use std::time::Duration;
+
+    #[test]
+    fn bucket_bounds_contain_their_values() {
+        for ns in (0..5_000u64).chain([1 << 20, (1 << 20) + 12_345, u64::MAX / 2]) {
+            let idx = Histogram::bucket_of(ns);
+            assert!(Histogram::upper_bound(idx) >= ns, "{ns} above bucket {idx}");
+            if idx > 0 {
+                assert!(Histogram::upper_bound(idx - 1) < ns, "{ns} fits bucket {}", idx - 1);
+            }
+        }
+    }
+
+    #[test]
+    fn percentiles_are_within_bucket_error() {
+        let mut h = Histogram::default();
+        for us in 1..=100u64 {
+            h.record(Duration::from_micros(us));
+        }
+        let p50 = h.percentile(0.5) as f64;
+        let p99 = h.percentile(0.99) as f64;
+        assert!((50_000.0..=62_500.0).contains(&p50), "p50 = {p50}");
+        assert!((99_000.0..=100_000.0).contains(&p99), "p99 = {p99}");
+        assert_eq!(h.max_ns, 100_000);
+        assert_eq!(h.stats().mean_ns, 50_500);
+    }
+}
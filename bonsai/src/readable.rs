@@ -0,0 +1,533 @@
+//! A readable serde representation of [`Behavior`], for trees kept in YAML,
+//! TOML or RON files and edited by hand.
+//!
+//! `Behavior`'s own `Serialize`/`Deserialize` produce serde's default
+//! externally tagged tuples, e.g. `{"While": ["WaitForever", [..]]}`. That
+//! format stays as it is for compatibility. Wrapping a tree in [`Readable`]
+//! (or tagging a field with `#[serde(with = "bonsai_bt::readable")]`) uses a
+//! map per node instead, tagged by `type`, with the node keywords of the
+//! [text DSL](crate::dsl):
+//!
+//! ```yaml
+//! type: while
+//! condition: { type: wait_forever }
+//! body:
+//!   - type: sequence
+//!     children:
+//!       - { type: action, action: Circling }
+//!       - { type: wait, seconds: 0.5 }
+//!       - type: if
+//!         condition: { type: action, action: Ready }
+//!         then: { type: invert, child: { type: action, action: Go } }
+//!         else: { type: reactive_select, children: [] }
+//! ```
+//!
+//! | `type`                                                        | fields                      |
+//! |---------------------------------------------------------------|-----------------------------|
+//! | `action`                                                      | `action`                    |
+//! | `wait`                                                        | `seconds`                   |
+//! | `wait_forever`                                                |                             |
+//! | `invert`, `always_succeed`                                    | `child`                     |
+//! | `sequence`, `select`, `reactive_sequence`, `reactive_select`, `when_all`, `when_any`, `after`, `race` | `children` |
+//! | `while`, `while_all`                                          | `condition`, `body`         |
+//! | `if`                                                          | `condition`, `then`, `else` |
+//!
+//! Any node may also have a `name`, as given with [`Behavior::named`], and a
+//! `meta` map of strings, as set with [`Behavior::meta`].
+//!
+//! The `yaml`, `toml` and `ron` features add `from_*`/`to_*` functions for
+//! each format, using this representation.
+// The whole module is gated on the `serde` feature in [`lib.rs`](crate).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use crate::{Behavior, Float};
+
+/// A [`Behavior`] that serializes in the readable representation. See the
+/// [module docs](self).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Readable<A>(pub Behavior<A>);
+
+impl<A> From<Behavior<A>> for Readable<A> {
+    fn from(behavior: Behavior<A>) -> Self {
+        Readable(behavior)
+    }
+}
+
+impl<A: Serialize> Serialize for Readable<A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, A: Deserialize<'de>> Deserialize<'de> for Readable<A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Readable)
+    }
+}
+
+/// Serialize `behavior` in the readable representation, for
+/// `#[serde(with = "bonsai_bt::readable")]`.
+pub fn serialize<A: Serialize, S: Serializer>(behavior: &Behavior<A>, serializer: S) -> Result<S::Ok, S::Error> {
+    NodeRef::from(behavior).serialize(serializer)
+}
+
+/// Deserialize a behavior from the readable representation, for
+/// `#[serde(with = "bonsai_bt::readable")]`.
+pub fn deserialize<'de, A: Deserialize<'de>, D: Deserializer<'de>>(deserializer: D) -> Result<Behavior<A>, D::Error> {
+    deserializer.deserialize_struct("Node", FIELDS, NodeVisitor(PhantomData))
+}
+
+/// Borrowing mirror of [`Behavior`] with the readable layout: the fields of
+/// every node type, each left out unless the node has it.
+#[derive(serde::Serialize)]
+#[serde(bound = "A: Serialize")]
+struct NodeRef<'a, A> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    action: Option<&'a A>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    seconds: Option<Float>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    child: Option<Box<NodeRef<'a, A>>>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    children: Option<Vec<NodeRef<'a, A>>>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    condition: Option<Box<NodeRef<'a, A>>>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    body: Option<Vec<NodeRef<'a, A>>>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    then: Option<Box<NodeRef<'a, A>>>,
+    #[serde(rename = "else", skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    otherwise: Option<Box<NodeRef<'a, A>>>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "present")]
+    meta: Option<BTreeMap<String, String>>,
+}
+
+/// Write a field `NodeRef` has, as its value rather than as an `Option`
+/// (which RON would write as `Some(..)`).
+fn present<T: Serialize, S: Serializer>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error> {
+    value.as_ref().expect("absent fields are skipped").serialize(serializer)
+}
+
+impl<'a, A> From<&'a Behavior<A>> for NodeRef<'a, A> {
+    fn from(behavior: &'a Behavior<A>) -> Self {
+        use Behavior::*;
+        let one = |b: &'a Behavior<A>| Some(Box::new(NodeRef::from(b)));
+        let all = |bs: &'a [Behavior<A>]| Some(bs.iter().map(NodeRef::from).collect());
+        let mut node = NodeRef {
+            kind: "",
+            action: None,
+            seconds: None,
+            child: None,
+            children: None,
+            condition: None,
+            body: None,
+            then: None,
+            otherwise: None,
+            name: behavior.name(),
+            meta: Some(behavior.metadata()).filter(|meta| !meta.is_empty()),
+        };
+        node.kind = match behavior.unnamed() {
+            Action(action) => {
+                node.action = Some(action);
+                "action"
+            }
+            Wait(seconds) => {
+                node.seconds = Some(*seconds);
+                "wait"
+            }
+            WaitForever => "wait_forever",
+            Invert(child) | AlwaysSucceed(child) => {
+                node.child = one(child);
+                if matches!(behavior.unnamed(), Invert(_)) {
+                    "invert"
+                } else {
+                    "always_succeed"
+                }
+            }
+            While(cond, body) | WhileAll(cond, body) => {
+                node.condition = one(cond);
+                node.body = all(body);
+                if matches!(behavior.unnamed(), While(..)) {
+                    "while"
+                } else {
+                    "while_all"
+                }
+            }
+            If(cond, ok, ko) => {
+                node.condition = one(cond);
+                node.then = one(ok);
+                node.otherwise = one(ko);
+                "if"
+            }
+            Sequence(xs)
+            | Select(xs)
+            | MemorylessSequence(xs)
+            | MemorylessSelector(xs)
+            | WhenAll(xs)
+            | WhenAny(xs)
+            | After(xs)
+            | Race(xs) => {
+                node.children = all(xs);
+                match behavior.unnamed() {
+                    Sequence(_) => "sequence",
+                    Select(_) => "select",
+                    MemorylessSequence(_) => "reactive_sequence",
+                    MemorylessSelector(_) => "reactive_select",
+                    WhenAll(_) => "when_all",
+                    WhenAny(_) => "when_any",
+                    After(_) => "after",
+                    _ => "race",
+                }
+            }
+            Named(..) | Meta(..) => unreachable!("unnamed() looks through names and metadata"),
+        };
+        node
+    }
+}
+
+/// Node types, by their `type` tag.
+const TYPES: &[&str] = &[
+    "action",
+    "wait",
+    "wait_forever",
+    "invert",
+    "always_succeed",
+    "sequence",
+    "select",
+    "reactive_sequence",
+    "reactive_select",
+    "when_all",
+    "when_any",
+    "after",
+    "race",
+    "while",
+    "while_all",
+    "if",
+];
+
+const FIELDS: &[&str] = &[
+    "type",
+    "action",
+    "seconds",
+    "child",
+    "children",
+    "condition",
+    "body",
+    "then",
+    "else",
+    "name",
+    "meta",
+];
+
+#[derive(serde::Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum Field {
+    Type,
+    Action,
+    Seconds,
+    Child,
+    Children,
+    Condition,
+    Body,
+    Then,
+    Else,
+    Name,
+    Meta,
+}
+
+/// Reads a node field by field.
+///
+/// Serde's derived internally tagged enums buffer the whole map before
+/// looking at `type`, and that buffer can't hold enum values in formats that
+/// spell them specially (YAML `!Tag`s, RON's `Variant(..)`). Every field name
+/// here implies its type regardless of `type`, so fields are read straight
+/// from the input and checked against `type` at the end.
+struct NodeVisitor<A>(PhantomData<A>);
+
+/// The fields of one node as read, before checking them against its type.
+struct Fields<A> {
+    action: Option<A>,
+    seconds: Option<Float>,
+    child: Option<Readable<A>>,
+    children: Option<Vec<Readable<A>>>,
+    condition: Option<Readable<A>>,
+    body: Option<Vec<Readable<A>>>,
+    then: Option<Readable<A>>,
+    otherwise: Option<Readable<A>>,
+    /// Any node type takes a name and metadata.
+    name: Option<String>,
+    meta: Option<BTreeMap<String, String>>,
+}
+
+impl<A> Fields<A> {
+    /// The first field read that no node type in `used` takes.
+    fn unused(&self, used: &[&str]) -> Option<&'static str> {
+        [
+            ("action", self.action.is_some()),
+            ("seconds", self.seconds.is_some()),
+            ("child", self.child.is_some()),
+            ("children", self.children.is_some()),
+            ("condition", self.condition.is_some()),
+            ("body", self.body.is_some()),
+            ("then", self.then.is_some()),
+            ("else", self.otherwise.is_some()),
+        ]
+        .into_iter()
+        .find(|(name, present)| *present && !used.contains(name))
+        .map(|(name, _)| name)
+    }
+}
+
+fn set<'de, T: Deserialize<'de>, M: MapAccess<'de>>(
+    slot: &mut Option<T>,
+    name: &'static str,
+    map: &mut M,
+) -> Result<(), M::Error> {
+    if slot.is_some() {
+        return Err(de::Error::duplicate_field(name));
+    }
+    *slot = Some(map.next_value()?);
+    Ok(())
+}
+
+fn take<T, E: de::Error>(slot: Option<T>, name: &'static str) -> Result<T, E> {
+    slot.ok_or_else(|| E::missing_field(name))
+}
+
+fn unwrap_all<A>(nodes: Vec<Readable<A>>) -> Vec<Behavior<A>> {
+    nodes.into_iter().map(|r| r.0).collect()
+}
+
+/// A `while` or `while_all` body, which ticking needs at least one node in.
+fn take_body<A, E: de::Error>(body: Option<Vec<Readable<A>>>) -> Result<Vec<Behavior<A>>, E> {
+    let body = unwrap_all(take(body, "body")?);
+    if body.is_empty() {
+        return Err(E::invalid_length(0, &"at least one body node"));
+    }
+    Ok(body)
+}
+
+impl<'de, A: Deserialize<'de>> Visitor<'de> for NodeVisitor<A> {
+    type Value = Behavior<A>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a behavior node with a `type` field")
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> Result<Behavior<A>, M::Error> {
+        let mut kind: Option<String> = None;
+        let mut fields = Fields {
+            action: None,
+            seconds: None,
+            child: None,
+            children: None,
+            condition: None,
+            body: None,
+            then: None,
+            otherwise: None,
+            name: None,
+            meta: None,
+        };
+        while let Some(field) = map.next_key()? {
+            match field {
+                Field::Type => set(&mut kind, "type", &mut map)?,
+                Field::Action => set(&mut fields.action, "action", &mut map)?,
+                Field::Seconds => set(&mut fields.seconds, "seconds", &mut map)?,
+                Field::Child => set(&mut fields.child, "child", &mut map)?,
+                Field::Children => set(&mut fields.children, "children", &mut map)?,
+                Field::Condition => set(&mut fields.condition, "condition", &mut map)?,
+                Field::Body => set(&mut fields.body, "body", &mut map)?,
+                Field::Then => set(&mut fields.then, "then", &mut map)?,
+                Field::Else => set(&mut fields.otherwise, "else", &mut map)?,
+                Field::Name => set(&mut fields.name, "name", &mut map)?,
+                Field::Meta => set(&mut fields.meta, "meta", &mut map)?,
+            }
+        }
+        let kind = take(kind, "type")?;
+        let used: &[&str] = match kind.as_str() {
+            "action" => &["action"],
+            "wait" => &["seconds"],
+            "wait_forever" => &[],
+            "invert" | "always_succeed" => &["child"],
+            "while" | "while_all" => &["condition", "body"],
+            "if" => &["condition", "then", "else"],
+            _ if TYPES.contains(&kind.as_str()) => &["children"],
+            _ => return Err(de::Error::unknown_variant(&kind, TYPES)),
+        };
+        if let Some(name) = fields.unused(used) {
+            return Err(de::Error::custom(format_args!(
+                "field `{name}` does not apply to type `{kind}`"
+            )));
+        }
+
+        use Behavior::*;
+        let Fields {
+            action,
+            seconds,
+            child,
+            children,
+            condition,
+            body,
+            then,
+            otherwise,
+            name,
+            meta,
+        } = fields;
+        let behavior = match kind.as_str() {
+            "action" => Action(take(action, "action")?),
+            "wait" => Wait(take(seconds, "seconds")?),
+            "wait_forever" => WaitForever,
+            "invert" => Invert(Box::new(take(child, "child")?.0)),
+            "always_succeed" => AlwaysSucceed(Box::new(take(child, "child")?.0)),
+            "while" => While(Box::new(take(condition, "condition")?.0), take_body(body)?),
+            "while_all" => WhileAll(Box::new(take(condition, "condition")?.0), take_body(body)?),
+            "if" => If(
+                Box::new(take(condition, "condition")?.0),
+                Box::new(take(then, "then")?.0),
+                Box::new(take(otherwise, "else")?.0),
+            ),
+            list => {
+                let children = unwrap_all(take(children, "children")?);
+                match list {
+                    "sequence" => Sequence(children),
+                    "select" => Select(children),
+                    "reactive_sequence" => MemorylessSequence(children),
+                    "reactive_select" => MemorylessSelector(children),
+                    "when_all" => WhenAll(children),
+                    "when_any" => WhenAny(children),
+                    "after" => After(children),
+                    "race" => Race(children),
+                    _ => unreachable!("checked against TYPES above"),
+                }
+            }
+        };
+        let behavior = match meta {
+            Some(meta) if !meta.is_empty() => Meta(meta, Box::new(behavior)),
+            _ => behavior,
+        };
+        Ok(match name {
+            Some(name) => behavior.named(name),
+            None => behavior,
+        })
+    }
+}
+
+/// Parse a YAML document in the readable representation.
+#[cfg(feature = "yaml")]
+pub fn from_yaml<A: serde::de::DeserializeOwned>(yaml: &str) -> Result<Behavior<A>, serde_yaml::Error> {
+    serde_yaml::from_str::<Readable<A>>(yaml).map(|r| r.0)
+}
+
+/// Write `behavior` as a YAML document in the readable representation.
+#[cfg(feature = "yaml")]
+pub fn to_yaml<A: Serialize>(behavior: &Behavior<A>) -> Result<String, serde_yaml::Error> {
+    serde_yaml::to_string(&NodeRef::from(behavior))
+}
+
+/// Parse a TOML document in the readable representation; the root node's
+/// fields are the top-level keys.
+#[cfg(feature = "toml")]
+pub fn from_toml<A: serde::de::DeserializeOwned>(toml: &str) -> Result<Behavior<A>, toml::de::Error> {
+    toml::from_str::<Readable<A>>(toml).map(|r| r.0)
+}
+
+/// Write `behavior` as a TOML document in the readable representation.
+#[cfg(feature = "toml")]
+pub fn to_toml<A: Serialize>(behavior: &Behavior<A>) -> Result<String, toml::ser::Error> {
+    toml::to_string_pretty(&NodeRef::from(behavior))
+}
+
+/// Parse a RON document in the readable representation.
+#[cfg(feature = "ron")]
+pub fn from_ron<A: serde::de::DeserializeOwned>(ron: &str) -> Result<Behavior<A>, ron::error::SpannedError> {
+    ron::from_str::<Readable<A>>(ron).map(|r| r.0)
+}
+
+/// Write `behavior` as a RON document in the readable representation.
+#[cfg(feature = "ron")]
+pub fn to_ron<A: Serialize>(behavior: &Behavior<A>) -> Result<String, ron::Error> {
+    ron::ser::to_string_pretty(&NodeRef::from(behavior), ron::ser::PrettyConfig::default())
+}
+
+#[cfg(feature = "schemars")]
+impl<A: schemars::JsonSchema> schemars::JsonSchema for Readable<A> {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        format!("Readable_{}", A::schema_name()).into()
+    }
+
+    fn schema_id() -> std::borrow::Cow<'static, str> {
+        format!("bonsai_bt::readable::Readable<{}>", A::schema_id()).into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        let node = generator.subschema_for::<Self>();
+        let nodes = schemars::json_schema!({ "type": "array", "items": node });
+        let fields = |kind: &str, fields: &[(&str, schemars::Schema)]| {
+            let mut properties = serde_json::Map::new();
+            properties.insert("type".to_string(), serde_json::json!({ "const": kind }));
+            for (name, schema) in fields {
+                properties.insert(name.to_string(), schema.clone().to_value());
+            }
+            properties.insert("name".to_string(), serde_json::json!({ "type": "string" }));
+            properties.insert(
+                "meta".to_string(),
+                serde_json::json!({ "type": "object", "additionalProperties": { "type": "string" } }),
+            );
+            let required: Vec<&str> = std::iter::once("type")
+                .chain(fields.iter().map(|(name, _)| *name))
+                .collect();
+            serde_json::json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false,
+            })
+        };
+        let one_of: Vec<serde_json::Value> = TYPES
+            .iter()
+            .map(|kind| match *kind {
+                "action" => fields(kind, &[("action", generator.subschema_for::<A>())]),
+                "wait" => fields(
+                    kind,
+                    &[("seconds", schemars::json_schema!({ "type": "number", "minimum": 0 }))],
+                ),
+                "wait_forever" => fields(kind, &[]),
+                "invert" | "always_succeed" => fields(kind, &[("child", node.clone())]),
+                "while" | "while_all" => fields(
+                    kind,
+                    &[
+                        ("condition", node.clone()),
+                        (
+                            "body",
+                            schemars::json_schema!({ "type": "array", "items": node, "minItems": 1 }),
+                        ),
+                    ],
+                ),
+                "if" => fields(
+                    kind,
+                    &[
+                        ("condition", node.clone()),
+                        ("then", node.clone()),
+                        ("else", node.clone()),
+                    ],
+                ),
+                _ => fields(kind, &[("children", nodes.clone())]),
+            })
+            .collect();
+        schemars::json_schema!({
+            "title": "Behavior",
+            "description": "A behavior tree node in bonsai-bt's readable representation, tagged by `type`.",
+            "oneOf": one_of,
+        })
+    }
+}
@@ -0,0 +1,522 @@
+//! Record [`TickTrace`] streams to disk and replay them offline in the web
+//! visualizer.
+//!
+//! A recording is a [JSON Lines](https://jsonlines.org/) file: one header line
+//! carrying the [`TreeDefinition`], then one line per tick with the trace, the
+//! tick's `dt` and its wall-clock offset from the start of the recording.
+//! It is plain text so it survives truncation (a crash mid-write only loses
+//! the last line) and can be inspected with `jq`.
+//!
+//! Where recording every tick is too heavy, a [`FlightRecorder`] keeps only
+//! the most recent ticks in memory and writes them out in the same format
+//! when something goes wrong.
+//!
+//! ```no_run
+//! use bonsai_bt::recording::{Recording, ReplayServer};
+//! use bonsai_bt::{Action, BT};
+//!
+//! // On the robot: record every tick.
+//! let mut bt = BT::new(Action("step"), ()).with_recording("run.bonsai.jsonl")?;
+//! // ... tick as usual ...
+//! bt.stop_recording()?;
+//!
+//! // Later, anywhere: replay it at http://127.0.0.1:8911/
+//! let recording = Recording::load("run.bonsai.jsonl")?;
+//! let _server = ReplayServer::bind("127.0.0.1", 8911, &recording)?;
+//! loop {
+//!     std::thread::park();
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::telemetry::{TickTrace, TreeDefinition};
+use crate::telemetry_state::AcceptorGuard;
+use crate::{Float, Status};
+
+/// Value of the header's `format` field.
+const FORMAT: &str = "bonsai-trace";
+/// Current file format version. Readers reject newer versions.
+const VERSION: u32 = 1;
+
+fn unix_ms_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// First line of a recording.
+#[derive(Serialize, Deserialize)]
+struct Header<T> {
+    format: String,
+    version: u32,
+    start_unix_ms: u64,
+    tree: T,
+}
+
+/// One recorded tick.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordedTick {
+    /// Seconds since the recording started, measured when the tick finished.
+    pub t: f64,
+    /// Delta time the tick was driven with; `None` for non-update events.
+    pub dt: Option<Float>,
+    pub trace: TickTrace,
+    /// The blackboard after the tick, if the tree was set up with
+    /// [`BT::with_telemetry_blackboard`](crate::BT::with_telemetry_blackboard).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blackboard: Option<serde_json::Value>,
+}
+
+/// Streams a [`TreeDefinition`] header and [`TickTrace`]s to `W`.
+///
+/// Usually attached to a tree with [`BT::with_recording`](crate::BT::with_recording)
+/// or [`BT::with_recorder`](crate::BT::with_recorder); can also be driven by
+/// hand with traces from [`BT::tick_recording`](crate::BT::tick_recording).
+///
+/// Write errors are sticky: after the first failure the recorder stops
+/// writing and [`finish`](Self::finish) reports the error.
+pub struct TraceRecorder<W: Write> {
+    out: W,
+    start: Instant,
+    frames: u64,
+    error: Option<io::Error>,
+}
+
+impl TraceRecorder<BufWriter<File>> {
+    /// Create (or truncate) the file at `path` and write the header.
+    pub fn create(path: impl AsRef<Path>, definition: &TreeDefinition) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), definition)
+    }
+}
+
+impl<W: Write> TraceRecorder<W> {
+    /// Write the header for `definition` to `out`. The recording clock starts now.
+    pub fn new(mut out: W, definition: &TreeDefinition) -> io::Result<Self> {
+        let header = Header {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            start_unix_ms: unix_ms_now(),
+            tree: definition,
+        };
+        serde_json::to_writer(&mut out, &header)?;
+        out.write_all(b"\n")?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+            frames: 0,
+            error: None,
+        })
+    }
+
+    /// Append one tick, timestamped now.
+    pub fn record(&mut self, trace: &TickTrace, dt: Option<Float>) -> io::Result<()> {
+        self.record_with_blackboard(trace, dt, None)
+    }
+
+    /// Append one tick with a blackboard snapshot, timestamped now.
+    pub fn record_with_blackboard(
+        &mut self,
+        trace: &TickTrace,
+        dt: Option<Float>,
+        blackboard: Option<&serde_json::Value>,
+    ) -> io::Result<()> {
+        if let Some(e) = &self.error {
+            return Err(io::Error::new(
+                e.kind(),
+                "recorder stopped after an earlier write error",
+            ));
+        }
+        let frame = RecordedTickRef {
+            t: self.start.elapsed().as_secs_f64(),
+            dt,
+            trace,
+            blackboard,
+        };
+        let written = serde_json::to_writer(&mut self.out, &frame)
+            .map_err(io::Error::from)
+            .and_then(|()| self.out.write_all(b"\n"));
+        match written {
+            Ok(()) => {
+                self.frames += 1;
+                Ok(())
+            }
+            Err(e) => {
+                self.error = Some(io::Error::new(e.kind(), e.to_string()));
+                Err(e)
+            }
+        }
+    }
+
+    /// Number of ticks written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Flush buffered ticks to the writer, or report the first write error.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(e) = &self.error {
+            return Err(io::Error::new(e.kind(), e.to_string()));
+        }
+        self.out.flush()
+    }
+
+    /// Flush and return the underlying writer, or the first write error.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Erase the writer type so recorders over different sinks can be stored
+    /// alike (as [`BT::with_recorder`](crate::BT::with_recorder) does).
+    pub fn boxed(self) -> TraceRecorder<Box<dyn Write + Send>>
+    where
+        W: Send + 'static,
+    {
+        TraceRecorder {
+            out: Box::new(self.out),
+            start: self.start,
+            frames: self.frames,
+            error: self.error,
+        }
+    }
+}
+
+impl<W: Write> fmt::Debug for TraceRecorder<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TraceRecorder")
+            .field("frames", &self.frames)
+            .field("error", &self.error)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A type-erased recorder shared between a [`BT`](crate::BT) and its clones.
+pub type SharedRecorder = Arc<Mutex<TraceRecorder<Box<dyn Write + Send>>>>;
+
+/// Borrowing twin of [`RecordedTick`] so recording doesn't clone the trace.
+#[derive(Serialize)]
+struct RecordedTickRef<'a> {
+    t: f64,
+    dt: Option<Float>,
+    trace: &'a TickTrace,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blackboard: Option<&'a serde_json::Value>,
+}
+
+/// A recording loaded back from disk.
+#[derive(Clone, Debug)]
+pub struct Recording {
+    /// Wall-clock start of the recording, in milliseconds since the Unix epoch.
+    pub start_unix_ms: u64,
+    /// The serialized [`TreeDefinition`], exactly as the live visualizer
+    /// receives it.
+    pub tree: serde_json::Value,
+    pub ticks: Vec<RecordedTick>,
+}
+
+impl Recording {
+    /// Read a recording from the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Read a recording from `reader`.
+    ///
+    /// A malformed final line — what a crash mid-write leaves behind — is
+    /// ignored; any other malformed line is an `InvalidData` error naming its
+    /// line number.
+    pub fn read<R: BufRead>(reader: R) -> io::Result<Self> {
+        let invalid =
+            |line: usize, msg: String| io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {msg}"));
+        let mut lines = reader.lines().enumerate().peekable();
+
+        let header_line = match lines.next() {
+            Some((_, line)) => line?,
+            None => return Err(invalid(1, "empty recording".into())),
+        };
+        let header: Header<serde_json::Value> =
+            serde_json::from_str(&header_line).map_err(|e| invalid(1, e.to_string()))?;
+        if header.format != FORMAT {
+            return Err(invalid(1, format!("not a bonsai trace (format {:?})", header.format)));
+        }
+        if header.version > VERSION {
+            return Err(invalid(1, format!("unsupported version {}", header.version)));
+        }
+
+        let mut ticks = Vec::new();
+        while let Some((idx, line)) = lines.next() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(tick) => ticks.push(tick),
+                Err(_) if lines.peek().is_none() => break,
+                Err(e) => return Err(invalid(idx + 1, e.to_string())),
+            }
+        }
+        Ok(Self {
+            start_unix_ms: header.start_unix_ms,
+            tree: header.tree,
+            ticks,
+        })
+    }
+
+    /// Seconds between the recording start and the last recorded tick.
+    pub fn duration(&self) -> f64 {
+        self.ticks.last().map_or(0.0, |t| t.t)
+    }
+
+    /// Write the recording to `out` in the on-disk format, so it can be
+    /// read back with [`read`](Self::read).
+    pub fn write<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let header = Header {
+            format: FORMAT.to_string(),
+            version: VERSION,
+            start_unix_ms: self.start_unix_ms,
+            tree: &self.tree,
+        };
+        serde_json::to_writer(&mut out, &header)?;
+        out.write_all(b"\n")?;
+        for tick in &self.ticks {
+            serde_json::to_writer(&mut out, tick)?;
+            out.write_all(b"\n")?;
+        }
+        out.flush()
+    }
+
+    /// Write the recording to the file at `path`, created or truncated.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Bounded in-memory buffer of the most recent ticks, for post-mortem dumps.
+///
+/// Attach it with [`BT::with_flight_recorder`](crate::BT::with_flight_recorder);
+/// every tick then pushes its [`TickTrace`] and, once `capacity` ticks are
+/// held, evicts the oldest. Nothing touches the disk until the buffer is
+/// dumped — on demand with [`dump`](Self::dump), automatically when the root
+/// fails (see [`dump_on_failure`](Self::dump_on_failure)), or from a panic
+/// hook. Dumps use the [`Recording`] format, so they replay in
+/// [`ReplayServer`] like any full recording.
+///
+/// `FlightRecorder` is a cheap handle: clones share one buffer, so keep a
+/// clone around for the panic hook.
+///
+/// ```no_run
+/// use bonsai_bt::recording::FlightRecorder;
+/// use bonsai_bt::{Action, BT};
+///
+/// let recorder = FlightRecorder::new(500).dump_on_failure("failure.bonsai.jsonl");
+/// let mut bt = BT::new(Action("step"), ()).with_flight_recorder(recorder.clone());
+///
+/// let default_hook = std::panic::take_hook();
+/// std::panic::set_hook(Box::new(move |info| {
+///     let _ = recorder.dump_to_file("panic.bonsai.jsonl");
+///     default_hook(info);
+/// }));
+/// // ... tick as usual ...
+/// ```
+#[derive(Clone)]
+pub struct FlightRecorder {
+    inner: Arc<Mutex<Ring>>,
+}
+
+struct Ring {
+    capacity: usize,
+    ticks: VecDeque<RecordedTick>,
+    start: Instant,
+    start_unix_ms: u64,
+    tree: serde_json::Value,
+    dump_on_failure: Option<PathBuf>,
+}
+
+impl FlightRecorder {
+    /// An empty recorder holding at most `capacity` ticks.
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "flight recorder capacity must be non-zero");
+        Self {
+            inner: Arc::new(Mutex::new(Ring {
+                capacity,
+                ticks: VecDeque::with_capacity(capacity),
+                start: Instant::now(),
+                start_unix_ms: unix_ms_now(),
+                tree: serde_json::Value::Null,
+                dump_on_failure: None,
+            })),
+        }
+    }
+
+    /// Dump the buffer to `path` (overwriting it) whenever the root of the
+    /// tree returns [`Failure`](crate::Status::Failure). Dump errors are
+    /// ignored so they never fail the tick.
+    pub fn dump_on_failure(self, path: impl Into<PathBuf>) -> Self {
+        self.lock().dump_on_failure = Some(path.into());
+        self
+    }
+
+    /// Maximum number of ticks held.
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity
+    }
+
+    /// Number of ticks currently held.
+    pub fn len(&self) -> usize {
+        self.lock().ticks.len()
+    }
+
+    /// Whether no tick has been recorded since creation or the last
+    /// [`clear`](Self::clear).
+    pub fn is_empty(&self) -> bool {
+        self.lock().ticks.is_empty()
+    }
+
+    /// Drop every buffered tick.
+    pub fn clear(&self) {
+        self.lock().ticks.clear();
+    }
+
+    /// Copy the buffered ticks out as a [`Recording`], oldest first.
+    pub fn snapshot(&self) -> Recording {
+        let ring = self.lock();
+        Recording {
+            start_unix_ms: ring.start_unix_ms,
+            tree: ring.tree.clone(),
+            ticks: ring.ticks.iter().cloned().collect(),
+        }
+    }
+
+    /// Write the buffered ticks to `out` in the recording format.
+    pub fn dump<W: Write>(&self, out: W) -> io::Result<()> {
+        self.snapshot().write(out)
+    }
+
+    /// Write the buffered ticks to the file at `path`, created or truncated.
+    pub fn dump_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.snapshot().save(path)
+    }
+
+    /// Point the recorder at the tree it observes. Called on attach.
+    pub(crate) fn set_tree(&self, definition: &TreeDefinition) {
+        self.lock().tree = serde_json::to_value(definition).unwrap_or(serde_json::Value::Null);
+    }
+
+    /// Push one tick, evicting the oldest if full, and dump if the root
+    /// failed and a failure path is set.
+    pub(crate) fn push(
+        &self,
+        trace: &TickTrace,
+        dt: Option<Float>,
+        blackboard: Option<serde_json::Value>,
+        root: Status,
+    ) {
+        let mut ring = self.lock();
+        if ring.ticks.len() == ring.capacity {
+            ring.ticks.pop_front();
+        }
+        let t = ring.start.elapsed().as_secs_f64();
+        ring.ticks.push_back(RecordedTick {
+            t,
+            dt,
+            trace: trace.clone(),
+            blackboard,
+        });
+        if root == Status::Failure {
+            if let Some(path) = ring.dump_on_failure.clone() {
+                drop(ring);
+                let _ = self.dump_to_file(path);
+            }
+        }
+    }
+
+    /// Lock the ring, recovering from poisoning: a panic hook must still be
+    /// able to dump after a panic mid-tick.
+    fn lock(&self) -> MutexGuard<'_, Ring> {
+        self.inner.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl fmt::Debug for FlightRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ring = self.lock();
+        f.debug_struct("FlightRecorder")
+            .field("capacity", &ring.capacity)
+            .field("len", &ring.ticks.len())
+            .field("dump_on_failure", &ring.dump_on_failure)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Serves a [`Recording`] to the web visualizer for offline debugging.
+///
+/// Browsers opening `http://{addr}:{port}/` get the same page as the live
+/// visualizer, switched to replay mode: a timeline with play/pause, seek and
+/// playback speed. The whole recording is sent to each client on connect,
+/// so playback runs entirely in the browser.
+///
+/// The server runs on background threads until this handle is dropped.
+pub struct ReplayServer {
+    addr: SocketAddr,
+    // Field order matters: the guard joins the acceptor before `_tx` drops
+    // and lets the (idle) broadcaster exit.
+    _guard: AcceptorGuard,
+    _tx: SyncSender<()>,
+}
+
+impl ReplayServer {
+    /// Bind `{addr}:{port}` and start serving `recording`. Port `0` picks a
+    /// free port; see [`local_addr`](Self::local_addr).
+    ///
+    /// # Errors
+    /// Returns `io::Error` if `{addr}:{port}` cannot be bound.
+    pub fn bind(addr: &str, port: u16, recording: &Recording) -> io::Result<Self> {
+        let listener = TcpListener::bind((addr, port))?;
+        let tree = serde_json::to_string(&recording.tree)?;
+        let replay = serde_json::to_string(&serde_json::json!({
+            "replay": {
+                "start_unix_ms": recording.start_unix_ms,
+                "ticks": recording.ticks,
+            }
+        }))?;
+        // Nothing is ever broadcast; the channel only keeps the broadcaster
+        // parked until this handle drops.
+        let (tx, rx) = sync_channel::<()>(1);
+        let (acceptor, shutdown, bound) = crate::visualizer_server::spawn_server_with_greeting(
+            listener,
+            vec![tree, replay],
+            rx,
+            Default::default(),
+            Default::default(),
+        )?;
+        Ok(Self {
+            addr: bound,
+            _guard: AcceptorGuard::new(shutdown, bound, acceptor),
+            _tx: tx,
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
@@ -0,0 +1,362 @@
+//! Hot reload: swap the behavior of a running [`BT`] for an edited one
+//! without restarting the program.
+//!
+//! [`BT::replace_behavior`] installs the new tree and decides, by
+//! [`ReloadPolicy`], how much of the running state survives. The blackboard
+//! and [`tick_count`](BT::tick_count) always do. With the `visualize`
+//! feature, connected visualizer clients are sent the new tree definition
+//! and redraw, and `/metrics` starts counting the new nodes.
+//!
+//! [`FileWatcher`] is a small polling helper for the usual loop: check the
+//! tree file every so often, and reload when it changes. Parsing is up to
+//! the caller, so any format works — `serde_json`, the [`dsl`](crate::dsl)
+//! or the [`readable`](crate::readable) loaders.
+//!
+//! ```no_run
+//! use bonsai_bt::reload::{FileWatcher, ReloadPolicy};
+//! use bonsai_bt::{dsl, Action, Event, Running, UpdateArgs, BT};
+//!
+//! let mut bt = BT::new(Action("idle".to_string()), ());
+//! let mut watcher = FileWatcher::new("enemy.bt");
+//! loop {
+//!     if let Ok(Some(text)) = watcher.poll() {
+//!         match dsl::from_str::<String>(&text) {
+//!             Ok(tree) => bt.replace_behavior(tree, ReloadPolicy::PreserveMatching),
+//!             Err(err) => eprintln!("enemy.bt: {err}"),
+//!         }
+//!     }
+//!     let e: Event = UpdateArgs { dt: 0.1 }.into();
+//!     bt.tick(&e, &mut |_, _| (Running, 0.0));
+//! #   break;
+//! }
+//! ```
+
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[cfg(feature = "visualize")]
+use crate::path::NodeIndex;
+use crate::state::{Branches, State};
+use crate::tracer::build_node_metas;
+use crate::{Behavior, BT};
+
+/// What [`BT::replace_behavior`] keeps of the running tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReloadPolicy {
+    /// Start the new tree from scratch, as if freshly built.
+    #[default]
+    Restart,
+    /// Keep the progress of every subtree the edit left alone: an unchanged
+    /// subtree keeps its state, a `Sequence` or `Select` whose children up to
+    /// the running one are unchanged keeps its place, a `Wait` keeps its
+    /// elapsed time, and so on. Anything else restarts. A tree that had
+    /// already finished restarts as a whole.
+    PreserveMatching,
+}
+
+impl<A: Clone, B> BT<A, B> {
+    /// Replace the tree's behavior with `new`, keeping running state as
+    /// `policy` allows. Later [`reset_bt`](Self::reset_bt) calls return to
+    /// `new`. A finished tree runs again.
+    ///
+    /// Node ids are preorder positions, so they change with the tree. A
+    /// [`Debugger`](crate::debugger::Debugger)'s breakpoints follow their
+    /// nodes by [`NodePath`](crate::path::NodePath), and are dropped if the
+    /// node is gone; recordings in progress keep the old numbering. `Debug`
+    /// labels the nodes of the visualizer's new tree definition.
+    ///
+    /// # Panics
+    /// Like [`BT::new`], if `new` holds a `While` or `WhileAll` with an empty
+    /// body.
+    pub fn replace_behavior(&mut self, new: Behavior<A>, policy: ReloadPolicy)
+    where
+        A: PartialEq + Debug,
+    {
+        let old = std::mem::replace(&mut self.initial_behavior, new.clone());
+        let state = std::mem::replace(&mut self.state, State::WaitForever);
+        self.state = match policy {
+            ReloadPolicy::PreserveMatching if !self.finished => carry_over(&old, state, new),
+            _ => State::new(new),
+        };
+        self.finished = false;
+        self.node_metas = build_node_metas(&self.initial_behavior);
+        #[cfg(feature = "visualize")]
+        {
+            if let Some(debugger) = &self.telemetry.debugger {
+                debugger.remap_breakpoints(&NodeIndex::new(&old), &NodeIndex::new(&self.initial_behavior));
+            }
+            self.announce_tree();
+        }
+    }
+}
+
+/// The state of `new`, keeping what `state` (the running state of `old`)
+/// has in the parts the two trees share.
+fn carry_over<A: Clone + PartialEq>(old: &Behavior<A>, state: State<A>, new: Behavior<A>) -> State<A> {
+    use Behavior as Bh;
+
+    if *old == new {
+        return state;
+    }
+    // Names and metadata don't run, so only what they wrap matters.
+    if let Bh::Named(_, new) | Bh::Meta(_, new) = new {
+        return carry_over(old, state, *new);
+    }
+    match (old.unnamed(), state, new) {
+        (Bh::Invert(old), State::Invert(child), Bh::Invert(new)) => {
+            State::Invert(Box::new(carry_over(old, *child, *new)))
+        }
+        (Bh::AlwaysSucceed(old), State::AlwaysSucceed(child), Bh::AlwaysSucceed(new)) => {
+            State::AlwaysSucceed(Box::new(carry_over(old, *child, *new)))
+        }
+        (Bh::Wait(_), State::Wait { elapsed_time, .. }, Bh::Wait(time_to_wait)) => State::Wait {
+            time_to_wait,
+            elapsed_time,
+        },
+        (
+            Bh::If(old_condition, old_success, old_failure),
+            State::If {
+                status, current_state, ..
+            },
+            Bh::If(condition, on_success, on_failure),
+        ) => {
+            let current_state = match status {
+                crate::Status::Running => carry_over(old_condition, *current_state, *condition),
+                // The branch taken is only still right if the condition that
+                // chose it is unchanged.
+                _ if **old_condition != *condition => return State::new(Bh::If(condition, on_success, on_failure)),
+                crate::Status::Success => carry_over(old_success, *current_state, (*on_success).clone()),
+                crate::Status::Failure => carry_over(old_failure, *current_state, (*on_failure).clone()),
+            };
+            State::If {
+                on_success,
+                on_failure,
+                status,
+                current_state: Box::new(current_state),
+            }
+        }
+        (
+            Bh::Select(old),
+            State::Select {
+                current_index,
+                current_state,
+                ..
+            },
+            Bh::Select(behaviors),
+        ) => match resume_list(old, current_index, *current_state, &behaviors) {
+            Some(current_state) => State::Select {
+                behaviors,
+                current_index,
+                current_state: Box::new(current_state),
+            },
+            None => State::new(Bh::Select(behaviors)),
+        },
+        (
+            Bh::Sequence(old),
+            State::Sequence {
+                current_index,
+                current_state,
+                ..
+            },
+            Bh::Sequence(behaviors),
+        ) => match resume_list(old, current_index, *current_state, &behaviors) {
+            Some(current_state) => State::Sequence {
+                behaviors,
+                current_index,
+                current_state: Box::new(current_state),
+            },
+            None => State::new(Bh::Sequence(behaviors)),
+        },
+        (
+            Bh::While(old_condition, old_body),
+            State::While {
+                condition_state,
+                loop_body_index,
+                loop_body_state,
+                loop_body_running,
+                ..
+            },
+            Bh::While(condition, loop_body),
+        ) => {
+            let (loop_body_index, loop_body_state, resumed) =
+                resume_body(old_body, loop_body_index, *loop_body_state, &loop_body);
+            State::While {
+                condition_state: Box::new(carry_over(old_condition, *condition_state, *condition)),
+                loop_body,
+                loop_body_index,
+                loop_body_state: Box::new(loop_body_state),
+                loop_body_running: loop_body_running && resumed,
+            }
+        }
+        (
+            Bh::WhileAll(old_condition, old_body),
+            State::WhileAll {
+                condition_state,
+                check_condition,
+                loop_body_index,
+                loop_body_state,
+                ..
+            },
+            Bh::WhileAll(condition, loop_body),
+        ) => {
+            let (loop_body_index, loop_body_state, _) =
+                resume_body(old_body, loop_body_index, *loop_body_state, &loop_body);
+            State::WhileAll {
+                condition_state: Box::new(carry_over(old_condition, *condition_state, *condition)),
+                check_condition,
+                loop_body,
+                loop_body_index,
+                loop_body_state: Box::new(loop_body_state),
+            }
+        }
+        (Bh::WhenAll(old), State::WhenAll(states), Bh::WhenAll(new)) => {
+            State::WhenAll(resume_parallel(old, states, new))
+        }
+        (Bh::WhenAny(old), State::WhenAny(states), Bh::WhenAny(new)) => {
+            State::WhenAny(resume_parallel(old, states, new))
+        }
+        (Bh::Race(old), State::Race(states), Bh::Race(new)) => State::Race(resume_parallel(old, states, new)),
+        (
+            Bh::After(old),
+            State::After {
+                next_success_index,
+                states,
+                mut entered,
+            },
+            Bh::After(new),
+        ) => {
+            // Children before `next_success_index` have already succeeded in
+            // order; that only still holds if they are unchanged.
+            let next_success_index =
+                if next_success_index <= new.len() && old[..next_success_index] == new[..next_success_index] {
+                    next_success_index
+                } else {
+                    0
+                };
+            entered.resize(states.len(), false);
+            let mut old_states = old.iter().zip(states.into_iter().zip(entered));
+            let (states, entered) = new
+                .into_iter()
+                .enumerate()
+                .map(|(j, new)| match old_states.next() {
+                    Some((old, (state, entered))) if j >= next_success_index || *old == new => {
+                        (carry_over(old, state, new), entered)
+                    }
+                    _ => (State::new(new), false),
+                })
+                .unzip();
+            State::After {
+                next_success_index,
+                states,
+                entered,
+            }
+        }
+        // Memoryless nodes re-walk their children every tick, and a node
+        // whose type changed has nothing to keep.
+        (_, _, new) => State::new(new),
+    }
+}
+
+/// The state of the child at `index` of a `Sequence` or `Select`, if the
+/// children before it, and so the path that led there, are unchanged.
+fn resume_list<A: Clone + PartialEq>(
+    old: &[Behavior<A>],
+    index: usize,
+    state: State<A>,
+    new: &[Behavior<A>],
+) -> Option<State<A>> {
+    if index >= new.len() || index >= old.len() || old[..index] != new[..index] {
+        return None;
+    }
+    Some(carry_over(&old[index], state, new[index].clone()))
+}
+
+/// Like [`resume_list`], for a loop body, which restarts at its first child.
+/// Also returns whether the child was resumed rather than restarted.
+fn resume_body<A: Clone + PartialEq>(
+    old: &[Behavior<A>],
+    index: usize,
+    state: State<A>,
+    new: &[Behavior<A>],
+) -> (usize, State<A>, bool) {
+    match resume_list(old, index, state, new) {
+        Some(state) => (index, state, true),
+        None => (0, State::new(new[0].clone()), false),
+    }
+}
+
+/// Match parallel children by position. A finished child (`None`) stays
+/// finished only if it is unchanged, and a child carried over keeps whether
+/// it has been entered.
+fn resume_parallel<A: Clone + PartialEq>(
+    old: &[Behavior<A>],
+    branches: Branches<A>,
+    new: Vec<Behavior<A>>,
+) -> Branches<A> {
+    let mut old_states = old.iter().zip(branches.states.into_iter().zip(branches.entered));
+    let (states, entered) = new
+        .into_iter()
+        .map(|new| match old_states.next() {
+            Some((old, (Some(state), entered))) => (Some(carry_over(old, state, new)), entered),
+            Some((old, (None, _))) if *old == new => (None, false),
+            _ => (Some(State::new(new)), false),
+        })
+        .unzip();
+    Branches { states, entered }
+}
+
+/// Polls a file for changes by its modification time and length.
+///
+/// Polling needs no extra dependencies or threads and copes with editors
+/// that save by writing a new file and renaming it over the old one.
+#[derive(Debug)]
+pub struct FileWatcher {
+    path: PathBuf,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl FileWatcher {
+    /// Watch `path`. Its current contents count as seen, so the first
+    /// [`poll`](Self::poll) only reports a later change.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let stamp = stamp(&path).ok();
+        Self { path, stamp }
+    }
+
+    /// The watched path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The file's contents if it changed since the last call, otherwise
+    /// `None`. A missing file is `None` too, as it briefly is while some
+    /// editors save; the file is reported again once it reappears.
+    ///
+    /// # Errors
+    /// Any I/O error other than [`io::ErrorKind::NotFound`].
+    pub fn poll(&mut self) -> io::Result<Option<String>> {
+        let current = match stamp(&self.path) {
+            Ok(current) => current,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.stamp = None;
+                return Ok(None);
+            }
+            Err(err) => return Err(err),
+        };
+        if self.stamp == Some(current) {
+            return Ok(None);
+        }
+        let text = fs::read_to_string(&self.path)?;
+        self.stamp = Some(current);
+        Ok(Some(text))
+    }
+}
+
+fn stamp(path: &Path) -> io::Result<(SystemTime, u64)> {
+    let metadata = fs::metadata(path)?;
+    Ok((metadata.modified()?, metadata.len()))
+}
@@ -0,0 +1,315 @@
+//! Export a tree and a recorded run as one self-contained file, for attaching
+//! to a code review or bug report.
+//!
+//! A [`Report`] renders to either of:
+//!
+//! - **HTML** ([`to_html`](Report::to_html)): the web visualizer page with the
+//!   tree and every tick inlined, opening straight into replay mode — no
+//!   server needed. In place of the d3 the live visualizer loads from its
+//!   CDN, the page carries a minimal stand-in, so it works offline too.
+//! - **SVG** ([`to_svg`](Report::to_svg)): a static timeline with one row per
+//!   node, in tree order, and one column per tick, colored by the status the
+//!   node returned. Blank cells are ticks in which the node wasn't visited.
+//!
+//! ```no_run
+//! use bonsai_bt::recording::Recording;
+//! use bonsai_bt::report::Report;
+//!
+//! let run = Recording::load("run.bonsai.jsonl")?;
+//! let report = Report::from_recording(&run).title("docking: retries after bump");
+//! report.save_html("docking.html")?;
+//! report.save_svg("docking.svg")?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::recording::{RecordedTick, Recording};
+use crate::telemetry::{TickTrace, TreeDefinition, VISUALIZER_HTML};
+use crate::Status;
+
+/// The d3 `<script>` of the visualizer page, and the stand-in replacing it.
+const D3_CDN: &str = "<script src=\"https://d3js.org/d3.v7.min.js\"></script>";
+const D3_LITE: &str = include_str!("d3_lite.js");
+
+/// Width of one tick column in the SVG timeline.
+const CELL_W: usize = 8;
+const ROW_H: usize = 18;
+/// Indentation per tree level in the SVG's node column.
+const INDENT: usize = 12;
+/// Approximate advance of one monospace character at the SVG's font size.
+const CHAR_W: usize = 7;
+/// Labels longer than this are truncated, as in the live visualizer.
+const LABEL_MAX: usize = 30;
+/// Columns between two tick-id labels on the SVG's axis.
+const AXIS_EVERY: usize = 10;
+const MARGIN: usize = 12;
+const HEADER_H: usize = 44;
+
+const BG: &str = "#1a1a1a";
+const FG: &str = "#ddd";
+const FG_DIM: &str = "#888";
+const STRIPE: &str = "#202020";
+
+fn status_color(status: Status) -> &'static str {
+    match status {
+        Status::Running => "#d4a017",
+        Status::Success => "#28a745",
+        Status::Failure => "#c0392b",
+    }
+}
+
+/// A tree and a run of its ticks, ready to export. See the
+/// [module docs](self).
+#[derive(Clone, Debug)]
+pub struct Report {
+    title: String,
+    recording: Recording,
+}
+
+/// One row of the SVG timeline.
+struct Row {
+    id: usize,
+    depth: usize,
+    label: String,
+    node_type: String,
+}
+
+impl Report {
+    /// A report of `traces`, in tick order, over the tree `definition`.
+    ///
+    /// Traces carry no timing, so the HTML replay spaces them one second
+    /// apart at 1× speed.
+    pub fn new(definition: &TreeDefinition, traces: impl IntoIterator<Item = TickTrace>) -> Self {
+        let ticks = traces
+            .into_iter()
+            .enumerate()
+            .map(|(i, trace)| RecordedTick {
+                t: i as f64,
+                dt: None,
+                trace,
+                blackboard: None,
+            })
+            .collect();
+        Self::from_recording(&Recording {
+            start_unix_ms: 0,
+            tree: serde_json::to_value(definition).unwrap_or(Value::Null),
+            ticks,
+        })
+    }
+
+    /// A report of a recording, keeping its timing and any blackboard
+    /// snapshots for the HTML replay.
+    pub fn from_recording(recording: &Recording) -> Self {
+        Self {
+            title: "bonsai-bt report".to_string(),
+            recording: recording.clone(),
+        }
+    }
+
+    /// Set the title shown in the browser tab and atop the SVG.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// The report as a standalone HTML page.
+    pub fn to_html(&self) -> String {
+        let frames = json!([
+            self.recording.tree,
+            {
+                "replay": {
+                    "start_unix_ms": self.recording.start_unix_ms,
+                    "ticks": self.recording.ticks,
+                }
+            }
+        ]);
+        // `</` can't appear inside a <script>; in JSON it only occurs within
+        // strings, where `<\/` is an equivalent escape.
+        let frames = frames.to_string().replace("</", "<\\/");
+        let inline = format!("<body>\n<script id=\"bonsai-report\" type=\"application/json\">{frames}</script>\n");
+        VISUALIZER_HTML
+            .replacen(D3_CDN, &format!("<script>\n{D3_LITE}</script>"), 1)
+            .replacen(
+                "<title>bonsai-bt visualizer</title>",
+                &format!("<title>{}</title>", escape(&self.title)),
+                1,
+            )
+            .replacen("<body>\n", &inline, 1)
+    }
+
+    /// The report as a static SVG timeline.
+    pub fn to_svg(&self) -> String {
+        let mut rows = Vec::new();
+        flatten(&self.recording.tree["root"], 0, &mut rows);
+        let ticks = &self.recording.ticks;
+
+        let label_w = rows
+            .iter()
+            .map(|r| r.depth * INDENT + r.label.chars().count() * CHAR_W)
+            .max()
+            .unwrap_or(0)
+            + 2 * MARGIN;
+        let width = label_w + ticks.len() * CELL_W + MARGIN;
+        let height = HEADER_H + rows.len() * ROW_H + 2 * ROW_H;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\" font-family=\"ui-monospace, Menlo, monospace\" font-size=\"12\">"
+        );
+        let _ = writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"{BG}\"/>");
+        let summary = match (ticks.first(), ticks.last()) {
+            (Some(first), Some(last)) => {
+                format!("{} ticks ({}–{})", ticks.len(), first.trace.tick_id, last.trace.tick_id)
+            }
+            _ => "no ticks".to_string(),
+        };
+        let _ = writeln!(
+            svg,
+            "<text x=\"{MARGIN}\" y=\"18\" fill=\"{FG}\" font-weight=\"bold\">{}</text>\
+             <text x=\"{MARGIN}\" y=\"34\" fill=\"{FG_DIM}\">{summary}</text>",
+            escape(&self.title)
+        );
+        for (col, tick) in ticks.iter().enumerate().step_by(AXIS_EVERY) {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" fill=\"{FG_DIM}\" font-size=\"10\">{}</text>",
+                label_w + col * CELL_W,
+                HEADER_H - 2,
+                tick.trace.tick_id
+            );
+        }
+
+        for (i, row) in rows.iter().enumerate() {
+            let y = HEADER_H + i * ROW_H;
+            if i % 2 == 1 {
+                let _ = writeln!(
+                    svg,
+                    "<rect x=\"0\" y=\"{y}\" width=\"{width}\" height=\"{ROW_H}\" fill=\"{STRIPE}\"/>"
+                );
+            }
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" fill=\"{FG}\"><title>#{} {}</title>{}</text>",
+                MARGIN + row.depth * INDENT,
+                y + ROW_H - 5,
+                row.id,
+                escape(&row.node_type),
+                escape(&row.label)
+            );
+            // One rect per run of ticks with the same status keeps long,
+            // mostly-steady runs small.
+            let mut col = 0;
+            while col < ticks.len() {
+                let Some(&status) = ticks[col].trace.states.get(&row.id) else {
+                    col += 1;
+                    continue;
+                };
+                let run = ticks[col..]
+                    .iter()
+                    .take_while(|t| t.trace.states.get(&row.id) == Some(&status))
+                    .count();
+                let (first, last) = (ticks[col].trace.tick_id, ticks[col + run - 1].trace.tick_id);
+                let _ = writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\">\
+                     <title>{}: {status:?}, ticks {first}–{last}</title></rect>",
+                    label_w + col * CELL_W,
+                    y + 2,
+                    run * CELL_W,
+                    ROW_H - 4,
+                    status_color(status),
+                    escape(&row.label)
+                );
+                col += run;
+            }
+        }
+
+        let y = HEADER_H + rows.len() * ROW_H + ROW_H;
+        let mut x = MARGIN;
+        for status in [Status::Running, Status::Success, Status::Failure] {
+            let name = format!("{status:?}");
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{}\" width=\"10\" height=\"10\" fill=\"{}\"/>\
+                 <text x=\"{}\" y=\"{}\" fill=\"{FG_DIM}\">{name}</text>",
+                y - 9,
+                status_color(status),
+                x + 14,
+                y
+            );
+            x += 14 + name.len() * CHAR_W + 16;
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Write the HTML page to `out`.
+    pub fn write_html<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(self.to_html().as_bytes())?;
+        out.flush()
+    }
+
+    /// Write the SVG timeline to `out`.
+    pub fn write_svg<W: Write>(&self, mut out: W) -> io::Result<()> {
+        out.write_all(self.to_svg().as_bytes())?;
+        out.flush()
+    }
+
+    /// Write the HTML page to the file at `path`, created or truncated.
+    pub fn save_html(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_html(BufWriter::new(File::create(path)?))
+    }
+
+    /// Write the SVG timeline to the file at `path`, created or truncated.
+    pub fn save_svg(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_svg(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Flatten the serialized `TreeDefinition` into rows, in preorder.
+fn flatten(node: &Value, depth: usize, out: &mut Vec<Row>) {
+    let Some(id) = node["id"].as_u64() else {
+        return;
+    };
+    let node_type = node["node_type"].as_str().unwrap_or("Node").to_string();
+    let label = node["label"].as_str().unwrap_or(&node_type);
+    let label = if label.chars().count() > LABEL_MAX {
+        label.chars().take(LABEL_MAX - 1).chain(['…']).collect()
+    } else {
+        label.to_string()
+    };
+    out.push(Row {
+        id: id as usize,
+        depth,
+        label,
+        node_type,
+    });
+    if let Some(children) = node["children"].as_array() {
+        for child in children {
+            flatten(child, depth + 1, out);
+        }
+    }
+}
+
+/// Escape text for XML/HTML element content.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
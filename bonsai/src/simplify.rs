@@ -0,0 +1,120 @@
+//! Semantics-preserving rewrites for [`Behavior`], for trees generated by
+//! tools or assembled from parts, which tend to carry redundant structure.
+
+use crate::Behavior;
+
+impl<A> Behavior<A> {
+    /// Remove redundant structure, bottom-up:
+    ///
+    /// - a `Sequence` or `Select` with a single child becomes that child;
+    /// - a `Sequence` directly inside a `Sequence` is spliced into it, and
+    ///   likewise for `Select` and for the `.memory(false)` variants;
+    /// - `Invert(Invert(x))` becomes `x`;
+    /// - `AlwaysSucceed(AlwaysSucceed(x))` and `AlwaysSucceed(Invert(x))`
+    ///   become `AlwaysSucceed(x)`.
+    ///
+    /// Names and metadata stay on the nodes they were given to, and such a
+    /// node is never spliced into its parent.
+    ///
+    /// The simplified tree calls the same actions in the same order, and
+    /// every tick ends with the same status. A tick that finishes also
+    /// reports the same remaining time; while the tree is still `Running`,
+    /// the reported time may differ, since a one-child `Sequence` reports 0
+    /// where its child may not. The smaller tree takes less memory to run
+    /// and fewer nested calls per tick. Node ids change, so traces recorded
+    /// against one tree don't apply to the other.
+    ///
+    /// ```
+    /// use bonsai_bt::{Action, AlwaysSucceed, Invert, Sequence};
+    ///
+    /// let tree = Sequence(vec![
+    ///     Sequence(vec![Action("a"), Action("b")]),
+    ///     Invert(Box::new(Invert(Box::new(Action("c"))))),
+    ///     AlwaysSucceed(Box::new(Sequence(vec![Action("d")]))),
+    /// ]);
+    /// assert_eq!(
+    ///     tree.simplify(),
+    ///     Sequence(vec![Action("a"), Action("b"), Action("c"), AlwaysSucceed(Box::new(Action("d")))])
+    /// );
+    /// ```
+    #[must_use]
+    pub fn simplify(self) -> Self {
+        use Behavior::*;
+
+        let simplify_all = |children: Vec<Behavior<A>>| children.into_iter().map(Behavior::simplify);
+        match self {
+            Action(_) | Wait(_) | WaitForever => self,
+            Invert(child) => match child.simplify() {
+                Invert(grandchild) => *grandchild,
+                child => Invert(Box::new(child)),
+            },
+            AlwaysSucceed(child) => {
+                let mut child = child.simplify();
+                // Whatever these decorators did to a finished child's status,
+                // `AlwaysSucceed` turns it into `Success`.
+                while let AlwaysSucceed(inner) | Invert(inner) = child {
+                    child = *inner;
+                }
+                AlwaysSucceed(Box::new(child))
+            }
+            If(condition, on_success, on_failure) => If(
+                Box::new(condition.simplify()),
+                Box::new(on_success.simplify()),
+                Box::new(on_failure.simplify()),
+            ),
+            Sequence(children) => unwrap_single(Sequence(splice(simplify_all(children), |child| match child {
+                Sequence(grandchildren) if !grandchildren.is_empty() => Ok(grandchildren),
+                other => Err(other),
+            }))),
+            Select(children) => unwrap_single(Select(splice(simplify_all(children), |child| match child {
+                Select(grandchildren) if !grandchildren.is_empty() => Ok(grandchildren),
+                other => Err(other),
+            }))),
+            // A memoryless node restarts its children every tick, so it can't
+            // stand in for a single child that keeps state; only splicing is
+            // safe, as a nested memoryless node restarts its own children too.
+            MemorylessSequence(children) => MemorylessSequence(splice(simplify_all(children), |child| match child {
+                MemorylessSequence(grandchildren) => Ok(grandchildren),
+                other => Err(other),
+            })),
+            MemorylessSelector(children) => MemorylessSelector(splice(simplify_all(children), |child| match child {
+                MemorylessSelector(grandchildren) => Ok(grandchildren),
+                other => Err(other),
+            })),
+            While(condition, body) => While(Box::new(condition.simplify()), simplify_all(body).collect()),
+            WhileAll(condition, body) => WhileAll(Box::new(condition.simplify()), simplify_all(body).collect()),
+            WhenAll(children) => WhenAll(simplify_all(children).collect()),
+            WhenAny(children) => WhenAny(simplify_all(children).collect()),
+            After(children) => After(simplify_all(children).collect()),
+            Race(children) => Race(simplify_all(children).collect()),
+            Named(name, child) => Named(name, Box::new(child.simplify())),
+            Meta(meta, child) => Meta(meta, Box::new(child.simplify())),
+        }
+    }
+}
+
+/// Collect `children`, replacing each that `nested` accepts by its own
+/// children.
+fn splice<A>(
+    children: impl Iterator<Item = Behavior<A>>,
+    mut nested: impl FnMut(Behavior<A>) -> Result<Vec<Behavior<A>>, Behavior<A>>,
+) -> Vec<Behavior<A>> {
+    let mut out = Vec::new();
+    for child in children {
+        match nested(child) {
+            Ok(grandchildren) => out.extend(grandchildren),
+            Err(child) => out.push(child),
+        }
+    }
+    out
+}
+
+/// A `Sequence` or `Select` of one child behaves as that child.
+fn unwrap_single<A>(behavior: Behavior<A>) -> Behavior<A> {
+    match behavior {
+        Behavior::Sequence(mut children) | Behavior::Select(mut children) if children.len() == 1 => {
+            children.pop().expect("one child")
+        }
+        other => other,
+    }
+}
@@ -2,7 +2,7 @@ use crate::event::UpdateEvent;
 use crate::sequence::{memoryless_sequence, sequence, MemorylessSequenceArgs, SequenceArgs};
 use crate::state::State::*;
 use crate::status::Status::*;
-use crate::tracer::{first_child_id, next_sibling_id, NodeMeta, Tracer};
+use crate::tracer::{first_child_id, halt_children, next_sibling_id, NodeMeta, Tracer};
 use crate::when_all::{when_all, WhenAllArgs};
 use crate::{Behavior, Float, Status};
 use std::fmt::Debug;
@@ -97,6 +97,10 @@ pub(crate) enum State<A> {
         loop_body_index: usize,
         /// The state of the behavior in the loop body currently being executed.
         loop_body_state: Box<State<A>>,
+        /// Whether that behavior returned `Running` on its last tick, and so
+        /// is halted if the condition terminates.
+        #[cfg_attr(feature = "serde", serde(default))]
+        loop_body_running: bool,
     },
     /// Keeps track of a `WhileAll` behavior.
     WhileAll {
@@ -113,14 +117,12 @@ pub(crate) enum State<A> {
         /// The state of the behavior in the loop body currently being executed.
         loop_body_state: Box<State<A>>,
     },
-    /// Keeps track of a `WhenAll` behavior. As the states finish, they are set
-    /// to [`None`].
-    WhenAll(Vec<Option<State<A>>>),
-    /// Keeps track of a `WhenAny` behavior. As the states finish, they are set
-    /// to [`None`].
-    WhenAny(Vec<Option<State<A>>>),
+    /// Keeps track of a `WhenAll` behavior.
+    WhenAll(Branches<A>),
+    /// Keeps track of a `WhenAny` behavior.
+    WhenAny(Branches<A>),
     /// Keeps track of a `Race` behavior.
-    Race(Vec<Option<State<A>>>),
+    Race(Branches<A>),
     /// Keeps track of an `After` behavior.
     After {
         /// The index of the next state that must succeed.
@@ -128,9 +130,56 @@ pub(crate) enum State<A> {
         /// The states for the behaviors currently executing. All the states
         /// before `next_success_index` must have finished with success.
         states: Vec<State<A>>,
+        /// Whether each state has been ticked, and so is halted if the
+        /// `After` fails. Empty in a state saved before this was tracked.
+        #[cfg_attr(feature = "serde", serde(default))]
+        entered: Vec<bool>,
     },
 }
 
+/// The children of a `WhenAll`, `WhenAny` or `Race`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "BranchesRepr<A>"))]
+pub(crate) struct Branches<A> {
+    /// The state of each child. As the states finish, they are set to
+    /// [`None`].
+    pub(crate) states: Vec<Option<State<A>>>,
+    /// Whether each child has been ticked, and so is halted if the node
+    /// terminates while that child is still running.
+    pub(crate) entered: Vec<bool>,
+}
+
+impl<A> Branches<A> {
+    pub(crate) fn new(states: Vec<Option<State<A>>>) -> Self {
+        let entered = vec![false; states.len()];
+        Branches { states, entered }
+    }
+}
+
+/// What a [`Branches`] deserializes from: itself, or the bare list of states
+/// it was saved as before `entered` was tracked.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum BranchesRepr<A> {
+    Branches {
+        states: Vec<Option<State<A>>>,
+        entered: Vec<bool>,
+    },
+    States(Vec<Option<State<A>>>),
+}
+
+#[cfg(feature = "serde")]
+impl<A> From<BranchesRepr<A>> for Branches<A> {
+    fn from(repr: BranchesRepr<A>) -> Self {
+        match repr {
+            BranchesRepr::Branches { states, entered } if entered.len() == states.len() => Branches { states, entered },
+            BranchesRepr::Branches { states, .. } | BranchesRepr::States(states) => Branches::new(states),
+        }
+    }
+}
+
 impl<A: Clone> State<A> {
     /// Creates a state from a behavior.
     ///
@@ -149,6 +198,7 @@ impl<A: Clone> State<A> {
                 elapsed_time: 0.0,
             },
             Behavior::WaitForever => State::WaitForever,
+            Behavior::Named(_, behavior) | Behavior::Meta(_, behavior) => State::new(*behavior),
             Behavior::If(condition, on_success, on_failure) => {
                 let state = State::new(*condition);
                 State::If {
@@ -190,13 +240,21 @@ impl<A: Clone> State<A> {
                     loop_body,
                     loop_body_index: 0,
                     loop_body_state: Box::new(state),
+                    loop_body_running: false,
                 }
             }
-            Behavior::WhenAll(all) => State::WhenAll(all.into_iter().map(|ev| Some(State::new(ev))).collect()),
-            Behavior::WhenAny(any) => State::WhenAny(any.into_iter().map(|ev| Some(State::new(ev))).collect()),
-            Behavior::Race(behaviors) => State::Race(behaviors.into_iter().map(|ev| Some(State::new(ev))).collect()),
+            Behavior::WhenAll(all) => {
+                State::WhenAll(Branches::new(all.into_iter().map(|ev| Some(State::new(ev))).collect()))
+            }
+            Behavior::WhenAny(any) => {
+                State::WhenAny(Branches::new(any.into_iter().map(|ev| Some(State::new(ev))).collect()))
+            }
+            Behavior::Race(behaviors) => State::Race(Branches::new(
+                behaviors.into_iter().map(|ev| Some(State::new(ev))).collect(),
+            )),
             Behavior::After(after_all) => State::After {
                 next_success_index: 0,
+                entered: vec![false; after_all.len()],
                 states: after_all.into_iter().map(State::new).collect(),
             },
             Behavior::WhileAll(condition, loop_body) => {
@@ -244,6 +302,7 @@ impl<A: Clone> State<A> {
         T: Tracer,
     {
         let upd = e.update(|args| Some(args.dt)).unwrap_or(None);
+        tracer.enter(self_id);
 
         // double match statements
         match (upd, self) {
@@ -256,7 +315,7 @@ impl<A: Clone> State<A> {
                     },
                     blackboard,
                 );
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (_, &mut Invert(ref mut cur)) => {
@@ -266,7 +325,7 @@ impl<A: Clone> State<A> {
                     (Failure, dt) => (Success, dt),
                     (Success, dt) => (Failure, dt),
                 };
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (_, &mut AlwaysSucceed(ref mut cur)) => {
@@ -275,7 +334,7 @@ impl<A: Clone> State<A> {
                     (Running, dt) => (Running, dt),
                     (_, dt) => (Success, dt),
                 };
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -293,7 +352,7 @@ impl<A: Clone> State<A> {
                 } else {
                     RUNNING
                 };
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -340,7 +399,7 @@ impl<A: Clone> State<A> {
                         }
                     }
                 };
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -365,7 +424,7 @@ impl<A: Clone> State<A> {
                     metas,
                     tracer,
                 });
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -390,7 +449,7 @@ impl<A: Clone> State<A> {
                     metas,
                     tracer,
                 });
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -412,7 +471,7 @@ impl<A: Clone> State<A> {
                     metas,
                     tracer,
                 });
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -434,7 +493,7 @@ impl<A: Clone> State<A> {
                     metas,
                     tracer,
                 });
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -444,6 +503,7 @@ impl<A: Clone> State<A> {
                     ref loop_body,
                     ref mut loop_body_index,
                     ref mut loop_body_state,
+                    ref mut loop_body_running,
                 },
             ) => {
                 let cond_id = first_child_id::<T>(self_id);
@@ -461,7 +521,12 @@ impl<A: Clone> State<A> {
                 match condition_state.tick(cond_id, metas, e, blackboard, f, tracer) {
                     (Running, _) => {}
                     x => {
-                        tracer.record(self_id, x.0);
+                        // The loop body is abandoned mid-iteration, if the
+                        // current child had started.
+                        if T::IS_RECORDING && *loop_body_running {
+                            tracer.halt(current_body_id);
+                        }
+                        tracer.exit(self_id, x.0, x.1);
                         return x;
                     }
                 };
@@ -476,7 +541,9 @@ impl<A: Clone> State<A> {
                         }
                         _ => e,
                     };
-                    match cur.tick(current_body_id, metas, ev, blackboard, f, tracer) {
+                    let status = cur.tick(current_body_id, metas, ev, blackboard, f, tracer);
+                    *loop_body_running = status.0 == Running;
+                    match status {
                         (Failure, x) => break (Failure, x),
                         (Running, _) => break RUNNING,
                         (Success, new_dt) => {
@@ -504,14 +571,14 @@ impl<A: Clone> State<A> {
                     // Use the same pointer to avoid allocation.
                     **cur = State::new(loop_body[*loop_body_index].clone());
                 };
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
-            (_, &mut WhenAll(ref mut cursors)) => {
+            (_, &mut WhenAll(ref mut branches)) => {
                 let result = when_all(WhenAllArgs {
                     any: false,
                     upd,
-                    cursors,
+                    branches,
                     e,
                     blackboard,
                     f,
@@ -519,14 +586,14 @@ impl<A: Clone> State<A> {
                     metas,
                     tracer,
                 });
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
-            (_, &mut WhenAny(ref mut cursors)) => {
+            (_, &mut WhenAny(ref mut branches)) => {
                 let result = when_all(WhenAllArgs {
                     any: true,
                     upd,
-                    cursors,
+                    branches,
                     e,
                     blackboard,
                     f,
@@ -534,37 +601,63 @@ impl<A: Clone> State<A> {
                     metas,
                     tracer,
                 });
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
-            (_, &mut Race(ref mut cursors)) => {
+            (
+                _,
+                &mut Race(Branches {
+                    ref mut states,
+                    ref mut entered,
+                }),
+            ) => {
                 // return the result of the first child to complete,
                 // regardless of whether it succeeds or fails.
                 let mut child_id = first_child_id::<T>(self_id);
-                for cur in cursors.iter_mut() {
+                let mut winner = None;
+                for (j, cur) in states.iter_mut().enumerate() {
                     let this_id = child_id;
                     child_id = next_sibling_id::<T>(metas, this_id);
                     match *cur {
                         None => {}
                         Some(ref mut state) => match state.tick(this_id, metas, e, blackboard, f, tracer) {
-                            (Running, _) => continue,
-                            (status, dt) => {
-                                tracer.record(self_id, status);
-                                return (status, dt);
+                            (Running, _) => entered[j] = true,
+                            x => {
+                                winner = Some((j, x));
+                                break;
                             }
                         },
                     }
                 }
-                tracer.record(self_id, Running);
-                RUNNING
+                let result = match winner {
+                    Some((j, x)) => {
+                        // Every other branch that has started loses the race.
+                        halt_children(
+                            tracer,
+                            metas,
+                            self_id,
+                            states
+                                .iter()
+                                .zip(&*entered)
+                                .enumerate()
+                                .map(|(k, (c, &e))| k != j && c.is_some() && e),
+                        );
+                        x
+                    }
+                    None => RUNNING,
+                };
+                tracer.exit(self_id, result.0, result.1);
+                result
             }
             (
                 _,
                 &mut After {
                     ref mut next_success_index,
                     ref mut states,
+                    ref mut entered,
                 },
             ) => {
+                entered.resize(states.len(), false);
                 // Get the least delta time left over.
                 let mut min_dt = Float::MAX;
                 let mut child_id = first_child_id::<T>(self_id);
@@ -573,11 +666,13 @@ impl<A: Clone> State<A> {
                         child_id = next_sibling_id::<T>(metas, child_id);
                     }
                 }
+                let mut failed = None;
                 for (j, item) in states.iter_mut().enumerate().skip(*next_success_index) {
                     let this_id = child_id;
                     child_id = next_sibling_id::<T>(metas, this_id);
                     match item.tick(this_id, metas, e, blackboard, f, tracer) {
                         (Running, _) => {
+                            entered[j] = true;
                             min_dt = 0.0;
                         }
                         (Success, new_dt) => {
@@ -588,22 +683,34 @@ impl<A: Clone> State<A> {
                             } else {
                                 // Return least delta time because
                                 // that is when failure is detected.
-                                tracer.record(self_id, Failure);
-                                return (Failure, min_dt.min(new_dt));
+                                failed = Some((j, min_dt.min(new_dt)));
+                                break;
                             }
                         }
                         (Failure, new_dt) => {
-                            tracer.record(self_id, Failure);
-                            return (Failure, new_dt);
+                            failed = Some((j, new_dt));
+                            break;
                         }
                     };
                 }
+                if let Some((j, dt)) = failed {
+                    // Children that had not yet succeeded in order are abandoned.
+                    let first_live = *next_success_index;
+                    halt_children(
+                        tracer,
+                        metas,
+                        self_id,
+                        entered.iter().enumerate().map(|(k, &e)| k >= first_live && k != j && e),
+                    );
+                    tracer.exit(self_id, Failure, dt);
+                    return (Failure, dt);
+                }
                 let result = if *next_success_index == states.len() {
                     (Success, min_dt)
                 } else {
                     RUNNING
                 };
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
             (
@@ -681,13 +788,13 @@ impl<A: Clone> State<A> {
                         }
                     };
                 };
-                tracer.record(self_id, result.0);
+                tracer.exit(self_id, result.0, result.1);
                 result
             }
 
             // WaitForeverState, WaitState
             _ => {
-                tracer.record(self_id, Running);
+                tracer.exit(self_id, Running, 0.0);
                 RUNNING
             }
         }
@@ -0,0 +1,154 @@
+//! In-process tick subscribers: every tick's [`TickTrace`], delivered to
+//! channels and callbacks in the same process — no sockets, no threads, and
+//! no `visualize` feature needed.
+//!
+//! [`BT::subscribe`] returns a bounded [`Receiver`]. Like the visualizer's
+//! broadcaster, a full channel never blocks the tick: the trace is dropped
+//! for that subscriber and counted in
+//! [`dropped_subscriber_traces`](BT::dropped_subscriber_traces). Receivers
+//! that are dropped are unsubscribed on the next tick.
+//!
+//! [`BT::on_tick`] registers a callback instead, run on the ticking thread
+//! right after each tick — keep it short.
+//!
+//! ```
+//! use bonsai_bt::{Action, Event, Running, UpdateArgs, BT};
+//!
+//! let mut bt = BT::new(Action("step"), ());
+//! let overlay = bt.subscribe();
+//! bt.on_tick(|trace| assert_eq!(trace.states.len(), 1));
+//!
+//! let e: Event = UpdateArgs { dt: 0.1 }.into();
+//! bt.tick(&e, &mut |_, _| (Running, 0.0));
+//! assert_eq!(overlay.try_recv().unwrap().tick_id, 1);
+//! ```
+
+use std::fmt;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+
+use crate::tracer::{RecordingTracer, TickTrace, Tracer};
+use crate::{ActionArgs, Float, Status, UpdateEvent, BT};
+
+/// Channel capacity of [`BT::subscribe`], matching the visualizer's.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// Wrapped in a `Mutex` only so `BT` stays `Sync`; it is never contended,
+/// since callbacks run through `&mut self` with `Mutex::get_mut`.
+type Callback = Mutex<Box<dyn FnMut(&TickTrace) + Send>>;
+
+/// The subscribers of one tree.
+///
+/// Not inherited by clones of the `BT`: a clone is a separate run, and its
+/// ticks would otherwise interleave with the original's.
+#[derive(Default)]
+pub(crate) struct Subscribers {
+    channels: Vec<SyncSender<TickTrace>>,
+    callbacks: Vec<Callback>,
+    dropped: u64,
+    /// Reused between ticks when no other path records a trace.
+    buffer: TickTrace,
+}
+
+impl Subscribers {
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.channels.is_empty() && self.callbacks.is_empty()
+    }
+
+    /// Deliver `trace` to every subscriber.
+    pub(crate) fn publish(&mut self, trace: &TickTrace) {
+        let dropped = &mut self.dropped;
+        self.channels.retain(|tx| match tx.try_send(trace.clone()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                *dropped += 1;
+                true
+            }
+            Err(TrySendError::Disconnected(_)) => false,
+        });
+        for callback in &mut self.callbacks {
+            let callback = callback.get_mut().unwrap_or_else(|p| p.into_inner());
+            callback(trace);
+        }
+    }
+}
+
+impl Clone for Subscribers {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl fmt::Debug for Subscribers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscribers")
+            .field("channels", &self.channels.len())
+            .field("callbacks", &self.callbacks.len())
+            .field("dropped", &self.dropped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Clone, B> BT<A, B> {
+    /// Receive the [`TickTrace`] of every following tick, through a channel
+    /// holding up to [`DEFAULT_CAPACITY`] traces. See the
+    /// [module docs](crate::subscriber).
+    pub fn subscribe(&mut self) -> Receiver<TickTrace> {
+        self.subscribe_with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Like [`subscribe`](Self::subscribe), with a channel of `capacity`
+    /// traces. A capacity of `0` only delivers to a receiver blocked in
+    /// `recv` at the moment of the tick.
+    pub fn subscribe_with_capacity(&mut self, capacity: usize) -> Receiver<TickTrace> {
+        let (tx, rx) = sync_channel(capacity);
+        self.subscribers.channels.push(tx);
+        rx
+    }
+
+    /// Call `callback` with the [`TickTrace`] of every following tick, on the
+    /// ticking thread.
+    pub fn on_tick(&mut self, callback: impl FnMut(&TickTrace) + Send + 'static) {
+        self.subscribers.callbacks.push(Mutex::new(Box::new(callback)));
+    }
+
+    /// Number of traces dropped because a subscriber's channel was full,
+    /// summed over subscribers.
+    pub fn dropped_subscriber_traces(&self) -> u64 {
+        self.subscribers.dropped
+    }
+
+    /// Tick with a [`RecordingTracer`] alongside `extra` and publish the
+    /// trace. The subscriber route of [`tick_with_tracer`](Self::tick_with_tracer)
+    /// when no visualizer or recorder is attached.
+    pub(crate) fn tick_subscribed<E, F, T>(&mut self, e: &E, f: &mut F, extra: &mut T) -> Option<(Status, Float)>
+    where
+        E: UpdateEvent,
+        F: FnMut(ActionArgs<E, A>, &mut B) -> (Status, Float),
+        T: Tracer,
+    {
+        if self.finished {
+            return None;
+        }
+        self.tick_count += 1;
+        self.ensure_node_metas();
+        let mut trace = std::mem::take(&mut self.subscribers.buffer);
+        trace.tick_id = self.tick_count;
+        trace.states.clear();
+        let result = {
+            let recording = RecordingTracer {
+                trace: &mut trace,
+                metas: &self.node_metas,
+            };
+            let mut tracer = (recording, extra);
+            self.state.tick(0, &self.node_metas, e, &mut self.bb, f, &mut tracer)
+        };
+        if matches!(result, (Status::Success | Status::Failure, _)) {
+            self.finished = true;
+        }
+        self.subscribers.publish(&trace);
+        self.subscribers.buffer = trace;
+        Some(result)
+    }
+}
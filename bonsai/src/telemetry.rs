@@ -2,39 +2,110 @@
 // The whole module is gated on the `visualize` feature in [`lib.rs`](crate);
 // per-item `#[cfg]` gates are therefore unnecessary inside this file.
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 
 // `NodeMeta` lives in the always-on `crate::tracer` module because
 // `State::tick`'s signature depends on it regardless of the `visualize`
 // feature. Re-exported here so the public path `bonsai_bt::telemetry::NodeMeta`
-// stays valid for downstream code.
-pub use crate::tracer::NodeMeta;
+// stays valid for downstream code. `TickTrace` and `RecordingTracer` moved
+// there too, so in-process subscribers work without this feature.
+pub use crate::tracer::{build_node_metas, NodeMeta, RecordingTracer, TickTrace};
 
+use crate::debugger::DebugStatus;
+use crate::path::NodeIndex;
+use crate::profiler::ProfileReport;
 use crate::tracer::Tracer;
-use crate::{Behavior, Status};
+pub(crate) use crate::tracer::{children_of, classify};
+use crate::wire::StatusVec;
+use crate::{Behavior, Float, Status};
 
-pub struct RecordingTracer<'a> {
-    pub trace: &'a mut TickTrace,
-    pub metas: &'a [NodeMeta],
+/// [`RecordingTracer`] into a [`StatusVec`]; what `BT` itself records with.
+pub(crate) struct PackedTracer<'a> {
+    pub states: &'a mut StatusVec,
 }
 
-impl Tracer for RecordingTracer<'_> {
+impl Tracer for PackedTracer<'_> {
     const IS_RECORDING: bool = true;
     #[inline]
-    fn record(&mut self, id: usize, status: Status) {
-        debug_assert_ne!(id, usize::MAX, "tracer.record called with sentinel id — gating bug");
-        self.trace.states.insert(id, status);
+    fn exit(&mut self, id: usize, status: Status, _dt: Float) {
+        debug_assert_ne!(id, usize::MAX, "tracer.exit called with sentinel id — gating bug");
+        self.states.set(id, status);
     }
 }
 
-/// The per-tick payload: maps each visited node's preorder ID to its returned Status.
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
-pub struct TickTrace {
-    pub tick_id: u64,
-    /// Maps Node ID to its return Status for the current tick.
-    pub states: HashMap<usize, Status>,
+/// One message on the visualizer WebSocket after the [`TreeDefinition`].
+///
+/// Serialized untagged, so a `Tick` frame is byte-for-byte a [`TickTrace`]
+/// and clients tell frames apart by their keys (`tick_id` vs `profile`).
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum TelemetryFrame {
+    /// Node statuses of one tick.
+    Tick(TickTrace),
+    /// Per-node timings published with `BT::publish_profile`.
+    Profile { profile: ProfileReport },
+    /// Debugger state, sent whenever it changes.
+    Debug { debug: DebugStatus },
+    /// Node statuses of one tick in packed form, plus the blackboard once
+    /// `BT::with_telemetry_blackboard` is on. What `BT` sends: cheap to copy
+    /// off the tick path, and serializes exactly like a [`TickTrace`] (with
+    /// an extra `blackboard` key when present). See [`crate::wire`] for the
+    /// compact encodings.
+    Packed {
+        tick_id: u64,
+        states: StatusVec,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        blackboard: Option<BlackboardUpdate>,
+    },
+    /// A new tree definition, sent when `BT::replace_behavior` swaps the
+    /// tree. Serializes exactly like the greeting's [`TreeDefinition`].
+    Tree(TreeDefinition),
+}
+
+/// Blackboard state attached to a tick on the live stream.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlackboardUpdate {
+    /// The whole blackboard, serialized.
+    Snapshot(serde_json::Value),
+    /// An [RFC 7386](https://www.rfc-editor.org/rfc/rfc7386) JSON merge patch
+    /// against the blackboard of the previous tick.
+    Patch(serde_json::Value),
+}
+
+/// The JSON merge patch turning `old` into `new`, or `None` if they are equal.
+///
+/// Objects are diffed key by key (removed keys map to `null`); anything else
+/// is replaced wholesale. A `null` *value* inside an object can't be told
+/// apart from a removal in merge-patch semantics, so clients treat both as
+/// "key absent".
+pub(crate) fn merge_patch_diff(old: &serde_json::Value, new: &serde_json::Value) -> Option<serde_json::Value> {
+    use serde_json::{Map, Value};
+    if old == new {
+        return None;
+    }
+    let (Value::Object(old), Value::Object(new)) = (old, new) else {
+        return Some(new.clone());
+    };
+    let mut patch = Map::new();
+    for (key, value) in new {
+        match old.get(key) {
+            Some(prev) => {
+                if let Some(diff) = merge_patch_diff(prev, value) {
+                    patch.insert(key.clone(), diff);
+                }
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for key in old.keys().filter(|k| !new.contains_key(*k)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    Some(Value::Object(patch))
 }
 
 /// The immutable structure of the tree, sent once upon WebSocket connection.
@@ -49,83 +120,15 @@ pub struct TreeNode {
     pub id: usize,
     pub node_type: &'static str,
     pub label: String,
+    /// The name given with [`Behavior::named`], if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The metadata set with [`Behavior::meta`]; left out when empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub meta: BTreeMap<String, String>,
     pub children: Vec<TreeNode>,
 }
 
-/// Walk `behavior` in DFS preorder and build a flat `Vec<NodeMeta>` indexed by
-/// preorder ID.  The ordering matches `TreeDefinition::traverse` exactly because
-/// both call `children_of`.
-pub fn build_node_metas<A>(behavior: &Behavior<A>) -> Vec<NodeMeta> {
-    let mut metas = Vec::new();
-    fill(behavior, &mut metas);
-    metas
-}
-
-fn fill<A>(b: &Behavior<A>, out: &mut Vec<NodeMeta>) -> usize {
-    let my_idx = out.len();
-    out.push(NodeMeta { subtree_size: 0 }); // placeholder, updated below
-    let mut size = 1;
-    for c in children_of(b) {
-        size += fill(c, out);
-    }
-    out[my_idx].subtree_size = size;
-    size
-}
-
-/// Returns the ordered children of a behavior node.
-///
-/// This is the **single source of truth** for preorder ID assignment order.
-/// `build_node_metas` and `TreeDefinition::traverse` must call this rather
-/// than re-implementing the ordering independently.
-pub(crate) fn children_of<A>(b: &Behavior<A>) -> Vec<&Behavior<A>> {
-    use Behavior::*;
-    match b {
-        Action(_) | Wait(_) | WaitForever => vec![],
-        Invert(c) | AlwaysSucceed(c) => vec![c.as_ref()],
-        // [condition, on_success, on_failure] — must match skip_subtree logic.
-        If(cond, ok, ko) => vec![cond.as_ref(), ok.as_ref(), ko.as_ref()],
-        While(cond, body) | WhileAll(cond, body) => {
-            let mut v = Vec::with_capacity(1 + body.len());
-            v.push(cond.as_ref());
-            v.extend(body.iter());
-            v
-        }
-        Select(xs)
-        | Sequence(xs)
-        | MemorylessSequence(xs)
-        | MemorylessSelector(xs)
-        | WhenAll(xs)
-        | WhenAny(xs)
-        | After(xs)
-        | Race(xs) => xs.iter().collect(),
-    }
-}
-
-/// Returns the static node-type name and an optional dynamic label.
-/// Dynamic label is `Some` only for variants with runtime data worth displaying
-/// (Action debug repr, Wait duration); composites fall back to `node_type`.
-fn classify<A: std::fmt::Debug>(b: &Behavior<A>) -> (&'static str, Option<String>) {
-    use Behavior::*;
-    match b {
-        Action(a) => ("Action", Some(format!("{a:?}"))),
-        Wait(t) => ("Wait", Some(format!("Wait({t:.2}s)"))),
-        WaitForever => ("WaitForever", None),
-        Invert(_) => ("Inverter", None),
-        AlwaysSucceed(_) => ("AlwaysSucceed", None),
-        Select(_) => ("Selector", None),
-        MemorylessSelector(_) => ("MemorylessSelector", None),
-        Sequence(_) => ("Sequence", None),
-        MemorylessSequence(_) => ("MemorylessSequence", None),
-        If(..) => ("If", None),
-        While(..) => ("While", None),
-        WhileAll(..) => ("WhileAll", None),
-        WhenAll(_) => ("WhenAll", None),
-        WhenAny(_) => ("WhenAny", None),
-        After(_) => ("After", None),
-        Race(_) => ("Race", None),
-    }
-}
-
 impl TreeDefinition {
     /// Walk the behavior tree in DFS preorder, assigning stable integer IDs.
     pub fn build<A: std::fmt::Debug>(behavior: &Behavior<A>) -> Self {
@@ -134,6 +137,20 @@ impl TreeDefinition {
         Self { root }
     }
 
+    /// The [`NodeIndex`] of this tree, for looking its nodes up by
+    /// [`NodePath`](crate::path::NodePath).
+    pub fn node_index(&self) -> NodeIndex {
+        fn walk(node: &TreeNode, parent: Option<usize>, out: &mut Vec<(Option<String>, Option<usize>)>) {
+            out.push((node.name.clone(), parent));
+            for child in &node.children {
+                walk(child, Some(node.id), out);
+            }
+        }
+        let mut nodes = Vec::new();
+        walk(&self.root, None, &mut nodes);
+        NodeIndex::from_preorder(nodes)
+    }
+
     pub(crate) fn traverse<A: std::fmt::Debug>(behavior: &Behavior<A>, id_counter: &mut usize) -> TreeNode {
         let id = *id_counter;
         *id_counter += 1;
@@ -146,6 +163,8 @@ impl TreeDefinition {
             id,
             node_type,
             label: label.unwrap_or_else(|| node_type.to_string()),
+            name: behavior.name().map(str::to_string),
+            meta: behavior.metadata(),
             children,
         }
     }
@@ -251,4 +270,19 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn merge_patch_diff_reports_changed_added_and_removed_keys() {
+        use super::merge_patch_diff;
+        use serde_json::json;
+
+        let old = json!({"pos": {"x": 1, "y": 2}, "goal": "dock", "battery": 0.5});
+        let new = json!({"pos": {"x": 1, "y": 3}, "battery": 0.5, "error": "stuck"});
+        assert_eq!(
+            merge_patch_diff(&old, &new),
+            Some(json!({"pos": {"y": 3}, "goal": null, "error": "stuck"}))
+        );
+        assert_eq!(merge_patch_diff(&new, &new), None);
+        assert_eq!(merge_patch_diff(&json!([1]), &json!([1, 2])), Some(json!([1, 2])));
+    }
 }
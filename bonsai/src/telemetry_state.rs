@@ -10,8 +10,11 @@ use std::sync::Arc;
 use std::thread::JoinHandle;
 use std::time::Duration;
 
-use crate::telemetry::TickTrace;
-use crate::tracer::NodeMeta;
+use crate::debugger::Debugger;
+use crate::metrics::TreeMetrics;
+use crate::recording::{FlightRecorder, SharedRecorder};
+use crate::telemetry::{merge_patch_diff, BlackboardUpdate, TelemetryFrame, TickTrace};
+use crate::wire::StatusVec;
 
 /// RAII handle that shuts down the visualizer acceptor thread when dropped.
 ///
@@ -76,15 +79,14 @@ fn wake_addr_for(addr: SocketAddr) -> SocketAddr {
     SocketAddr::new(loopback, addr.port())
 }
 
-#[derive(Clone, Debug, Default)]
-pub(crate) struct TelemetryState {
-    /// Preorder node metadata, computed once at `BT::new`. Used by
-    /// `RecordingTracer` to advance past unvisited subtrees in O(1).
-    pub node_metas: Vec<NodeMeta>,
+/// Generic over the blackboard type only for `blackboard`, the opt-in
+/// snapshot hook; everything else is blackboard-agnostic.
+#[derive(Clone, Debug)]
+pub(crate) struct TelemetryState<B> {
     /// Channel sender for shipping `TickTrace`s to the broadcaster thread.
     /// `None` until [`BT::with_telemetry_at`](crate::BT::with_telemetry_at)
     /// attaches a sender; cleared back to `None` when the broadcaster drops.
-    pub sender: Option<SyncSender<TickTrace>>,
+    pub sender: Option<SyncSender<TelemetryFrame>>,
     /// Shared cleanup handle for the visualizer acceptor thread. Held behind
     /// `Arc` so cloning a telemetry-attached `BT` shares the guard across
     /// clones — only the last drop runs teardown.
@@ -92,20 +94,125 @@ pub(crate) struct TelemetryState {
     /// Number of `TickTrace`s dropped because the channel was full. Reset on
     /// `BT::reset_bt`. Useful for diagnosing slow visualizer clients.
     pub dropped_traces: u64,
-    /// Reusable buffer for the recording trace. Held for the BT's lifetime;
-    /// `tick_recording` clears it on entry, preserving capacity. Avoids one
-    /// `HashMap` allocation per tick on the hot path.
+    /// Reusable packed buffer the tick records node statuses into. Held for
+    /// the BT's lifetime and cleared on entry to each recorded tick; copying
+    /// it to the broadcaster costs `nodes / 4` bytes.
+    pub status_buffer: StatusVec,
+    /// Reusable `TickTrace` for the on-disk and flight recorders, unpacked
+    /// from `status_buffer` only when one of them is attached.
     pub trace_buffer: TickTrace,
+    /// On-disk trace recorder attached with
+    /// [`BT::with_recording`](crate::BT::with_recording). Behind `Arc<Mutex>`
+    /// only so `TelemetryState` stays `Clone`; clones share one file.
+    pub recorder: Option<SharedRecorder>,
+    /// Ring buffer of recent ticks attached with
+    /// [`BT::with_flight_recorder`](crate::BT::with_flight_recorder).
+    pub flight_recorder: Option<FlightRecorder>,
+    /// Counters served on the visualizer's `/metrics` endpoint. Set together
+    /// with `sender`.
+    pub(crate) metrics: Option<Arc<TreeMetrics>>,
+    /// Blackboard snapshot hook installed by
+    /// [`BT::with_telemetry_blackboard`](crate::BT::with_telemetry_blackboard).
+    pub(crate) blackboard: Option<BlackboardTap<B>>,
+    /// Pause/step/breakpoint state, created by
+    /// [`BT::debugger`](crate::BT::debugger) or when the visualizer attaches.
+    pub(crate) debugger: Option<Debugger>,
 }
 
-impl TelemetryState {
-    pub fn new(node_metas: Vec<NodeMeta>) -> Self {
+// Manual impl: deriving would demand `B: Default`, which `BT::new` can't assume.
+impl<B> Default for TelemetryState<B> {
+    fn default() -> Self {
         Self {
-            node_metas,
             sender: None,
             acceptor_guard: None,
             dropped_traces: 0,
+            status_buffer: StatusVec::default(),
             trace_buffer: TickTrace::default(),
+            recorder: None,
+            flight_recorder: None,
+            metrics: None,
+            blackboard: None,
+            debugger: None,
         }
     }
 }
+
+impl<B> TelemetryState<B> {
+    /// Whether any consumer wants per-tick traces, i.e. whether `tick` must
+    /// take the recording path.
+    #[inline(always)]
+    pub fn is_active(&self) -> bool {
+        self.sender.is_some() || self.recorder.is_some() || self.flight_recorder.is_some() || self.debugger.is_some()
+    }
+}
+
+/// Ticks between full blackboard snapshots on the live stream; the ticks in
+/// between carry merge patches. Bounds how long a client that joined
+/// mid-stream waits for a complete view.
+const BLACKBOARD_KEYFRAME_TICKS: u64 = 50;
+
+/// Serializes the blackboard each tick and turns consecutive snapshots into
+/// the live stream's keyframes and patches.
+pub(crate) struct BlackboardTap<B> {
+    snapshot: fn(&B) -> serde_json::Value,
+    /// Last snapshot sent to the broadcaster.
+    last: Option<serde_json::Value>,
+    /// Ticks sent since the last keyframe.
+    since_keyframe: u64,
+}
+
+impl<B> BlackboardTap<B> {
+    pub(crate) fn new(snapshot: fn(&B) -> serde_json::Value) -> Self {
+        Self {
+            snapshot,
+            last: None,
+            since_keyframe: 0,
+        }
+    }
+
+    /// The blackboard as JSON.
+    pub(crate) fn snapshot(&self, bb: &B) -> serde_json::Value {
+        (self.snapshot)(bb)
+    }
+
+    /// Encode `current` for the live stream: a full snapshot on the first
+    /// tick and every [`BLACKBOARD_KEYFRAME_TICKS`], otherwise a merge patch
+    /// against the previous tick (`None` when nothing changed).
+    pub(crate) fn update(&mut self, current: serde_json::Value) -> Option<BlackboardUpdate> {
+        let update = match &self.last {
+            Some(last) if self.since_keyframe < BLACKBOARD_KEYFRAME_TICKS => {
+                self.since_keyframe += 1;
+                merge_patch_diff(last, &current).map(BlackboardUpdate::Patch)
+            }
+            _ => {
+                self.since_keyframe = 1;
+                Some(BlackboardUpdate::Snapshot(current.clone()))
+            }
+        };
+        self.last = Some(current);
+        update
+    }
+
+    /// Forget the last snapshot so the next update is a keyframe.
+    pub(crate) fn restart(&mut self) {
+        self.last = None;
+    }
+}
+
+impl<B> Clone for BlackboardTap<B> {
+    fn clone(&self) -> Self {
+        Self {
+            snapshot: self.snapshot,
+            last: self.last.clone(),
+            since_keyframe: self.since_keyframe,
+        }
+    }
+}
+
+impl<B> std::fmt::Debug for BlackboardTap<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlackboardTap")
+            .field("since_keyframe", &self.since_keyframe)
+            .finish_non_exhaustive()
+    }
+}
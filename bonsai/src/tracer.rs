@@ -1,10 +1,23 @@
-//! Always-compiled telemetry primitives. Lives outside `telemetry.rs` so
+//! Always-compiled tracing primitives. Lives outside `telemetry.rs` so
 //! `State::tick`'s signature carries the same `Tracer`/`NodeMeta` types whether
 //! or not the `visualize` feature is on.
+//!
+//! Implement [`Tracer`] and pass it to [`BT::tick_with_tracer`](crate::BT::tick_with_tracer)
+//! to observe every node the tick visits — for logging, profiling or metrics —
+//! without the `visualize` feature or its threads.
 
-use crate::Status;
+use std::collections::HashMap;
 
-/// Tick-time recording sink, monomorphized into `State::tick`.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Behavior, Float, Status};
+
+/// Tick-time observation sink, monomorphized into `State::tick`.
+///
+/// Node ids are DFS preorder indices into the tree passed to
+/// [`BT::new`](crate::BT::new): the root is `0`, its first child `1`, and so
+/// on. They match the `id` fields of `telemetry::TreeDefinition`.
 ///
 /// `IS_RECORDING` is a const switch — when `false`, the optimizer constant-
 /// folds every `if T::IS_RECORDING { ... }` site to a no-op, allowing the
@@ -13,20 +26,123 @@ use crate::Status;
 /// the entire branch at monomorphization time.
 ///
 /// Implementations should:
-/// - Set `IS_RECORDING = false` only for no-op tracers (use `#[inline(always)]`
-///   on `record` so the call disappears).
-/// - Set `IS_RECORDING = true` for any tracer that actually consumes the
-///   `(id, status)` pair.
+/// - Set `IS_RECORDING = false` only for no-op tracers. The hooks are still
+///   called, but every `id` is `usize::MAX` because id bookkeeping is elided.
+/// - Set `IS_RECORDING = true` for any tracer that actually consumes ids.
+///
+/// All hooks default to no-ops, so a tracer only overrides what it needs.
+///
+/// # Example
+/// ```rust
+/// use bonsai_bt::tracer::Tracer;
+/// use bonsai_bt::{Action, Event, Float, Sequence, Status, Success, UpdateArgs, BT};
+///
+/// #[derive(Default)]
+/// struct Log(Vec<String>);
+///
+/// impl Tracer for Log {
+///     const IS_RECORDING: bool = true;
+///     fn enter(&mut self, id: usize) {
+///         self.0.push(format!("enter {id}"));
+///     }
+///     fn exit(&mut self, id: usize, status: Status, _dt: Float) {
+///         self.0.push(format!("exit {id} {status:?}"));
+///     }
+/// }
+///
+/// let mut bt = BT::new(Sequence(vec![Action(())]), ());
+/// let mut log = Log::default();
+/// let e: Event = UpdateArgs { dt: 0.1 }.into();
+/// bt.tick_with_tracer(&e, &mut |args, _| (Success, args.dt), &mut log);
+/// assert_eq!(log.0, ["enter 0", "enter 1", "exit 1 Success", "exit 0 Success"]);
+/// ```
 pub trait Tracer {
     const IS_RECORDING: bool;
-    fn record(&mut self, id: usize, status: Status);
+
+    /// Called when node `id` is about to be ticked, before any of its children.
+    #[inline(always)]
+    fn enter(&mut self, _id: usize) {}
+
+    /// Called when node `id` returns from its tick with `status` and the
+    /// remaining delta time `dt`. Children always exit before their parent.
+    #[inline(always)]
+    fn exit(&mut self, _id: usize, _status: Status, _dt: Float) {}
+
+    /// Called when a parent abandons child subtree `id` without it having
+    /// returned a terminal status — e.g. the losing branches of a `Race`, the
+    /// remaining children of a short-circuiting `WhenAll`/`WhenAny`/`After`,
+    /// or a `While` body when the condition terminates. The subtree will not
+    /// be ticked again in its current state.
+    #[inline(always)]
+    fn halt(&mut self, _id: usize) {}
 }
 
+/// Tracer that observes nothing. Used by [`BT::tick`](crate::BT::tick) when no
+/// telemetry is attached; compiles to exactly the untraced code.
 pub struct NoopTracer;
 impl Tracer for NoopTracer {
     const IS_RECORDING: bool = false;
+}
+
+impl<T: Tracer + ?Sized> Tracer for &mut T {
+    const IS_RECORDING: bool = T::IS_RECORDING;
+    #[inline(always)]
+    fn enter(&mut self, id: usize) {
+        (**self).enter(id)
+    }
     #[inline(always)]
-    fn record(&mut self, _id: usize, _status: Status) {}
+    fn exit(&mut self, id: usize, status: Status, dt: Float) {
+        (**self).exit(id, status, dt)
+    }
+    #[inline(always)]
+    fn halt(&mut self, id: usize) {
+        (**self).halt(id)
+    }
+}
+
+/// A pair of tracers observes the tick together: every hook is forwarded to
+/// `.0` first, then `.1`. Nest pairs to combine more than two.
+impl<T: Tracer, U: Tracer> Tracer for (T, U) {
+    const IS_RECORDING: bool = T::IS_RECORDING || U::IS_RECORDING;
+    #[inline(always)]
+    fn enter(&mut self, id: usize) {
+        self.0.enter(id);
+        self.1.enter(id);
+    }
+    #[inline(always)]
+    fn exit(&mut self, id: usize, status: Status, dt: Float) {
+        self.0.exit(id, status, dt);
+        self.1.exit(id, status, dt);
+    }
+    #[inline(always)]
+    fn halt(&mut self, id: usize) {
+        self.0.halt(id);
+        self.1.halt(id);
+    }
+}
+
+/// The per-tick payload: maps each visited node's preorder ID to its returned Status.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TickTrace {
+    pub tick_id: u64,
+    /// Maps Node ID to its return Status for the current tick.
+    pub states: HashMap<usize, Status>,
+}
+
+/// Tracer that collects the statuses of one tick into a [`TickTrace`].
+pub struct RecordingTracer<'a> {
+    pub trace: &'a mut TickTrace,
+    pub metas: &'a [NodeMeta],
+}
+
+impl Tracer for RecordingTracer<'_> {
+    const IS_RECORDING: bool = true;
+    #[inline]
+    fn exit(&mut self, id: usize, status: Status, _dt: Float) {
+        debug_assert_ne!(id, usize::MAX, "tracer.exit called with sentinel id — gating bug");
+        self.trace.states.insert(id, status);
+    }
 }
 
 /// Preorder metadata for one node — computed once at `BT::new`,
@@ -37,6 +153,75 @@ pub struct NodeMeta {
     pub subtree_size: usize,
 }
 
+/// Walk `behavior` in DFS preorder and build a flat `Vec<NodeMeta>` indexed by
+/// preorder ID.  The ordering matches `TreeDefinition::traverse` exactly because
+/// both call `children_of`.
+pub fn build_node_metas<A>(behavior: &Behavior<A>) -> Vec<NodeMeta> {
+    let mut metas = Vec::new();
+    fill(behavior, &mut metas);
+    metas
+}
+
+fn fill<A>(b: &Behavior<A>, out: &mut Vec<NodeMeta>) -> usize {
+    let my_idx = out.len();
+    out.push(NodeMeta { subtree_size: 0 }); // placeholder, updated below
+    let mut size = 1;
+    for c in children_of(b) {
+        size += fill(c, out);
+    }
+    out[my_idx].subtree_size = size;
+    size
+}
+
+/// Returns the ordered children of a behavior node.
+///
+/// This is the **single source of truth** for preorder ID assignment order.
+/// `build_node_metas` and `TreeDefinition::traverse` must call this rather
+/// than re-implementing the ordering independently. It is
+/// [`Behavior::children`], which tools outside the crate use too, and which
+/// looks through `Named` and `Meta` wrappers. A wrapper and the node it names share
+/// one id, so a name never shifts one; per-node code looks through it.
+pub(crate) fn children_of<A>(b: &Behavior<A>) -> Vec<&Behavior<A>> {
+    b.children()
+}
+
+/// Returns the static node-type name and an optional dynamic label.
+/// Dynamic label is `Some` only for variants with runtime data worth displaying
+/// (Action debug repr, Wait duration); composites fall back to `node_type`.
+pub(crate) fn classify<A: std::fmt::Debug>(b: &Behavior<A>) -> (&'static str, Option<String>) {
+    use Behavior::*;
+    match b {
+        Action(a) => ("Action", Some(format!("{a:?}"))),
+        Wait(t) => ("Wait", Some(format!("Wait({t:.2}s)"))),
+        WaitForever => ("WaitForever", None),
+        Invert(_) => ("Inverter", None),
+        AlwaysSucceed(_) => ("AlwaysSucceed", None),
+        Select(_) => ("Selector", None),
+        MemorylessSelector(_) => ("MemorylessSelector", None),
+        Sequence(_) => ("Sequence", None),
+        MemorylessSequence(_) => ("MemorylessSequence", None),
+        If(..) => ("If", None),
+        While(..) => ("While", None),
+        WhileAll(..) => ("WhileAll", None),
+        WhenAll(_) => ("WhenAll", None),
+        WhenAny(_) => ("WhenAny", None),
+        After(_) => ("After", None),
+        Race(_) => ("Race", None),
+        Named(_, b) | Meta(_, b) => classify(b),
+    }
+}
+
+/// The label diagrams and diffs show for a node: the [`classify`] label,
+/// after the node's name if it has one.
+pub(crate) fn display_label<A: std::fmt::Debug>(b: &Behavior<A>) -> String {
+    let (node_type, label) = classify(b);
+    let label = label.unwrap_or_else(|| node_type.to_string());
+    match b.name() {
+        Some(name) => format!("{name}: {label}"),
+        None => label,
+    }
+}
+
 /// Compute the preorder id of the first child of `self_id`, or a sentinel
 /// when telemetry is off. Inlined; the const-fold of `T::IS_RECORDING` removes
 /// all arithmetic in the noop path.
@@ -59,3 +244,24 @@ pub(crate) fn next_sibling_id<T: Tracer>(metas: &[NodeMeta], child_id: usize) ->
         usize::MAX
     }
 }
+
+/// Emit [`Tracer::halt`] for every child of `parent_id` whose flag in `live`
+/// is `true`. `live` yields one flag per child, in child order. The whole
+/// walk is elided when `!T::IS_RECORDING`.
+#[inline(always)]
+pub(crate) fn halt_children<T: Tracer>(
+    tracer: &mut T,
+    metas: &[NodeMeta],
+    parent_id: usize,
+    live: impl IntoIterator<Item = bool>,
+) {
+    if T::IS_RECORDING {
+        let mut id = first_child_id::<T>(parent_id);
+        for is_live in live {
+            if is_live {
+                tracer.halt(id);
+            }
+            id = next_sibling_id::<T>(metas, id);
+        }
+    }
+}
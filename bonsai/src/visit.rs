@@ -0,0 +1,183 @@
+//! Generic traversal of [`Behavior`] trees, so tools don't each re-implement
+//! the match over every node type.
+//!
+//! [`Behavior::children`] and [`Behavior::children_mut`] give a node's
+//! children in preorder-id order: the condition, `then` and `else` branches
+//! of an `If`, the condition then the body of a `While`. On top of them,
+//! [`Behavior::visit`] drives a [`Visitor`] and [`Behavior::fold`]
+//! accumulates a value over every node; both pass each node's path, the
+//! child indices that lead to it from the root. [`Behavior::map_actions`]
+//! turns a tree into one of another action type.
+//!
+//! [`Named`](Behavior::Named) and [`Meta`](Behavior::Meta) wrappers are
+//! looked through: a wrapper and the node it wraps are one node, with that
+//! node's children, as with preorder ids and
+//! [`NodePath`](crate::path::NodePath)s. So a path here is a `NodePath` of
+//! child indices, and visitors see each node once. They get it as written in
+//! its parent, wrappers included, so [`name`](Behavior::name) and
+//! [`metadata`](Behavior::metadata) work on it; match on
+//! [`unnamed`](Behavior::unnamed) for its kind.
+//!
+//! ```
+//! use bonsai_bt::{Action, Behavior, Invert, Sequence, Wait};
+//!
+//! let tree = Sequence(vec![Action("aim"), Invert(Box::new(Action("miss"))).named("dodge"), Wait(1.0)]);
+//!
+//! let actions = tree.fold(Vec::new(), |mut actions, _, node| {
+//!     if let Behavior::Action(action) = node.unnamed() {
+//!         actions.push(*action);
+//!     }
+//!     actions
+//! });
+//! assert_eq!(actions, ["aim", "miss"]);
+//!
+//! let depth = tree.fold(0, |depth, path, _| depth.max(path.len() + 1));
+//! assert_eq!(depth, 3, "`dodge` and its Invert are one node");
+//!
+//! let lengths = tree.map_actions(str::len);
+//! assert_eq!(
+//!     lengths,
+//!     Sequence(vec![Action(3), Invert(Box::new(Action(4))).named("dodge"), Wait(1.0)])
+//! );
+//! ```
+
+use crate::Behavior;
+
+/// Callbacks for [`Behavior::visit`]. `path` holds the child indices that
+/// lead from the root to `node`; the root's is empty. `node` keeps its
+/// wrappers; see the [module docs](self).
+pub trait Visitor<A> {
+    /// Called on each node before its children, in preorder. Return `false`
+    /// to skip the node's children.
+    fn enter(&mut self, path: &[usize], node: &Behavior<A>) -> bool {
+        let _ = (path, node);
+        true
+    }
+
+    /// Called on each node after its children (or after `enter` declined
+    /// them), in postorder.
+    fn leave(&mut self, path: &[usize], node: &Behavior<A>) {
+        let _ = (path, node);
+    }
+}
+
+impl<A> Behavior<A> {
+    /// The node's children, in preorder-id order. Empty for leaves. Those of
+    /// a [`Named`](Behavior::Named) or [`Meta`](Behavior::Meta) wrapper are
+    /// the wrapped node's.
+    pub fn children(&self) -> Vec<&Behavior<A>> {
+        use Behavior::*;
+        match self {
+            Action(_) | Wait(_) | WaitForever => vec![],
+            Named(_, child) | Meta(_, child) => child.children(),
+            Invert(child) | AlwaysSucceed(child) => vec![child.as_ref()],
+            If(condition, on_success, on_failure) => vec![condition.as_ref(), on_success.as_ref(), on_failure.as_ref()],
+            While(condition, body) | WhileAll(condition, body) => {
+                std::iter::once(condition.as_ref()).chain(body.iter()).collect()
+            }
+            Select(children)
+            | Sequence(children)
+            | MemorylessSequence(children)
+            | MemorylessSelector(children)
+            | WhenAll(children)
+            | WhenAny(children)
+            | After(children)
+            | Race(children) => children.iter().collect(),
+        }
+    }
+
+    /// Like [`children`](Self::children), mutably.
+    pub fn children_mut(&mut self) -> Vec<&mut Behavior<A>> {
+        use Behavior::*;
+        match self {
+            Action(_) | Wait(_) | WaitForever => vec![],
+            Named(_, child) | Meta(_, child) => child.children_mut(),
+            Invert(child) | AlwaysSucceed(child) => vec![child.as_mut()],
+            If(condition, on_success, on_failure) => vec![condition.as_mut(), on_success.as_mut(), on_failure.as_mut()],
+            While(condition, body) | WhileAll(condition, body) => {
+                std::iter::once(condition.as_mut()).chain(body.iter_mut()).collect()
+            }
+            Select(children)
+            | Sequence(children)
+            | MemorylessSequence(children)
+            | MemorylessSelector(children)
+            | WhenAll(children)
+            | WhenAny(children)
+            | After(children)
+            | Race(children) => children.iter_mut().collect(),
+        }
+    }
+
+    /// Walk the tree depth-first, calling `visitor` on every node.
+    pub fn visit<V: Visitor<A>>(&self, visitor: &mut V) {
+        fn walk<A, V: Visitor<A>>(node: &Behavior<A>, path: &mut Vec<usize>, visitor: &mut V) {
+            if visitor.enter(path, node) {
+                for (i, child) in node.children().into_iter().enumerate() {
+                    path.push(i);
+                    walk(child, path, visitor);
+                    path.pop();
+                }
+            }
+            visitor.leave(path, node);
+        }
+        walk(self, &mut Vec::new(), visitor);
+    }
+
+    /// Combine every node into an accumulator, in preorder: `f` gets the
+    /// value so far, the node's path and the node.
+    pub fn fold<T>(&self, init: T, mut f: impl FnMut(T, &[usize], &Behavior<A>) -> T) -> T {
+        struct Fold<T, F> {
+            acc: Option<T>,
+            f: F,
+        }
+        impl<A, T, F: FnMut(T, &[usize], &Behavior<A>) -> T> Visitor<A> for Fold<T, F> {
+            fn enter(&mut self, path: &[usize], node: &Behavior<A>) -> bool {
+                let acc = self.acc.take().expect("accumulator is put back after every node");
+                self.acc = Some((self.f)(acc, path, node));
+                true
+            }
+        }
+        let mut fold = Fold {
+            acc: Some(init),
+            f: &mut f,
+        };
+        self.visit(&mut fold);
+        fold.acc.expect("accumulator is put back after every node")
+    }
+
+    /// The same tree with every action replaced by `f(action)`, in preorder.
+    pub fn map_actions<B>(self, mut f: impl FnMut(A) -> B) -> Behavior<B> {
+        self.map_actions_with(&mut f)
+    }
+
+    fn map_actions_with<B, F: FnMut(A) -> B>(self, f: &mut F) -> Behavior<B> {
+        use Behavior::*;
+        let one = |child: Box<Behavior<A>>, f: &mut F| Box::new(child.map_actions_with(f));
+        let all = |children: Vec<Behavior<A>>, f: &mut F| {
+            children
+                .into_iter()
+                .map(|child| child.map_actions_with(f))
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Action(action) => Action(f(action)),
+            Wait(dt) => Wait(dt),
+            WaitForever => WaitForever,
+            Invert(child) => Invert(one(child, f)),
+            AlwaysSucceed(child) => AlwaysSucceed(one(child, f)),
+            If(condition, on_success, on_failure) => If(one(condition, f), one(on_success, f), one(on_failure, f)),
+            While(condition, body) => While(one(condition, f), all(body, f)),
+            WhileAll(condition, body) => WhileAll(one(condition, f), all(body, f)),
+            Select(children) => Select(all(children, f)),
+            Sequence(children) => Sequence(all(children, f)),
+            MemorylessSequence(children) => MemorylessSequence(all(children, f)),
+            MemorylessSelector(children) => MemorylessSelector(all(children, f)),
+            WhenAll(children) => WhenAll(all(children, f)),
+            WhenAny(children) => WhenAny(all(children, f)),
+            After(children) => After(all(children, f)),
+            Race(children) => Race(all(children, f)),
+            Named(name, child) => Named(name, one(child, f)),
+            Meta(meta, child) => Meta(meta, one(child, f)),
+        }
+    }
+}
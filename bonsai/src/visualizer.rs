@@ -1,7 +1,11 @@
 #![allow(dead_code, unused_imports, unused_variables)]
+use crate::diagram::dot_escape;
 use crate::{state::State, Behavior, Float, BT};
 use petgraph::{graph::Graph, stable_graph::NodeIndex, Direction::Outgoing};
-use std::{collections::VecDeque, fmt::Debug};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Debug,
+};
 
 #[derive(Debug, Clone)]
 pub(crate) enum NodeType<A> {
@@ -149,8 +153,62 @@ impl<A: Clone + Debug, K: Debug> BT<A, K> {
                     Self::dfs_recursive(graph, b, node_id)
                 }
             }
+            Behavior::Named(_, behavior) | Behavior::Meta(_, behavior) => {
+                Self::dfs_recursive(graph, *behavior, parent_node)
+            }
+        }
+    }
+}
+
+/// The preorder id (as in `TreeDefinition` and `TickTrace`) of every node
+/// `dfs_recursive` adds, indexed like the graph: `None` for the `Root` node
+/// and for the `Sequence` wrapped around each `While`/`WhileAll` body.
+pub(crate) fn graph_preorder_ids<A>(behavior: &Behavior<A>) -> Vec<Option<usize>> {
+    fn walk<A>(behavior: &Behavior<A>, next: &mut usize, out: &mut Vec<Option<usize>>) {
+        out.push(Some(*next));
+        *next += 1;
+        match behavior.unnamed() {
+            Behavior::While(cond, body) | Behavior::WhileAll(cond, body) => {
+                walk(cond, next, out);
+                out.push(None);
+                for child in body {
+                    walk(child, next, out);
+                }
+            }
+            _ => {
+                for child in crate::tracer::children_of(behavior) {
+                    walk(child, next, out);
+                }
+            }
+        }
+    }
+    let mut ids = vec![None];
+    walk(behavior, &mut 0, &mut ids);
+    ids
+}
+
+/// The DOT `tooltip` attribute of every node `dfs_recursive` adds, indexed
+/// like the graph: the node's metadata as `key: value` lines, or empty if
+/// it has none.
+pub(crate) fn graph_tooltips<A>(behavior: &Behavior<A>) -> Vec<String> {
+    fn walk<A>(behavior: &Behavior<A>, out: &mut Vec<BTreeMap<String, String>>) {
+        out.push(behavior.metadata());
+        for child in crate::tracer::children_of(behavior) {
+            walk(child, out);
         }
     }
+    let mut metadata = Vec::new();
+    walk(behavior, &mut metadata);
+    graph_preorder_ids(behavior)
+        .into_iter()
+        .map(|id| match id.map(|id| &metadata[id]) {
+            Some(meta) if !meta.is_empty() => {
+                let lines: Vec<String> = meta.iter().map(|(key, value)| format!("{key}: {value}")).collect();
+                format!("tooltip = \"{}\"", dot_escape(&lines.join("\n")))
+            }
+            _ => String::new(),
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -205,7 +263,7 @@ mod tests {
         ]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -223,7 +281,7 @@ mod tests {
         ]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -242,7 +300,7 @@ mod tests {
         ]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -256,7 +314,7 @@ mod tests {
         let behavior = While(Box::new(Wait(50.0)), vec![Wait(0.5), Action(Inc), Wait(0.5)]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -270,7 +328,7 @@ mod tests {
         let behavior = While(Box::new(WaitForever), vec![Wait(0.5), Action(Inc), WaitForever]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -291,7 +349,7 @@ mod tests {
         );
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -305,7 +363,7 @@ mod tests {
         let behavior = Sequence(vec![Invert(Box::new(Action(Inc))), Action(Dec)]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -324,7 +382,7 @@ mod tests {
         ]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -338,7 +396,7 @@ mod tests {
         let behavior = Sequence(vec![AlwaysSucceed(Box::new(Action(Inc))), Action(Dec)]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -357,7 +415,7 @@ mod tests {
         ]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -378,7 +436,7 @@ mod tests {
         let behavior = Select(vec![_while, Action(Inc), seq, Action(Dec)]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -399,7 +457,7 @@ mod tests {
         let behavior = Select(vec![Invert(Box::new(_while)), _select, Action(Inc), Action(Dec)]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -418,7 +476,7 @@ mod tests {
         );
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -436,7 +494,7 @@ mod tests {
         ]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -455,7 +513,7 @@ mod tests {
         ]);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -474,7 +532,7 @@ mod tests {
         .memory(false);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -494,7 +552,7 @@ mod tests {
         .memory(false);
 
         let h: HashMap<String, i32> = HashMap::new();
-        let mut bt = BT::new(behavior, h);
+        let bt = BT::new(behavior, h);
         let (_, g) = bt.get_graphviz_with_graph_instance();
 
         println!("{:?}", Dot::with_config(&g, &[Config::EdgeNoLabel]));
@@ -502,4 +560,78 @@ mod tests {
         assert_eq!(g.node_count(), 7);
         assert_eq!(g.edge_count(), 6);
     }
+
+    #[test]
+    fn test_graph_preorder_ids_skip_root_and_while_body() {
+        let behavior = Sequence(vec![
+            While(Box::new(Wait(5.0)), vec![Action(Inc), Action(Dec)]),
+            Action(Inc),
+        ]);
+        let (_, g) = BT::new(behavior.clone(), ()).get_graphviz_with_graph_instance();
+        let ids = graph_preorder_ids(&behavior);
+        assert_eq!(ids.len(), g.node_count());
+        // Root, Sequence, While, Wait, (body Sequence), Inc, Dec, Inc
+        assert_eq!(ids, [None, Some(0), Some(1), Some(2), None, Some(3), Some(4), Some(5)]);
+    }
+
+    #[test]
+    fn test_graphviz_with_trace_colors_visited_and_greys_the_rest() {
+        let behavior = Select(vec![Action(Dec), Sequence(vec![Action(Inc), Action(Dec)])]);
+        let bt = BT::new(behavior, ());
+        let trace = crate::tracer::TickTrace {
+            tick_id: 1,
+            states: HashMap::from([
+                (0, Status::Running),
+                (1, Status::Failure),
+                (2, Status::Running),
+                (3, Status::Running),
+            ]),
+        };
+        let dot = bt.get_graphviz_with_trace(&trace);
+        let attrs = |index: usize| {
+            let line = dot
+                .lines()
+                .find(|l| l.trim_start().starts_with(&format!("{index} [")))
+                .unwrap();
+            line[line.find("\" ").unwrap() + 2..].to_string()
+        };
+        assert_eq!(attrs(0), "]", "Root is left alone");
+        assert_eq!(attrs(1), "style = filled, fillcolor = \"#d4a017\"]");
+        assert_eq!(attrs(2), "style = filled, fillcolor = \"#c0392b\"]");
+        assert!(attrs(5).contains("fillcolor = \"#eeeeee\""), "Dec was not visited");
+        assert_eq!(
+            bt.get_graphviz(),
+            bt.get_graphviz_with_trace(&Default::default())
+                .replace(", color = \"#bbbbbb\", fontcolor = \"#999999\"", "")
+                .replace("style = filled, fillcolor = \"#eeeeee\"", ""),
+            "the same graph either way"
+        );
+    }
+
+    #[test]
+    fn test_graphviz_tooltips_list_metadata() {
+        let behavior = While(
+            Box::new(WaitForever),
+            vec![Action(Inc).meta("writes", "count").meta("description", "Add \"one\"")],
+        );
+        let bt = BT::new(behavior, ());
+        let dot = bt.get_graphviz();
+        let inc = dot.lines().find(|l| l.contains("label = \"Action(Inc)\"")).unwrap();
+        assert!(
+            inc.ends_with("tooltip = \"description: Add \\\"one\\\"\\nwrites: count\"]"),
+            "{inc}"
+        );
+        assert_eq!(dot.matches("tooltip").count(), 1, "{dot}");
+
+        let trace = crate::tracer::TickTrace {
+            tick_id: 1,
+            states: HashMap::from([(2, Status::Running)]),
+        };
+        let dot = bt.get_graphviz_with_trace(&trace);
+        let inc = dot.lines().find(|l| l.contains("label = \"Action(Inc)\"")).unwrap();
+        assert!(
+            inc.contains("fillcolor = \"#d4a017\", tooltip = \"description: "),
+            "{inc}"
+        );
+    }
 }
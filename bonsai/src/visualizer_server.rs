@@ -1,12 +1,17 @@
+use std::collections::BTreeMap;
 use std::io::{self, Write};
-use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use crate::telemetry::{TickTrace, VISUALIZER_HTML};
+use crate::access::{AccessPolicy, Denied, Role};
+use crate::debugger::{DebugCommand, Debugger};
+use crate::metrics::{self, TreeMetrics};
+use crate::telemetry::{TelemetryFrame, VISUALIZER_HTML};
+use crate::wire::{self, Proto, StatusVec, WireMessage};
 
 /// Slowloris budget: drop a connection that hasn't delivered headers in this long.
 const READ_TIMEOUT: Duration = Duration::from_secs(5);
@@ -20,8 +25,31 @@ const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
 /// comfortably; oversize headers are misclassified as HTTP (the client retries).
 const PEEK_BUF_BYTES: usize = 1024;
 
+/// How often the broadcaster reads clients' commands and checks the
+/// debugger for state changes, whether or not frames arrive (e.g. the tree
+/// is paused, or a client pressed "Step").
+const DEBUG_POLL: Duration = Duration::from_millis(100);
+
+/// Optional services of a live server, beyond streaming frames.
+#[derive(Clone, Default)]
+pub(crate) struct ServerExtras {
+    /// Served on `GET /metrics` in the Prometheus text format; without,
+    /// that path 404s like any unknown one.
+    pub(crate) metrics: Option<Arc<TreeMetrics>>,
+    /// Accepts [`DebugCommand`]s from clients and broadcasts its state.
+    pub(crate) debugger: Option<Debugger>,
+}
+
 struct Client {
     ws: tungstenite::WebSocket<TcpStream>,
+    /// Tick encoding negotiated at the handshake.
+    proto: Proto,
+    /// Whether the client has received a keyframe, so deltas apply. Always
+    /// `false` for [`Proto::Json`] clients, which never need one.
+    synced: bool,
+    /// What the client may do; only [`Role::Control`] clients' commands
+    /// reach the debugger.
+    role: Role,
 }
 
 /// Spawn the broadcaster thread + accept loop. Returns once the listener is
@@ -32,7 +60,10 @@ struct Client {
 /// clients get the static layout before any tick frames.
 ///
 /// `rx` is moved into the broadcaster thread; dropping the matching `Sender`
-/// causes the broadcaster to exit cleanly.
+/// causes the broadcaster to exit cleanly. Each received message is sent to
+/// every client as one JSON text frame, or in the client's compact encoding
+/// for packed ticks (see [`crate::wire`]) — `BT` sends
+/// [`TelemetryFrame`](crate::telemetry::TelemetryFrame)s.
 ///
 /// Returns `(acceptor_handle, shutdown_flag, bound_addr)`. The caller
 /// (typically [`BT::with_telemetry_at`](crate::BT::with_telemetry_at)) uses
@@ -40,10 +71,153 @@ struct Client {
 /// guard sets the flag, self-connects to `bound_addr` to unblock the parked
 /// `accept()`, and joins the acceptor handle so the listener (and thus the
 /// port) is guaranteed released before `Drop` returns.
-pub fn spawn_server(
+pub fn spawn_server<M>(
     listener: TcpListener,
     tree_definition_json: String,
-    rx: Receiver<TickTrace>,
+    rx: Receiver<M>,
+) -> io::Result<(JoinHandle<()>, Arc<AtomicBool>, SocketAddr)>
+where
+    M: WireMessage + Send + 'static,
+{
+    spawn_server_with_greeting(
+        listener,
+        vec![tree_definition_json],
+        rx,
+        ServerExtras::default(),
+        AccessPolicy::new(),
+    )
+}
+
+/// [`spawn_server`] generalized to several greeting frames: every new WS
+/// client receives each string in `greeting`, in order, before any broadcast
+/// frame. The replay server uses this to follow the tree definition with
+/// the recorded ticks.
+///
+/// With a debugger in `extras`, clients also get its state as
+/// `{"debug": ..}` frames — after the greeting and on every change — and
+/// each client's text frames are read as [`DebugCommand`]s.
+///
+/// Every connection is checked against `access` first.
+pub(crate) fn spawn_server_with_greeting<M>(
+    listener: TcpListener,
+    greeting: Vec<String>,
+    rx: Receiver<M>,
+    extras: ServerExtras,
+    access: AccessPolicy,
+) -> io::Result<(JoinHandle<()>, Arc<AtomicBool>, SocketAddr)>
+where
+    M: WireMessage + Send + 'static,
+{
+    let endpoint = Arc::new(Endpoint::new(greeting, extras));
+    let (acceptor_handle, shutdown, bound_addr) =
+        spawn_acceptor(listener, Routes::Single(Arc::clone(&endpoint)), access)?;
+    // When all senders drop, signal the acceptor to stop on its next wakeup.
+    let shutdown_broadcaster = Arc::clone(&shutdown);
+    spawn_broadcaster(endpoint, rx, move || {
+        shutdown_broadcaster.store(true, Ordering::Relaxed)
+    })?;
+    Ok((acceptor_handle, shutdown, bound_addr))
+}
+
+/// One stream of frames and the clients watching it: the whole of a
+/// single-tree server, or one tree of a [`TelemetryHub`](crate::hub::TelemetryHub).
+pub(crate) struct Endpoint {
+    clients: Mutex<Vec<Client>>,
+    /// Replaced only while `clients` is held, so a client registering can
+    /// tell whether it missed a new tree definition while being greeted.
+    greeting: Mutex<Greeting>,
+    extras: ServerExtras,
+}
+
+/// Frames every new client gets first, the tree definition leading.
+struct Greeting {
+    frames: Vec<String>,
+    /// How many times the tree definition was replaced.
+    generation: u64,
+}
+
+impl Endpoint {
+    pub(crate) fn new(greeting: Vec<String>, extras: ServerExtras) -> Self {
+        Self {
+            clients: Mutex::new(Vec::new()),
+            greeting: Mutex::new(Greeting {
+                frames: greeting,
+                generation: 0,
+            }),
+            extras,
+        }
+    }
+
+    /// Make `definition` the tree definition later clients are greeted with.
+    fn set_tree_definition(&self, definition: String) {
+        let mut greeting = self.lock_greeting();
+        greeting.generation += 1;
+        match greeting.frames.first_mut() {
+            Some(first) => *first = definition,
+            None => greeting.frames.push(definition),
+        }
+    }
+
+    /// The greeting frames and their generation.
+    fn greeting(&self) -> (u64, Vec<String>) {
+        let greeting = self.lock_greeting();
+        (greeting.generation, greeting.frames.clone())
+    }
+
+    /// The tree definition, if it was replaced since `generation`, and the
+    /// current generation.
+    fn tree_definition_since(&self, generation: u64) -> (u64, Option<String>) {
+        let greeting = self.lock_greeting();
+        let newer = greeting.generation != generation;
+        (greeting.generation, newer.then(|| greeting.frames[0].clone()))
+    }
+
+    fn lock_greeting(&self) -> std::sync::MutexGuard<'_, Greeting> {
+        self.greeting.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+/// A hub's endpoints by tree name.
+pub(crate) type HubTrees = Arc<Mutex<BTreeMap<String, Arc<Endpoint>>>>;
+
+/// Which endpoint the acceptor hands a connection to.
+#[derive(Clone)]
+pub(crate) enum Routes {
+    /// Every path is served by the one endpoint.
+    Single(Arc<Endpoint>),
+    /// `/tree/<name>…` is served by the named tree; `/` by the page alone,
+    /// and `/trees` lists the names.
+    Hub(HubTrees),
+}
+
+impl Routes {
+    /// The endpoint for request path `path`, and the rest of the path below
+    /// it (always starting with `/`).
+    fn resolve<'p>(&self, path: &'p str) -> Option<(Arc<Endpoint>, &'p str)> {
+        match self {
+            Routes::Single(endpoint) => Some((Arc::clone(endpoint), path)),
+            Routes::Hub(trees) => {
+                let rest = path.strip_prefix("/tree/")?;
+                let (name, rest) = match rest.find('/') {
+                    Some(slash) => rest.split_at(slash),
+                    None => (rest, "/"),
+                };
+                let trees = trees.lock().unwrap_or_else(|p| p.into_inner());
+                trees.get(name).map(|endpoint| (Arc::clone(endpoint), rest))
+            }
+        }
+    }
+}
+
+/// Spawn the accept loop, dispatching each connection allowed by `access`
+/// by `routes`.
+///
+/// Returns `(acceptor_handle, shutdown_flag, bound_addr)` for an
+/// [`AcceptorGuard`](crate::telemetry_state::AcceptorGuard).
+pub(crate) fn spawn_acceptor(
+    listener: TcpListener,
+    routes: Routes,
+    access: AccessPolicy,
 ) -> io::Result<(JoinHandle<()>, Arc<AtomicBool>, SocketAddr)> {
     // Listener arrives pre-bound from the caller. Stdlib `TcpListener` is
     // already blocking by default; no `set_nonblocking(false)` needed.
@@ -51,16 +225,11 @@ pub fn spawn_server(
     // closure so the caller can wake us on shutdown.
     let bound_addr = listener.local_addr()?;
 
-    let clients: Arc<Mutex<Vec<Client>>> = Arc::new(Mutex::new(Vec::new()));
-    // Shared shutdown flag: the broadcaster sets it on exit, and the
-    // `AcceptorGuard` returned to the caller sets it on `Drop` then
+    // Shared shutdown flag: a single tree's broadcaster sets it on exit, and
+    // the `AcceptorGuard` returned to the caller sets it on `Drop` then
     // self-connects to `bound_addr` to wake the parked `accept()` call.
     let shutdown = Arc::new(AtomicBool::new(false));
 
-    // Acceptor thread. The `move` closure takes ownership of
-    // `tree_definition_json` directly — it's not used elsewhere in
-    // `spawn_server`, so cloning it would be redundant.
-    let clients_acceptor = Arc::clone(&clients);
     let shutdown_acceptor = Arc::clone(&shutdown);
     let acceptor_handle = std::thread::Builder::new()
         .name("bonsai-viz-acceptor".into())
@@ -76,39 +245,71 @@ pub fn spawn_server(
                 stream.set_nodelay(true).ok();
                 stream.set_read_timeout(Some(READ_TIMEOUT)).ok();
                 stream.set_write_timeout(Some(WRITE_TIMEOUT)).ok();
-                handle_connection(stream, &clients_acceptor, &tree_definition_json);
+                handle_connection(stream, &routes, &access);
             }
             // Falling out of the loop drops `listener`, releasing the OS
             // socket. `AcceptorGuard` joins this handle so callers can rely
             // on the port being free once Drop returns.
         })?;
 
-    // Broadcaster thread
-    let clients_broadcaster = Arc::clone(&clients);
-    let shutdown_broadcaster = Arc::clone(&shutdown);
+    Ok((acceptor_handle, shutdown, bound_addr))
+}
+
+/// Spawn the thread broadcasting `rx` to `endpoint`'s clients. Runs until
+/// every sender is dropped, then disconnects the clients and calls `on_exit`.
+pub(crate) fn spawn_broadcaster<M>(
+    endpoint: Arc<Endpoint>,
+    rx: Receiver<M>,
+    on_exit: impl FnOnce() + Send + 'static,
+) -> io::Result<()>
+where
+    M: WireMessage + Send + 'static,
+{
     std::thread::Builder::new()
         .name("bonsai-viz-broadcaster".into())
         .spawn(move || {
-            while let Ok(trace) = rx.recv() {
+            let debugger = endpoint.extras.debugger.as_ref();
+            // Statuses of the last broadcast tick: the base for every delta.
+            let mut prev: Option<StatusVec> = None;
+            let mut debug_version = debugger.map(Debugger::version);
+            let mut last_poll = Instant::now();
+            loop {
+                // Without a debugger there is nothing to poll: block on the channel.
+                let received = match debugger {
+                    Some(_) => rx.recv_timeout(DEBUG_POLL),
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                let trace = match received {
+                    Ok(trace) => Some(trace),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
                 // Wrap the per-trace work in catch_unwind so a malformed trace
                 // (or a tungstenite bug) can't kill the broadcaster thread and
                 // silently starve all connected clients.
-                let clients = &clients_broadcaster;
                 if std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    let Ok(json) = serde_json::to_string(&trace) else {
-                        return;
-                    };
-                    let mut guard = clients.lock().expect("clients mutex poisoned");
-                    let mut i = 0;
-                    while i < guard.len() {
-                        // Clone the String once per client — with 0–2 clients typical this is fine.
-                        match guard[i].ws.send(tungstenite::Message::Text(json.clone())) {
-                            Ok(()) => i += 1,
-                            Err(_) => {
-                                // Client dropped or write timed out — evict O(1).
-                                let _ = guard.swap_remove(i);
+                    let mut guard = endpoint.clients.lock().expect("clients mutex poisoned");
+                    if let Some(trace) = &trace {
+                        if trace.is_tree_definition() {
+                            if let Ok(text) = serde_json::to_string(trace) {
+                                endpoint.set_tree_definition(text);
                             }
                         }
+                        broadcast(trace, &mut guard, &mut prev);
+                    }
+                    if let Some(debugger) = debugger {
+                        if trace.is_none() || last_poll.elapsed() >= DEBUG_POLL {
+                            last_poll = Instant::now();
+                            read_commands(&mut guard, debugger);
+                        }
+                        let version = debugger.version();
+                        if debug_version != Some(version) {
+                            debug_version = Some(version);
+                            let frame = TelemetryFrame::Debug {
+                                debug: debugger.status(),
+                            };
+                            broadcast(&frame, &mut guard, &mut prev);
+                        }
                     }
                 }))
                 .is_err()
@@ -116,14 +317,85 @@ pub fn spawn_server(
                     eprintln!("bonsai-viz broadcaster: panic while broadcasting tick trace; continuing");
                 }
             }
-            // All senders dropped — signal acceptor to stop on its next wakeup.
-            shutdown_broadcaster.store(true, Ordering::Relaxed);
+            // All senders dropped: disconnect the clients.
+            if let Ok(clients) = endpoint.clients.lock() {
+                for client in clients.iter() {
+                    let _ = client.ws.get_ref().shutdown(Shutdown::Both);
+                }
+            }
+            on_exit();
         })?;
+    Ok(())
+}
 
-    Ok((acceptor_handle, shutdown, bound_addr))
+/// Send `msg` to every client in its negotiated encoding, evicting clients
+/// whose send fails. Each encoding is computed at most once per message.
+fn broadcast<M: WireMessage>(msg: &M, clients: &mut Vec<Client>, prev: &mut Option<StatusVec>) {
+    use tungstenite::Message;
+
+    if msg.is_tree_definition() {
+        // Node ids now refer to another tree: deltas against the old one
+        // are meaningless, so every client's next tick is a keyframe.
+        *prev = None;
+        for client in clients.iter_mut() {
+            client.synced = false;
+        }
+    }
+    let tick = msg.tick_view();
+    let mut json = None;
+    let (mut delta_json, mut key_json, mut delta_bin, mut key_bin, mut bb_json) = (None, None, None, None, None);
+    let mut i = 0;
+    while i < clients.len() {
+        let client = &mut clients[i];
+        let base = if client.synced { prev.as_ref() } else { None };
+        let mut frames = Vec::with_capacity(2);
+        match (&tick, client.proto) {
+            (Some(tick), Proto::Delta) => {
+                let cache = if base.is_some() { &mut delta_json } else { &mut key_json };
+                let text = cache.get_or_insert_with(|| wire::delta_json(tick, base));
+                frames.push(Message::Text(text.clone()));
+                client.synced = true;
+            }
+            (Some(tick), Proto::Binary) => {
+                if let Some(update) = tick.blackboard {
+                    let text = bb_json.get_or_insert_with(|| {
+                        serde_json::to_string(&serde_json::json!({ "blackboard": update }))
+                            .expect("blackboard update is always serializable")
+                    });
+                    frames.push(Message::Text(text.clone()));
+                }
+                let cache = if base.is_some() { &mut delta_bin } else { &mut key_bin };
+                let bytes = cache.get_or_insert_with(|| wire::binary(tick, base));
+                frames.push(Message::Binary(bytes.clone()));
+                client.synced = true;
+            }
+            _ => {
+                let text = json.get_or_insert_with(|| serde_json::to_string(msg).ok());
+                let Some(text) = text else {
+                    return;
+                };
+                frames.push(Message::Text(text.clone()));
+            }
+        }
+        if frames.into_iter().all(|frame| client.ws.send(frame).is_ok()) {
+            i += 1;
+        } else {
+            // Client dropped or write timed out — evict O(1).
+            let evicted = clients.swap_remove(i);
+            let _ = evicted.ws.get_ref().shutdown(Shutdown::Both);
+        }
+    }
+    if let Some(tick) = tick {
+        match prev {
+            Some(prev) => prev.clone_from(tick.states),
+            None => *prev = Some(tick.states.clone()),
+        }
+    }
 }
 
-fn handle_connection(stream: TcpStream, clients: &Mutex<Vec<Client>>, tree_json: &str) {
+// The handshake callback's error type is tungstenite's, not ours to shrink.
+#[allow(clippy::result_large_err)]
+fn handle_connection(mut stream: TcpStream, routes: &Routes, access: &AccessPolicy) {
     // Peek without consuming — tungstenite::accept needs to re-read the headers.
     let mut peek = [0u8; PEEK_BUF_BYTES];
     let n = match stream.peek(&mut peek) {
@@ -131,38 +403,191 @@ fn handle_connection(stream: TcpStream, clients: &Mutex<Vec<Client>>, tree_json:
         Err(_) => return,
     };
     let head = std::str::from_utf8(&peek[..n]).unwrap_or("");
+    // WebSocket clients see a refused upgrade as the plain HTTP error it is.
+    let role = match access.authorize(stream.peer_addr().ok().map(|a| a.ip()), head) {
+        Ok(role) => role,
+        Err(Denied::Forbidden) => {
+            return write_response(&mut stream, "403 Forbidden", "text/plain", b"forbidden", "");
+        }
+        Err(Denied::Unauthorized) => {
+            return write_response(
+                &mut stream,
+                "401 Unauthorized",
+                "text/plain",
+                b"unauthorized",
+                "WWW-Authenticate: Bearer\r\n",
+            );
+        }
+    };
     let is_ws = head.lines().any(|l| {
         let lower = l.to_ascii_lowercase();
         lower.starts_with("upgrade:") && lower.contains("websocket")
     });
 
     if is_ws {
-        if let Ok(mut ws) = tungstenite::accept(stream) {
-            // First frame: the static tree definition.
-            if ws.send(tungstenite::Message::Text(tree_json.to_owned())).is_err() {
-                return;
+        let mut target = None;
+        let accepted = tungstenite::accept_hdr(stream, |req: &tungstenite::handshake::server::Request, resp| {
+            let Some((endpoint, _)) = routes.resolve(req.uri().path()) else {
+                let mut not_found = tungstenite::handshake::server::ErrorResponse::new(Some("no such tree".into()));
+                *not_found.status_mut() = tungstenite::http::StatusCode::NOT_FOUND;
+                return Err(not_found);
+            };
+            target = Some((endpoint, Proto::from_request_target(&req.uri().to_string())));
+            Ok(resp)
+        });
+        let Ok(mut ws) = accepted else {
+            return;
+        };
+        let Some((endpoint, proto)) = target else {
+            return;
+        };
+        // First frame(s): the static tree definition, plus anything else
+        // a late-joining client needs before live frames.
+        let (mut generation, mut frames) = endpoint.greeting();
+        let debugger = endpoint.extras.debugger.as_ref();
+        let mut debug_version = debugger.map(Debugger::version);
+        if let Some(debugger) = debugger {
+            frames.push(debug_status(debugger));
+            // Lets the page hide controls it may not use.
+            if role < Role::Control {
+                frames.push(r#"{"role":"viewer"}"#.into());
             }
-            let mut guard = clients.lock().expect("clients mutex poisoned");
-            guard.push(Client { ws });
         }
+        // Written without holding `clients`, so a slow client can't stall
+        // the broadcaster. Whatever the broadcaster sent meanwhile — a new
+        // tree definition, a debugger change — is caught up on before the
+        // client joins, under the lock, so its first live frame follows both.
+        // Only what is left in the write buffer, all of a greeting under its
+        // 128 KiB, is flushed under the lock, so a client that has read such
+        // a greeting has already joined.
+        let mut clients = loop {
+            for frame in frames {
+                if ws.write(tungstenite::Message::Text(frame)).is_err() {
+                    return;
+                }
+            }
+            let clients = endpoint.clients.lock().expect("clients mutex poisoned");
+            let (latest, definition) = endpoint.tree_definition_since(generation);
+            generation = latest;
+            frames = definition.into_iter().collect();
+            if let Some(debugger) = debugger {
+                let version = debugger.version();
+                if debug_version != Some(version) {
+                    debug_version = Some(version);
+                    frames.push(debug_status(debugger));
+                }
+            }
+            if frames.is_empty() {
+                if ws.flush().is_err() {
+                    return;
+                }
+                break clients;
+            }
+        };
+        clients.push(Client {
+            ws,
+            proto,
+            synced: false,
+            role,
+        });
     } else {
-        serve_http(stream, head);
+        serve_http(stream, head, routes);
     }
 }
 
-fn serve_http(mut stream: TcpStream, head: &str) {
-    let path = head.split_whitespace().nth(1).unwrap_or("/");
-    let is_root = path == "/" || path.starts_with("/?");
-    let (status, body): (&str, &[u8]) = if is_root {
-        ("200 OK", VISUALIZER_HTML.as_bytes())
-    } else {
-        ("404 Not Found", b"not found")
+/// `debugger`'s state as a `{"debug": ..}` frame.
+fn debug_status(debugger: &Debugger) -> String {
+    let frame = TelemetryFrame::Debug {
+        debug: debugger.status(),
+    };
+    serde_json::to_string(&frame).expect("debug status is always serializable")
+}
+
+/// Apply the [`DebugCommand`]s clients have sent since the last call,
+/// without waiting for more, and evict clients whose connection closed.
+/// Unparseable frames, and commands from clients below [`Role::Control`],
+/// are ignored.
+///
+/// Reading here, on the broadcaster thread, keeps one owner per socket:
+/// tungstenite's pong and close replies go out between whole data frames.
+fn read_commands(clients: &mut Vec<Client>, debugger: &Debugger) {
+    let mut i = 0;
+    while i < clients.len() {
+        if read_pending(&mut clients[i], debugger) {
+            i += 1;
+        } else {
+            let evicted = clients.swap_remove(i);
+            let _ = evicted.ws.get_ref().shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Read `client`'s frames until none are left; `false` if it is gone.
+fn read_pending(client: &mut Client, debugger: &Debugger) -> bool {
+    // Only for the read: sends keep blocking, bounded by `WRITE_TIMEOUT`.
+    if client.ws.get_ref().set_nonblocking(true).is_err() {
+        return false;
+    }
+    let alive = loop {
+        match client.ws.read() {
+            Ok(tungstenite::Message::Text(text)) => {
+                if client.role >= Role::Control {
+                    if let Ok(command) = serde_json::from_str::<DebugCommand>(&text) {
+                        debugger.apply(command);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => break true,
+            Err(_) => break false,
+        }
+    };
+    alive && client.ws.get_ref().set_nonblocking(false).is_ok()
+}
+
+fn serve_http(mut stream: TcpStream, head: &str, routes: &Routes) {
+    type Response = (&'static str, &'static str, Vec<u8>);
+    let target = head.split_whitespace().nth(1).unwrap_or("/");
+    let path = target.split('?').next().unwrap_or("/");
+    let page = || -> Response {
+        (
+            "200 OK",
+            "text/html; charset=utf-8",
+            VISUALIZER_HTML.as_bytes().to_vec(),
+        )
+    };
+    let response = match (routes, path) {
+        (Routes::Hub(_), "/") => Some(page()),
+        (Routes::Hub(trees), "/trees") => {
+            let names: Vec<String> = trees
+                .lock()
+                .map_or_else(|_| Vec::new(), |t| t.keys().cloned().collect());
+            let body = serde_json::to_vec(&serde_json::json!({ "trees": names })).expect("names are serializable");
+            Some(("200 OK", "application/json", body))
+        }
+        _ => match routes.resolve(path) {
+            Some((_, "/")) => Some(page()),
+            Some((endpoint, "/metrics")) => endpoint.extras.metrics.as_ref().map(|metrics| {
+                let connected = endpoint.clients.lock().map_or(0, |c| c.len());
+                ("200 OK", metrics::CONTENT_TYPE, metrics.render(connected).into_bytes())
+            }),
+            _ => None,
+        },
     };
+    let (status, content_type, body) =
+        response.unwrap_or_else(|| ("404 Not Found", "text/html; charset=utf-8", b"not found".to_vec()));
+    write_response(&mut stream, status, content_type, &body, "");
+}
+
+/// Write a whole `Connection: close` response; `headers` are extra header
+/// lines, each ending in `\r\n`.
+fn write_response(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8], headers: &str) {
     let header = format!(
         "HTTP/1.1 {status}\r\n\
-         Content-Type: text/html; charset=utf-8\r\n\
+         Content-Type: {content_type}\r\n\
          Content-Length: {}\r\n\
          Cache-Control: no-store\r\n\
+         {headers}\
          Connection: close\r\n\r\n",
         body.len()
     );
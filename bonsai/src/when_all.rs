@@ -1,12 +1,12 @@
 use crate::status::Status::*;
-use crate::tracer::{first_child_id, next_sibling_id, NodeMeta, Tracer};
+use crate::tracer::{first_child_id, halt_children, next_sibling_id, NodeMeta, Tracer};
 use crate::Float;
-use crate::{event::UpdateEvent, state::State, ActionArgs, Status, RUNNING};
+use crate::{event::UpdateEvent, state::Branches, ActionArgs, Status, RUNNING};
 
 pub struct WhenAllArgs<'a, A, E, F, B, T> {
     pub any: bool,
     pub upd: Option<Float>,
-    pub cursors: &'a mut [Option<State<A>>],
+    pub branches: &'a mut Branches<A>,
     pub e: &'a E,
     pub blackboard: &'a mut B,
     pub f: &'a mut F,
@@ -29,7 +29,10 @@ where
     let WhenAllArgs {
         any,
         upd,
-        cursors,
+        branches: Branches {
+            states: cursors,
+            entered,
+        },
         e,
         blackboard,
         f,
@@ -49,7 +52,8 @@ where
     // Count number of terminated events.
     let mut terminated = 0;
     let mut child_id = first_child_id::<T>(parent_id);
-    for cur in cursors.iter_mut() {
+    let mut short_circuit = None;
+    for (j, cur) in cursors.iter_mut().enumerate() {
         let this_id = child_id;
         child_id = next_sibling_id::<T>(metas, this_id);
         match *cur {
@@ -57,12 +61,14 @@ where
             Some(ref mut cur) => {
                 match cur.tick(this_id, metas, e, blackboard, f, tracer) {
                     (Running, _) => {
+                        entered[j] = true;
                         continue;
                     }
                     (s, new_dt) if s == inv_status => {
                         // Fail for `WhenAll`.
                         // Succeed for `WhenAny`.
-                        return (inv_status, new_dt);
+                        short_circuit = Some((j, new_dt));
+                        break;
                     }
                     (s, new_dt) if s == status => {
                         min_dt = min_dt.min(new_dt);
@@ -75,6 +81,20 @@ where
         terminated += 1;
         *cur = None;
     }
+    if let Some((j, dt)) = short_circuit {
+        // The remaining parallel children that have started are abandoned.
+        halt_children(
+            tracer,
+            metas,
+            parent_id,
+            cursors
+                .iter()
+                .zip(&*entered)
+                .enumerate()
+                .map(|(k, (c, &e))| k != j && c.is_some() && e),
+        );
+        return (inv_status, dt);
+    }
     #[allow(clippy::manual_unwrap_or)]
     match terminated {
         // If there are no events, there is a whole 'dt' left.
@@ -0,0 +1,358 @@
+//! Compact encodings of per-tick node statuses for the visualizer WebSocket.
+//!
+//! [`StatusVec`] stores one 2-bit status code per node, indexed by preorder
+//! id, so copying a tick's statuses off the tick path is a `memcpy` of
+//! `nodes / 4` bytes. Each client picks a wire encoding when it connects, with
+//! a `proto` query parameter on the WebSocket URL (see [`Proto`]):
+//!
+//! | `proto`       | Tick frames                                             |
+//! |---------------|---------------------------------------------------------|
+//! | *(absent)*    | JSON [`TickTrace`] — every visited node, every tick     |
+//! | `delta`       | JSON, only the nodes whose status changed               |
+//! | `binary`      | Binary WebSocket messages, only the changed nodes       |
+//!
+//! Delta and binary streams start each client with a keyframe (the full
+//! status vector) and continue with deltas against the previous tick. All
+//! other frames — the tree definition, profiles, replays, blackboard updates
+//! in binary mode — stay JSON text.
+//!
+//! # Status codes
+//! `0` not visited this tick, `1` running, `2` success, `3` failure.
+//!
+//! # Delta JSON frames
+//! ```json
+//! {"tick_id": 42, "key": false, "delta": [[3, 2], [4, 1]], "blackboard": {"patch": {}}}
+//! ```
+//! `delta` lists `[id, code]` pairs. With `"key": true` the list holds every
+//! visited node and all other nodes are implicitly `0`.
+//!
+//! # Binary frames
+//! Little-endian throughout:
+//!
+//! ```text
+//! u8  kind        1 = keyframe, 2 = delta
+//! u64 tick_id
+//! u32 count
+//! keyframe: ceil(count / 4) bytes — `count` 2-bit codes, node i in byte i/4, bits 2*(i%4)..
+//! delta:    count × (u32 id, u8 code)
+//! ```
+//! In binary mode a tick's blackboard update arrives as a `{"blackboard": ..}`
+//! text frame immediately *before* the binary frame it belongs to.
+// The whole module is gated on the `visualize` feature in [`lib.rs`](crate).
+
+use std::collections::HashMap;
+
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+
+use crate::telemetry::{BlackboardUpdate, TelemetryFrame, TickTrace};
+use crate::Status;
+
+const KIND_KEYFRAME: u8 = 1;
+const KIND_DELTA: u8 = 2;
+
+/// Per-node statuses of one tick, 2 bits per node, indexed by preorder id.
+///
+/// Serializes like [`TickTrace::states`]: a map from the id of every visited
+/// node to its status.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatusVec {
+    len: usize,
+    bytes: Vec<u8>,
+}
+
+impl StatusVec {
+    /// A vector for `len` nodes, none visited.
+    pub fn new(len: usize) -> Self {
+        Self {
+            len,
+            bytes: vec![0; len.div_ceil(4)],
+        }
+    }
+
+    /// Number of nodes (visited or not).
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Mark every node as not visited, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.bytes.fill(0);
+    }
+
+    /// Record that node `id` returned `status`. Grows the vector if `id` is
+    /// out of range.
+    #[inline]
+    pub fn set(&mut self, id: usize, status: Status) {
+        if id >= self.len {
+            self.len = id + 1;
+            self.bytes.resize(self.len.div_ceil(4), 0);
+        }
+        self.set_code(id, encode(Some(status)));
+    }
+
+    /// The status node `id` returned this tick, or `None` if not visited.
+    pub fn get(&self, id: usize) -> Option<Status> {
+        if id >= self.len {
+            return None;
+        }
+        decode(self.code(id))
+    }
+
+    /// `(id, status)` for every visited node, in id order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Status)> + '_ {
+        (0..self.len).filter_map(|id| self.get(id).map(|s| (id, s)))
+    }
+
+    /// Number of visited nodes.
+    pub fn visited(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// `(id, code)` for every node whose code differs from `prev` (which may
+    /// be shorter, e.g. empty before the first tick).
+    pub fn diff(&self, prev: &StatusVec) -> Vec<(usize, u8)> {
+        let mut out = Vec::new();
+        for (i, &byte) in self.bytes.iter().enumerate() {
+            let old = prev.bytes.get(i).copied().unwrap_or(0);
+            if byte == old {
+                continue;
+            }
+            for slot in 0..4 {
+                let id = i * 4 + slot;
+                let (new_code, old_code) = ((byte >> (slot * 2)) & 3, (old >> (slot * 2)) & 3);
+                if id < self.len && new_code != old_code {
+                    out.push((id, new_code));
+                }
+            }
+        }
+        out
+    }
+
+    /// The statuses as a [`TickTrace`].
+    pub fn to_trace(&self, tick_id: u64) -> TickTrace {
+        let mut states = HashMap::with_capacity(self.visited());
+        states.extend(self.iter());
+        TickTrace { tick_id, states }
+    }
+
+    #[inline]
+    fn code(&self, id: usize) -> u8 {
+        (self.bytes[id / 4] >> ((id % 4) * 2)) & 3
+    }
+
+    #[inline]
+    fn set_code(&mut self, id: usize, code: u8) {
+        let shift = (id % 4) * 2;
+        let byte = &mut self.bytes[id / 4];
+        *byte = (*byte & !(3 << shift)) | (code << shift);
+    }
+}
+
+impl From<&TickTrace> for StatusVec {
+    fn from(trace: &TickTrace) -> Self {
+        let mut out = StatusVec::new(trace.states.keys().max().map_or(0, |m| m + 1));
+        for (&id, &status) in &trace.states {
+            out.set(id, status);
+        }
+        out
+    }
+}
+
+impl Serialize for StatusVec {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (id, status) in self.iter() {
+            map.serialize_entry(&id, &status)?;
+        }
+        map.end()
+    }
+}
+
+fn encode(status: Option<Status>) -> u8 {
+    match status {
+        None => 0,
+        Some(Status::Running) => 1,
+        Some(Status::Success) => 2,
+        Some(Status::Failure) => 3,
+    }
+}
+
+fn decode(code: u8) -> Option<Status> {
+    match code {
+        1 => Some(Status::Running),
+        2 => Some(Status::Success),
+        3 => Some(Status::Failure),
+        _ => None,
+    }
+}
+
+/// Tick frame encoding requested by a WebSocket client.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Proto {
+    /// Full JSON [`TickTrace`]s. The default, and what older clients get.
+    #[default]
+    Json,
+    /// JSON frames carrying only changed statuses.
+    Delta,
+    /// Binary frames carrying only changed statuses.
+    Binary,
+}
+
+impl Proto {
+    /// Parse the `proto` query parameter of a request target like
+    /// `/?proto=binary`. Unknown or missing values mean [`Proto::Json`].
+    pub fn from_request_target(target: &str) -> Self {
+        let query = target.split_once('?').map_or("", |(_, q)| q);
+        for pair in query.split('&') {
+            match pair.split_once('=') {
+                Some(("proto", "delta")) => return Proto::Delta,
+                Some(("proto", "binary")) => return Proto::Binary,
+                _ => {}
+            }
+        }
+        Proto::Json
+    }
+}
+
+/// A tick as the broadcaster sees it, for delta/binary encoding.
+pub struct TickView<'a> {
+    pub tick_id: u64,
+    pub states: &'a StatusVec,
+    pub blackboard: Option<&'a BlackboardUpdate>,
+}
+
+/// A message the visualizer broadcaster can send. Every message has a JSON
+/// form; ticks that expose a [`TickView`] can also be delta/binary encoded.
+pub trait WireMessage: Serialize {
+    fn tick_view(&self) -> Option<TickView<'_>> {
+        None
+    }
+
+    /// Whether this message is a new tree definition, which replaces the
+    /// one later clients are greeted with and resets every client's deltas.
+    fn is_tree_definition(&self) -> bool {
+        false
+    }
+}
+
+impl WireMessage for TickTrace {}
+impl WireMessage for () {}
+
+impl WireMessage for TelemetryFrame {
+    fn tick_view(&self) -> Option<TickView<'_>> {
+        match self {
+            TelemetryFrame::Packed {
+                tick_id,
+                states,
+                blackboard,
+            } => Some(TickView {
+                tick_id: *tick_id,
+                states,
+                blackboard: blackboard.as_ref(),
+            }),
+            _ => None,
+        }
+    }
+
+    fn is_tree_definition(&self) -> bool {
+        matches!(self, TelemetryFrame::Tree(_))
+    }
+}
+
+/// Delta JSON frame; see the module docs.
+#[derive(Serialize)]
+struct DeltaFrame<'a> {
+    tick_id: u64,
+    key: bool,
+    delta: Vec<(usize, u8)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blackboard: Option<&'a BlackboardUpdate>,
+}
+
+/// Encode `tick` as a delta JSON frame — against `prev`, or as a keyframe
+/// when `prev` is `None`.
+pub(crate) fn delta_json(tick: &TickView<'_>, prev: Option<&StatusVec>) -> String {
+    let frame = DeltaFrame {
+        tick_id: tick.tick_id,
+        key: prev.is_none(),
+        delta: match prev {
+            Some(prev) => tick.states.diff(prev),
+            None => tick.states.iter().map(|(id, s)| (id, encode(Some(s)))).collect(),
+        },
+        blackboard: tick.blackboard,
+    };
+    serde_json::to_string(&frame).expect("delta frame is always serializable")
+}
+
+/// Encode `tick` as a binary frame — a delta against `prev`, or a keyframe
+/// when `prev` is `None`.
+pub(crate) fn binary(tick: &TickView<'_>, prev: Option<&StatusVec>) -> Vec<u8> {
+    match prev {
+        None => {
+            let mut out = Vec::with_capacity(13 + tick.states.bytes.len());
+            out.push(KIND_KEYFRAME);
+            out.extend_from_slice(&tick.tick_id.to_le_bytes());
+            out.extend_from_slice(&(tick.states.len as u32).to_le_bytes());
+            out.extend_from_slice(&tick.states.bytes);
+            out
+        }
+        Some(prev) => {
+            let delta = tick.states.diff(prev);
+            let mut out = Vec::with_capacity(13 + delta.len() * 5);
+            out.push(KIND_DELTA);
+            out.extend_from_slice(&tick.tick_id.to_le_bytes());
+            out.extend_from_slice(&(delta.len() as u32).to_le_bytes());
+            for (id, code) in delta {
+                out.extend_from_slice(&(id as u32).to_le_bytes());
+                out.push(code);
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_vec_round_trips_codes() {
+        let mut v = StatusVec::new(6);
+        v.set(0, Status::Running);
+        v.set(3, Status::Failure);
+        v.set(5, Status::Success);
+        assert_eq!(
+            v.iter().collect::<Vec<_>>(),
+            [(0, Status::Running), (3, Status::Failure), (5, Status::Success)]
+        );
+        assert_eq!(v.get(1), None);
+        v.clear();
+        assert_eq!(v.visited(), 0);
+        assert_eq!(v.len(), 6);
+    }
+
+    #[test]
+    fn diff_reports_changed_and_cleared_nodes() {
+        let mut prev = StatusVec::new(5);
+        prev.set(0, Status::Running);
+        prev.set(1, Status::Running);
+        let mut next = StatusVec::new(5);
+        next.set(0, Status::Running);
+        next.set(4, Status::Success);
+        assert_eq!(next.diff(&prev), [(1, 0), (4, 2)]);
+        assert_eq!(next.diff(&StatusVec::default()), [(0, 1), (4, 2)]);
+    }
+
+    #[test]
+    fn proto_is_negotiated_from_the_query() {
+        assert_eq!(Proto::from_request_target("/"), Proto::Json);
+        assert_eq!(Proto::from_request_target("/?proto=delta"), Proto::Delta);
+        assert_eq!(Proto::from_request_target("/?x=1&proto=binary"), Proto::Binary);
+        assert_eq!(Proto::from_request_target("/?proto=cbor"), Proto::Json);
+    }
+}
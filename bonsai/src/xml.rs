@@ -0,0 +1,771 @@
+//! Import and export [BehaviorTree.CPP] v4 XML, the format Groot saves.
+//!
+//! [`from_str`] parses a document into a [`Behavior`], turning every leaf
+//! that isn't a built-in node into an action through a mapping you provide;
+//! [`to_string`] writes a behavior back out. A `Behavior` survives the round
+//! trip unchanged, but for its node names and metadata, and `Wait` times,
+//! which are rounded to the whole milliseconds of `Sleep msec`.
+//!
+//! | BehaviorTree.CPP                                    | bonsai                        |
+//! |-----------------------------------------------------|-------------------------------|
+//! | `Sequence`, `SequenceWithMemory`                    | `Sequence`                    |
+//! | `ReactiveSequence`                                  | `Sequence(..).memory(false)`  |
+//! | `Fallback`                                          | `Select`                      |
+//! | `ReactiveFallback`                                  | `Select(..).memory(false)`    |
+//! | `Parallel` requiring all to succeed                 | `WhenAll`                     |
+//! | `Parallel` requiring one to succeed                 | `WhenAny`                     |
+//! | `Inverter`                                          | `Invert`                      |
+//! | `ForceSuccess`                                      | `AlwaysSucceed`               |
+//! | `ForceFailure`                                      | `Invert(AlwaysSucceed(..))`   |
+//! | `IfThenElse`                                        | `If`                          |
+//! | `RetryUntilSuccessful num_attempts="n"`             | `Select` of `n` copies        |
+//! | `Repeat num_cycles="n"`                             | `Sequence` of `n` copies      |
+//! | `Delay delay_msec="ms"`                             | `Sequence([Wait(ms), child])` |
+//! | `Sleep msec="ms"`                                   | `Wait`                        |
+//! | `SubTree ID="name"`                                 | the tree `name`, inlined      |
+//! | `While`, `WhileAll`, `After`, `Race`, `WaitForever` | bonsai extensions, same name  |
+//! | any other leaf, or `Action`/`Condition ID="name"`   | an action, via your mapping   |
+//!
+//! Names given with [`Behavior::named`] and metadata set with
+//! [`Behavior::meta`] aren't written, and BehaviorTree.CPP's
+//! `name` attributes don't name nodes: actions see them as a port, and
+//! other nodes ignore them.
+//!
+//! Anything else — an unknown node with children, a `Parallel` with other
+//! thresholds, an unmapped action — is reported with its line and column.
+//! Parsing carries on past such problems, so one [`XmlError`] lists them all.
+//!
+//! Retries and repeats are unrolled, so they come back out as `Fallback` and
+//! `Sequence` nodes; BehaviorTree.CPP runs those the same way.
+//!
+//! ```
+//! use bonsai_bt::xml::{self, XmlAction};
+//! use bonsai_bt::{Action, Select};
+//!
+//! #[derive(Clone, Debug, PartialEq)]
+//! enum Door { IsOpen, Open { force: u32 } }
+//!
+//! let tree = r#"
+//! <root BTCPP_format="4" main_tree_to_execute="Main">
+//!   <BehaviorTree ID="Main">
+//!     <Fallback>
+//!       <IsOpen/>
+//!       <OpenDoor force="3"/>
+//!     </Fallback>
+//!   </BehaviorTree>
+//! </root>"#;
+//!
+//! let behavior = xml::from_str(tree, |node: &XmlAction| match node.name.as_str() {
+//!     "IsOpen" => Some(Door::IsOpen),
+//!     "OpenDoor" => Some(Door::Open { force: node.get("force")?.parse().ok()? }),
+//!     _ => None,
+//! })
+//! .unwrap();
+//! assert_eq!(behavior, Select(vec![Action(Door::IsOpen), Action(Door::Open { force: 3 })]));
+//!
+//! let out = xml::to_string(&behavior, |door| match door {
+//!     Door::IsOpen => XmlAction::new("IsOpen"),
+//!     Door::Open { force } => XmlAction::new("OpenDoor").port("force", force),
+//! });
+//! assert!(out.contains(r#"<OpenDoor force="3"/>"#));
+//! ```
+//!
+//! [BehaviorTree.CPP]: https://www.behaviortree.dev/docs/learn-the-basics/xml_format
+// The whole module is gated on the `xml` feature in [`lib.rs`](crate).
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{self, Write as _};
+
+use xml_rs::common::Position;
+use xml_rs::reader::{EventReader, XmlEvent};
+
+use crate::{Behavior, Float};
+
+/// Most copies `RetryUntilSuccessful` and `Repeat` are unrolled into.
+pub const MAX_UNROLL: i64 = 100;
+
+/// ID of the tree [`to_string`] writes.
+const MAIN_TREE: &str = "MainTree";
+
+/// Element names the parser gives a meaning of its own. Actions with these
+/// names are written as `<Action ID="..."/>`.
+const BUILTIN: &[&str] = &[
+    "root",
+    "BehaviorTree",
+    "TreeNodesModel",
+    "Action",
+    "Condition",
+    "SubTree",
+    "Sequence",
+    "SequenceWithMemory",
+    "ReactiveSequence",
+    "Fallback",
+    "ReactiveFallback",
+    "Parallel",
+    "Inverter",
+    "ForceSuccess",
+    "ForceFailure",
+    "IfThenElse",
+    "RetryUntilSuccessful",
+    "Repeat",
+    "Delay",
+    "Sleep",
+    "While",
+    "WhileAll",
+    "After",
+    "Race",
+    "WaitForever",
+];
+
+/// An action leaf as it appears in XML: the node's name and its ports
+/// (attributes).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct XmlAction {
+    /// The element name, or the `ID` of an `<Action>`/`<Condition>` element.
+    pub name: String,
+    /// Every other attribute, including BehaviorTree.CPP's instance `name`.
+    pub ports: BTreeMap<String, String>,
+}
+
+impl XmlAction {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ports: BTreeMap::new(),
+        }
+    }
+
+    /// Set port `key` to `value`.
+    pub fn port(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.ports.insert(key.into(), value.to_string());
+        self
+    }
+
+    /// The value of port `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.ports.get(key).map(String::as_str)
+    }
+}
+
+/// What is wrong at an [`XmlIssue`]'s position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XmlIssueKind {
+    /// Not well-formed XML.
+    Syntax(String),
+    /// A node bonsai has no equivalent for.
+    Unsupported(String),
+    /// A leaf the action mapping returned `None` for.
+    UnknownAction(String),
+    MissingAttribute {
+        element: String,
+        attribute: &'static str,
+    },
+    BadAttribute {
+        element: String,
+        attribute: String,
+        value: String,
+        expected: &'static str,
+    },
+    ChildCount {
+        element: String,
+        expected: &'static str,
+        found: usize,
+    },
+    /// A `SubTree` naming no `BehaviorTree` in the document.
+    UnknownTree(String),
+    /// A `SubTree` that (indirectly) includes itself.
+    RecursiveSubTree(String),
+    /// No `main_tree_to_execute`, and not exactly one `BehaviorTree`.
+    NoMainTree,
+}
+
+/// One problem in an XML document, at a 1-based line and column.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlIssue {
+    pub line: u64,
+    pub column: u64,
+    pub kind: XmlIssueKind,
+}
+
+impl fmt::Display for XmlIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            XmlIssueKind::Syntax(msg) => write!(f, "{msg}"),
+            XmlIssueKind::Unsupported(name) => write!(f, "unsupported node <{name}>"),
+            XmlIssueKind::UnknownAction(name) => write!(f, "no action mapped for <{name}>"),
+            XmlIssueKind::MissingAttribute { element, attribute } => {
+                write!(f, "<{element}> needs a {attribute:?} attribute")
+            }
+            XmlIssueKind::BadAttribute {
+                element,
+                attribute,
+                value,
+                expected,
+            } => write!(f, "<{element}> {attribute}={value:?}: expected {expected}"),
+            XmlIssueKind::ChildCount {
+                element,
+                expected,
+                found,
+            } => write!(f, "<{element}> takes {expected} children, found {found}"),
+            XmlIssueKind::UnknownTree(id) => write!(f, "no BehaviorTree with ID {id:?}"),
+            XmlIssueKind::RecursiveSubTree(id) => write!(f, "SubTree {id:?} includes itself"),
+            XmlIssueKind::NoMainTree => write!(f, "no main_tree_to_execute, and not exactly one BehaviorTree"),
+        }
+    }
+}
+
+/// Every problem [`from_str`] found; never empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct XmlError {
+    pub issues: Vec<XmlIssue>,
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+/// Parse a BehaviorTree.CPP XML document, mapping action leaves with
+/// `action`. See the [module docs](self) for the node mapping.
+///
+/// The tree run is `main_tree_to_execute`, or the only `BehaviorTree`. A
+/// bare `<BehaviorTree>` document works too.
+pub fn from_str<A, F>(xml: &str, action: F) -> Result<Behavior<A>, XmlError>
+where
+    A: Clone,
+    F: FnMut(&XmlAction) -> Option<A>,
+{
+    let root = parse_document(xml).map_err(|issue| XmlError { issues: vec![issue] })?;
+    let mut converter = Converter {
+        trees: HashMap::new(),
+        action,
+        issues: Vec::new(),
+        open: Vec::new(),
+    };
+    let behavior = converter.document(&root);
+    match behavior {
+        Some(behavior) if converter.issues.is_empty() => Ok(behavior),
+        _ => Err(XmlError {
+            issues: converter.issues,
+        }),
+    }
+}
+
+/// Write `behavior` as a BehaviorTree.CPP v4 document with one tree,
+/// `MainTree`, turning actions into leaves with `action`. See the
+/// [module docs](self) for the node mapping.
+pub fn to_string<A, F>(behavior: &Behavior<A>, mut action: F) -> String
+where
+    F: FnMut(&A) -> XmlAction,
+{
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(out, "<root BTCPP_format=\"4\" main_tree_to_execute=\"{MAIN_TREE}\">");
+    let _ = writeln!(out, "  <BehaviorTree ID=\"{MAIN_TREE}\">");
+    write_node(behavior, 2, &mut action, &mut out);
+    out.push_str("  </BehaviorTree>\n</root>\n");
+    out
+}
+
+/// A parsed element, with the 1-based position of its start tag.
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    line: u64,
+    column: u64,
+}
+
+impl Element {
+    fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    fn issue(&self, kind: XmlIssueKind) -> XmlIssue {
+        XmlIssue {
+            line: self.line,
+            column: self.column,
+            kind,
+        }
+    }
+}
+
+fn parse_document(xml: &str) -> Result<Element, XmlIssue> {
+    let mut reader = EventReader::new(xml.as_bytes());
+    let mut stack: Vec<Element> = Vec::new();
+    loop {
+        let event = reader.next().map_err(|e| XmlIssue {
+            line: e.position().row + 1,
+            column: e.position().column + 1,
+            kind: XmlIssueKind::Syntax(e.msg().to_string()),
+        })?;
+        match event {
+            XmlEvent::StartElement { name, attributes, .. } => {
+                let position = reader.position();
+                stack.push(Element {
+                    name: name.local_name,
+                    attributes: attributes.into_iter().map(|a| (a.name.local_name, a.value)).collect(),
+                    children: Vec::new(),
+                    line: position.row + 1,
+                    column: position.column + 1,
+                });
+            }
+            XmlEvent::EndElement { .. } => {
+                let element = stack.pop().expect("the reader checks that elements nest");
+                match stack.last_mut() {
+                    Some(parent) => parent.children.push(element),
+                    None => return Ok(element),
+                }
+            }
+            XmlEvent::EndDocument => unreachable!("the reader fails on a document without a root element"),
+            _ => {}
+        }
+    }
+}
+
+struct Converter<'a, F> {
+    trees: HashMap<&'a str, &'a Element>,
+    action: F,
+    issues: Vec<XmlIssue>,
+    /// IDs of the subtrees being inlined, to catch recursion.
+    open: Vec<&'a str>,
+}
+
+impl<'a, F> Converter<'a, F> {
+    fn document<A>(&mut self, root: &'a Element) -> Option<Behavior<A>>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        if root.name == "BehaviorTree" {
+            return self.tree(root);
+        }
+        if root.name != "root" {
+            self.issues
+                .push(root.issue(XmlIssueKind::Unsupported(root.name.clone())));
+            return None;
+        }
+        let mut first = None;
+        for child in &root.children {
+            match child.name.as_str() {
+                "BehaviorTree" => match child.attribute("ID") {
+                    Some(id) => {
+                        first.get_or_insert(id);
+                        self.trees.insert(id, child);
+                    }
+                    None => self.issues.push(child.issue(XmlIssueKind::MissingAttribute {
+                        element: child.name.clone(),
+                        attribute: "ID",
+                    })),
+                },
+                "TreeNodesModel" => {}
+                other => self
+                    .issues
+                    .push(child.issue(XmlIssueKind::Unsupported(other.to_string()))),
+            }
+        }
+        let main = match root.attribute("main_tree_to_execute") {
+            Some(main) => main,
+            None if self.trees.len() == 1 => first?,
+            None => {
+                self.issues.push(root.issue(XmlIssueKind::NoMainTree));
+                return None;
+            }
+        };
+        match self.trees.get(main) {
+            Some(tree) => {
+                self.open.push(main);
+                let behavior = self.tree(tree);
+                self.open.pop();
+                behavior
+            }
+            None => {
+                self.issues
+                    .push(root.issue(XmlIssueKind::UnknownTree(main.to_string())));
+                None
+            }
+        }
+    }
+
+    /// The single root node of a `<BehaviorTree>`.
+    fn tree<A>(&mut self, tree: &'a Element) -> Option<Behavior<A>>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        match tree.children.as_slice() {
+            [root] => self.node(root),
+            children => {
+                self.issues.push(tree.issue(XmlIssueKind::ChildCount {
+                    element: tree.name.clone(),
+                    expected: "exactly 1",
+                    found: children.len(),
+                }));
+                None
+            }
+        }
+    }
+
+    fn node<A>(&mut self, el: &'a Element) -> Option<Behavior<A>>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        use Behavior::*;
+        match el.name.as_str() {
+            "Sequence" | "SequenceWithMemory" => self.children(el).map(Sequence),
+            "ReactiveSequence" => self.children(el).map(MemorylessSequence),
+            "Fallback" => self.children(el).map(Select),
+            "ReactiveFallback" => self.children(el).map(MemorylessSelector),
+            "After" => self.children(el).map(After),
+            "Race" => self.children(el).map(Race),
+            "Parallel" => self.parallel(el),
+            "Inverter" => self.only_child(el).map(|c| Invert(Box::new(c))),
+            "ForceSuccess" => self.only_child(el).map(|c| AlwaysSucceed(Box::new(c))),
+            "ForceFailure" => self
+                .only_child(el)
+                .map(|c| Invert(Box::new(AlwaysSucceed(Box::new(c))))),
+            "IfThenElse" => {
+                let [cond, ok, ko] = self.exact_children::<A, 3>(el, "exactly 3")?;
+                Some(If(Box::new(cond), Box::new(ok), Box::new(ko)))
+            }
+            "While" | "WhileAll" => {
+                let mut children = self.children(el)?;
+                if children.len() < 2 {
+                    self.issues.push(el.issue(XmlIssueKind::ChildCount {
+                        element: el.name.clone(),
+                        expected: "a condition and at least 1 body",
+                        found: children.len(),
+                    }));
+                    return None;
+                }
+                let cond = Box::new(children.remove(0));
+                Some(if el.name == "While" {
+                    While(cond, children)
+                } else {
+                    WhileAll(cond, children)
+                })
+            }
+            "RetryUntilSuccessful" => {
+                let n = self.count(el, "num_attempts")?;
+                let child = self.only_child(el)?;
+                Some(unroll(child, n, Select))
+            }
+            "Repeat" => {
+                let n = self.count(el, "num_cycles")?;
+                let child = self.only_child(el)?;
+                Some(unroll(child, n, Sequence))
+            }
+            "Delay" => {
+                let ms = self.millis(el, "delay_msec")?;
+                let child = self.only_child(el)?;
+                Some(Sequence(vec![Wait(ms), child]))
+            }
+            "Sleep" => self.millis(el, "msec").map(Wait),
+            "WaitForever" => Some(WaitForever),
+            "SubTree" => self.subtree(el),
+            "Action" | "Condition" => match el.attribute("ID") {
+                Some(id) => self.action(el, id),
+                None => {
+                    self.issues.push(el.issue(XmlIssueKind::MissingAttribute {
+                        element: el.name.clone(),
+                        attribute: "ID",
+                    }));
+                    None
+                }
+            },
+            name if el.children.is_empty() => self.action(el, name),
+            name => {
+                self.issues.push(el.issue(XmlIssueKind::Unsupported(name.to_string())));
+                // Still look inside, to report everything in one go.
+                let _ = self.children::<A>(el);
+                None
+            }
+        }
+    }
+
+    fn action<A>(&mut self, el: &Element, name: &str) -> Option<Behavior<A>>
+    where
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        let node = XmlAction {
+            name: name.to_string(),
+            ports: el
+                .attributes
+                .iter()
+                .filter(|(k, _)| !(k == "ID" && matches!(el.name.as_str(), "Action" | "Condition")))
+                .cloned()
+                .collect(),
+        };
+        match (self.action)(&node) {
+            Some(action) => Some(Behavior::Action(action)),
+            None => {
+                self.issues.push(el.issue(XmlIssueKind::UnknownAction(node.name)));
+                None
+            }
+        }
+    }
+
+    fn subtree<A>(&mut self, el: &'a Element) -> Option<Behavior<A>>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        let Some(id) = el.attribute("ID") else {
+            self.issues.push(el.issue(XmlIssueKind::MissingAttribute {
+                element: el.name.clone(),
+                attribute: "ID",
+            }));
+            return None;
+        };
+        let Some(tree) = self.trees.get(id).copied() else {
+            self.issues.push(el.issue(XmlIssueKind::UnknownTree(id.to_string())));
+            return None;
+        };
+        if self.open.contains(&id) {
+            self.issues
+                .push(el.issue(XmlIssueKind::RecursiveSubTree(id.to_string())));
+            return None;
+        }
+        self.open.push(id);
+        let behavior = self.tree(tree);
+        self.open.pop();
+        behavior
+    }
+
+    /// `Parallel`, whose thresholds count children (negative: from the end,
+    /// so `-1` is all of them).
+    fn parallel<A>(&mut self, el: &'a Element) -> Option<Behavior<A>>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        let n = el.children.len() as i64;
+        let success = self.int(el, "success_count", -1);
+        let failure = self.int(el, "failure_count", 1);
+        let children = self.children(el)?;
+        let (success, failure) = (success?, failure?);
+        let resolve = |value: i64| if value < 0 { n + value + 1 } else { value };
+        let all = resolve(success) == n && resolve(failure) == 1;
+        let any = resolve(success) == 1 && resolve(failure) == n;
+        match (all, any) {
+            // One child: both hold, and both behave alike. Go by how the
+            // failure threshold is written, as `to_string` writes it.
+            (true, true) if failure < 0 => Some(Behavior::WhenAny(children)),
+            (true, _) => Some(Behavior::WhenAll(children)),
+            (false, true) => Some(Behavior::WhenAny(children)),
+            (false, false) => {
+                self.issues.push(el.issue(XmlIssueKind::Unsupported(format!(
+                    "Parallel success_count={success} failure_count={failure}"
+                ))));
+                None
+            }
+        }
+    }
+
+    /// Convert every child, reporting all problems before giving up.
+    fn children<A>(&mut self, el: &'a Element) -> Option<Vec<Behavior<A>>>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        let children: Vec<_> = el.children.iter().map(|c| self.node(c)).collect();
+        children.into_iter().collect()
+    }
+
+    fn exact_children<A, const N: usize>(&mut self, el: &'a Element, expected: &'static str) -> Option<[Behavior<A>; N]>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        if el.children.len() != N {
+            self.issues.push(el.issue(XmlIssueKind::ChildCount {
+                element: el.name.clone(),
+                expected,
+                found: el.children.len(),
+            }));
+            let _ = self.children::<A>(el);
+            return None;
+        }
+        self.children(el)?.try_into().ok()
+    }
+
+    fn only_child<A>(&mut self, el: &'a Element) -> Option<Behavior<A>>
+    where
+        A: Clone,
+        F: FnMut(&XmlAction) -> Option<A>,
+    {
+        let [child] = self.exact_children::<A, 1>(el, "exactly 1")?;
+        Some(child)
+    }
+
+    fn int(&mut self, el: &Element, key: &str, default: i64) -> Option<i64> {
+        let Some(value) = el.attribute(key) else {
+            return Some(default);
+        };
+        match value.trim().parse() {
+            Ok(v) => Some(v),
+            Err(_) => {
+                self.issues.push(self.bad_attribute(el, key, value, "an integer"));
+                None
+            }
+        }
+    }
+
+    /// A repeat count: required, between 1 and [`MAX_UNROLL`].
+    fn count(&mut self, el: &Element, key: &'static str) -> Option<i64> {
+        let Some(value) = el.attribute(key) else {
+            self.issues.push(el.issue(XmlIssueKind::MissingAttribute {
+                element: el.name.clone(),
+                attribute: key,
+            }));
+            return None;
+        };
+        match value.trim().parse() {
+            Ok(n) if (1..=MAX_UNROLL).contains(&n) => Some(n),
+            _ => {
+                self.issues
+                    .push(self.bad_attribute(el, key, value, "a count from 1 to 100"));
+                None
+            }
+        }
+    }
+
+    /// A duration in milliseconds, as seconds.
+    fn millis(&mut self, el: &Element, key: &'static str) -> Option<Float> {
+        let Some(value) = el.attribute(key) else {
+            self.issues.push(el.issue(XmlIssueKind::MissingAttribute {
+                element: el.name.clone(),
+                attribute: key,
+            }));
+            return None;
+        };
+        match value.trim().parse::<Float>() {
+            Ok(ms) if ms >= 0.0 => Some(ms / 1000.0),
+            _ => {
+                self.issues
+                    .push(self.bad_attribute(el, key, value, "a non-negative number of milliseconds"));
+                None
+            }
+        }
+    }
+
+    fn bad_attribute(&self, el: &Element, key: &str, value: &str, expected: &'static str) -> XmlIssue {
+        el.issue(XmlIssueKind::BadAttribute {
+            element: el.name.clone(),
+            attribute: key.to_string(),
+            value: value.to_string(),
+            expected,
+        })
+    }
+}
+
+fn unroll<A: Clone>(child: Behavior<A>, n: i64, wrap: fn(Vec<Behavior<A>>) -> Behavior<A>) -> Behavior<A> {
+    if n == 1 {
+        child
+    } else {
+        wrap(vec![child; n as usize])
+    }
+}
+
+fn write_node<A, F>(behavior: &Behavior<A>, depth: usize, action: &mut F, out: &mut String)
+where
+    F: FnMut(&A) -> XmlAction,
+{
+    use Behavior::*;
+    let indent = "  ".repeat(depth);
+    let (tag, attributes, children): (&str, String, Vec<&Behavior<A>>) = match behavior {
+        Action(a) => {
+            let node = action(a);
+            let (tag, mut attributes) = if BUILTIN.contains(&node.name.as_str()) || !is_xml_name(&node.name) {
+                ("Action", format!(" ID=\"{}\"", escape(&node.name)))
+            } else {
+                (node.name.as_str(), String::new())
+            };
+            for (key, value) in &node.ports {
+                let _ = write!(attributes, " {key}=\"{}\"", escape(value));
+            }
+            let _ = writeln!(out, "{indent}<{tag}{attributes}/>");
+            return;
+        }
+        Wait(t) => ("Sleep", format!(" msec=\"{}\"", (t * 1000.0).round()), vec![]),
+        WaitForever => ("WaitForever", String::new(), vec![]),
+        Invert(inner) => match inner.as_ref() {
+            AlwaysSucceed(c) => ("ForceFailure", String::new(), vec![c.as_ref()]),
+            c => ("Inverter", String::new(), vec![c]),
+        },
+        AlwaysSucceed(c) => ("ForceSuccess", String::new(), vec![c.as_ref()]),
+        Sequence(xs) => ("Sequence", String::new(), xs.iter().collect()),
+        MemorylessSequence(xs) => ("ReactiveSequence", String::new(), xs.iter().collect()),
+        Select(xs) => ("Fallback", String::new(), xs.iter().collect()),
+        MemorylessSelector(xs) => ("ReactiveFallback", String::new(), xs.iter().collect()),
+        If(cond, ok, ko) => (
+            "IfThenElse",
+            String::new(),
+            vec![cond.as_ref(), ok.as_ref(), ko.as_ref()],
+        ),
+        While(cond, body) | WhileAll(cond, body) => (
+            if matches!(behavior, While(..)) {
+                "While"
+            } else {
+                "WhileAll"
+            },
+            String::new(),
+            std::iter::once(cond.as_ref()).chain(body).collect(),
+        ),
+        WhenAll(xs) => (
+            "Parallel",
+            " success_count=\"-1\" failure_count=\"1\"".to_string(),
+            xs.iter().collect(),
+        ),
+        WhenAny(xs) => (
+            "Parallel",
+            " success_count=\"1\" failure_count=\"-1\"".to_string(),
+            xs.iter().collect(),
+        ),
+        After(xs) => ("After", String::new(), xs.iter().collect()),
+        Race(xs) => ("Race", String::new(), xs.iter().collect()),
+        Named(_, inner) | Meta(_, inner) => return write_node(inner, depth, action, out),
+    };
+    if children.is_empty() {
+        let _ = writeln!(out, "{indent}<{tag}{attributes}/>");
+        return;
+    }
+    let _ = writeln!(out, "{indent}<{tag}{attributes}>");
+    for child in children {
+        write_node(child, depth + 1, action, out);
+    }
+    let _ = writeln!(out, "{indent}</{tag}>");
+}
+
+/// Whether `name` can be an element name as is (ASCII subset of XML names).
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Escape an attribute value.
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,171 @@
+//! Tests for the Chrome trace-event exporter.
+
+use std::collections::HashMap;
+
+use bonsai_bt::chrome_trace::ChromeTrace;
+use bonsai_bt::recording::{RecordedTick, Recording};
+use bonsai_bt::telemetry::{TickTrace, TreeDefinition};
+use bonsai_bt::{Action, Behavior, Failure, Race, Running, Sequence, Status, Success, WhenAll};
+
+fn recording(ticks: Vec<Vec<(usize, Status)>>) -> Recording {
+    // ids: 0 Sequence, 1 "a", 2 "b"
+    recording_of(Sequence(vec![Action("a"), Action("b")]), ticks)
+}
+
+fn recording_of(behavior: Behavior<&str>, ticks: Vec<Vec<(usize, Status)>>) -> Recording {
+    Recording {
+        start_unix_ms: 1_000,
+        tree: serde_json::to_value(TreeDefinition::build(&behavior)).unwrap(),
+        ticks: ticks
+            .into_iter()
+            .enumerate()
+            .map(|(i, states)| RecordedTick {
+                t: i as f64 * 0.1,
+                dt: Some(0.1),
+                trace: TickTrace {
+                    tick_id: i as u64 + 1,
+                    states: states.into_iter().collect::<HashMap<_, _>>(),
+                },
+                blackboard: None,
+            })
+            .collect(),
+    }
+}
+
+/// The `thread_name` of every track, in order.
+fn track_names(trace: &serde_json::Value) -> Vec<(u64, &str)> {
+    trace["traceEvents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["name"] == "thread_name")
+        .map(|e| (e["tid"].as_u64().unwrap(), e["args"]["name"].as_str().unwrap()))
+        .collect()
+}
+
+/// The B/E events of track `tid`, as (phase, ts, name-or-status).
+fn spans(trace: &serde_json::Value, tid: u64) -> Vec<(String, f64, String)> {
+    trace["traceEvents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["tid"] == tid && (e["ph"] == "B" || e["ph"] == "E"))
+        .map(|e| {
+            let what = if e["ph"] == "B" {
+                e["name"].as_str().unwrap().to_string()
+            } else {
+                e["args"]["status"].as_str().unwrap().to_string()
+            };
+            (e["ph"].as_str().unwrap().to_string(), e["ts"].as_f64().unwrap(), what)
+        })
+        .collect()
+}
+
+#[test]
+fn running_nodes_become_nested_spans() {
+    let rec = recording(vec![
+        vec![(0, Running), (1, Running)],
+        vec![(0, Running), (1, Success), (2, Running)],
+        vec![(0, Failure), (2, Failure)],
+    ]);
+    let trace = ChromeTrace::new().add_tree("nav", &rec).to_json();
+
+    let got: Vec<(String, String)> = spans(&trace, 1).into_iter().map(|(ph, _, w)| (ph, w)).collect();
+    let expect = [
+        ("B", "Sequence"),
+        ("B", "\"a\""),
+        ("E", "success"),
+        ("B", "\"b\""),
+        ("E", "failure"),
+        ("E", "failure"),
+    ];
+    let expect: Vec<(String, String)> = expect.iter().map(|(p, w)| (p.to_string(), w.to_string())).collect();
+    assert_eq!(got, expect);
+
+    let ts: Vec<f64> = spans(&trace, 1).into_iter().map(|(_, ts, _)| ts).collect();
+    assert_eq!(ts[0], 1_000_000.0, "epoch microseconds");
+    assert_eq!(ts[2], 1_100_000.0);
+}
+
+#[test]
+fn unvisited_and_unfinished_spans_are_closed() {
+    let rec = recording(vec![vec![(0, Running), (1, Running)], vec![(0, Running)]]);
+    let trace = ChromeTrace::new().add_tree("nav", &rec).to_json();
+
+    let whats: Vec<String> = spans(&trace, 1).into_iter().map(|(_, _, w)| w).collect();
+    assert_eq!(whats, ["Sequence", "\"a\"", "halted", "running"]);
+}
+
+#[test]
+fn one_named_track_per_tree() {
+    let rec = recording(vec![vec![(0, Running)]]);
+    let trace = ChromeTrace::new().add_tree("nav", &rec).add_tree("arm", &rec).to_json();
+
+    assert_eq!(track_names(&trace), [(1, "nav"), (2, "arm")]);
+    assert_eq!(spans(&trace, 2).len(), 2);
+}
+
+/// (phase, name-or-status) of the B/E events of track `tid`.
+fn phases(trace: &serde_json::Value, tid: u64) -> Vec<(String, String)> {
+    spans(trace, tid).into_iter().map(|(ph, _, w)| (ph, w)).collect()
+}
+
+fn owned(events: &[(&str, &str)]) -> Vec<(String, String)> {
+    events.iter().map(|(p, w)| (p.to_string(), w.to_string())).collect()
+}
+
+#[test]
+fn parallel_branches_get_tracks_of_their_own() {
+    // ids: 0 WhenAll, 1 Sequence, 2 "a", 3 "b", 4 "c"
+    let tree = WhenAll(vec![Sequence(vec![Action("a"), Action("b")]), Action("c")]);
+    let rec = recording_of(
+        tree,
+        vec![
+            vec![(0, Running), (1, Running), (2, Running), (4, Running)],
+            vec![(0, Running), (1, Running), (2, Success), (3, Running), (4, Running)],
+            vec![(0, Success), (1, Success), (3, Success), (4, Success)],
+        ],
+    );
+    let trace = ChromeTrace::new()
+        .add_tree("t", &rec)
+        .add_tree("u", &recording(vec![]))
+        .to_json();
+
+    assert_eq!(
+        track_names(&trace),
+        [(1, "t"), (2, "t / Sequence #1"), (3, "t / \"c\" #4"), (4, "u")]
+    );
+    assert_eq!(phases(&trace, 1), owned(&[("B", "WhenAll"), ("E", "success")]));
+    // `a` ending no longer closes `c`, which is still running.
+    assert_eq!(
+        phases(&trace, 2),
+        owned(&[
+            ("B", "Sequence"),
+            ("B", "\"a\""),
+            ("E", "success"),
+            ("B", "\"b\""),
+            ("E", "success"),
+            ("E", "success"),
+        ])
+    );
+    assert_eq!(phases(&trace, 3), owned(&[("B", "\"c\""), ("E", "success")]));
+    let c = spans(&trace, 3);
+    assert_eq!((c[0].1, c[1].1), (1_000_000.0, 1_200_000.0));
+}
+
+#[test]
+fn race_losers_are_halted_on_their_own_tracks() {
+    // ids: 0 Race, 1 "a", 2 "b"
+    let rec = recording_of(
+        Race(vec![Action("a"), Action("b")]),
+        vec![
+            vec![(0, Running), (1, Running), (2, Running)],
+            vec![(0, Success), (1, Success)],
+        ],
+    );
+    let trace = ChromeTrace::new().add_tree("t", &rec).to_json();
+
+    assert_eq!(phases(&trace, 1), owned(&[("B", "Race"), ("E", "success")]));
+    assert_eq!(phases(&trace, 2), owned(&[("B", "\"a\""), ("E", "success")]));
+    assert_eq!(phases(&trace, 3), owned(&[("B", "\"b\""), ("E", "halted")]));
+}
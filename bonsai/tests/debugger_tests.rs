@@ -0,0 +1,179 @@
+//! Tests for the interactive debugger: pause, single-step, breakpoints and
+//! the visualizer's control channel.
+
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use bonsai_bt::access::AccessPolicy;
+use bonsai_bt::debugger::{Breakpoint, PauseMode};
+use bonsai_bt::{Action, ActionArgs, Event, Failure, Float, Running, Sequence, Status, Success, UpdateArgs, BT};
+
+type Tree = BT<&'static str, u32>;
+
+fn tree() -> Tree {
+    BT::new(Sequence(vec![Action("a"), Action("b")]), 0)
+}
+
+/// Tick once, counting action calls on the blackboard; "b" returns `b`.
+fn tick(bt: &mut Tree, b: Status) -> Option<(Status, Float)> {
+    let e: Event = UpdateArgs { dt: 0.5 }.into();
+    bt.tick(&e, &mut |args: ActionArgs<Event, &str>, calls: &mut u32| {
+        *calls += 1;
+        match *args.action {
+            "a" => (Success, 0.0),
+            _ => (b, 0.0),
+        }
+    })
+}
+
+#[test]
+fn paused_tick_skips_without_advancing() {
+    let mut bt = tree();
+    let debugger = bt.debugger();
+    tick(&mut bt, Running);
+    debugger.pause();
+
+    assert_eq!(tick(&mut bt, Success), Some((Running, 0.5)), "none of dt consumed");
+    assert_eq!(bt.tick_count(), 1);
+    assert_eq!(*bt.blackboard(), 2, "no action ran while paused");
+    assert!(!bt.is_finished());
+
+    debugger.resume();
+    assert_eq!(tick(&mut bt, Success).map(|(s, _)| s), Some(Success));
+    assert_eq!(bt.tick_count(), 2);
+}
+
+#[test]
+fn step_runs_exactly_one_tick() {
+    let mut bt = tree();
+    let debugger = bt.debugger();
+    debugger.pause();
+    tick(&mut bt, Running);
+    assert_eq!(bt.tick_count(), 0);
+
+    debugger.step();
+    tick(&mut bt, Running);
+    tick(&mut bt, Running);
+    assert_eq!(bt.tick_count(), 1);
+    assert!(debugger.is_paused());
+    assert_eq!(debugger.status().tick_id, 1);
+}
+
+#[test]
+fn breakpoint_pauses_after_the_matching_tick() {
+    let mut bt = tree();
+    let debugger = bt.debugger();
+    debugger.add_breakpoint(Breakpoint::on_status(2, Failure));
+
+    tick(&mut bt, Running);
+    assert!(!debugger.is_paused(), "node 2 ran but did not fail");
+    tick(&mut bt, Failure);
+    assert!(debugger.is_paused());
+    let hit = debugger.last_hit().unwrap();
+    assert_eq!((hit.tick_id, hit.node, hit.status), (2, 2, Failure));
+
+    debugger.resume();
+    assert_eq!(debugger.last_hit(), None);
+}
+
+#[test]
+fn visit_breakpoint_matches_any_status() {
+    let mut bt = tree();
+    let debugger = bt.debugger();
+    debugger.add_breakpoint(Breakpoint::on_visit(1));
+    tick(&mut bt, Running);
+    assert_eq!(debugger.last_hit().map(|h| h.status), Some(Success));
+}
+
+#[test]
+fn block_mode_waits_for_resume_from_another_thread() {
+    let mut bt = tree();
+    let debugger = bt.debugger();
+    debugger.set_pause_mode(PauseMode::Block);
+    debugger.pause();
+
+    let remote = debugger.clone();
+    let resumer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        remote.resume();
+    });
+    let started = Instant::now();
+    tick(&mut bt, Running);
+    assert!(
+        started.elapsed() >= Duration::from_millis(100),
+        "tick blocked while paused"
+    );
+    assert_eq!(bt.tick_count(), 1, "and ran once resumed");
+    resumer.join().unwrap();
+}
+
+fn ws_read_json(ws: &mut tungstenite::WebSocket<TcpStream>) -> serde_json::Value {
+    loop {
+        if let tungstenite::Message::Text(text) = ws.read().expect("ws read") {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+#[test]
+fn visualizer_commands_drive_the_debugger() {
+    let port = {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    };
+    let mut bt = tree()
+        .with_telemetry_access("127.0.0.1", port, AccessPolicy::open())
+        .unwrap();
+    let debugger = bt.debugger();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let (mut ws, _) = tungstenite::client::client(format!("ws://127.0.0.1:{port}/"), stream).unwrap();
+    assert!(ws_read_json(&mut ws).get("root").is_some());
+    assert_eq!(ws_read_json(&mut ws)["debug"]["paused"], false);
+
+    let command = r#"{"cmd":"set_breakpoints","breakpoints":[{"node":2,"status":"failure"}]}"#;
+    ws.send(tungstenite::Message::Text(command.into())).unwrap();
+    ws.send(tungstenite::Message::Text(r#"{"cmd":"pause"}"#.into()))
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while !debugger.is_paused() {
+        assert!(Instant::now() < deadline, "pause command never applied");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(debugger.breakpoints(), [Breakpoint::on_status(2, Failure)]);
+    tick(&mut bt, Running);
+    assert_eq!(bt.tick_count(), 0);
+
+    // State changes are broadcast back to every client.
+    loop {
+        let frame = ws_read_json(&mut ws);
+        if frame["debug"]["paused"] == true {
+            assert_eq!(frame["debug"]["breakpoints"][0]["node"], 2);
+            break;
+        }
+    }
+}
+
+#[test]
+fn breakpoints_follow_their_nodes_across_reloads() {
+    use bonsai_bt::reload::ReloadPolicy;
+
+    let mut bt = BT::new(Sequence(vec![Action("a"), Action("b").named("b")]), 0);
+    let debugger = bt.debugger();
+    debugger.add_breakpoint(Breakpoint::on_status(2, Failure));
+    debugger.add_breakpoint(Breakpoint::on_visit(1));
+
+    bt.replace_behavior(
+        Sequence(vec![Action("x"), Action("a"), Action("b").named("b")]),
+        ReloadPolicy::Restart,
+    );
+    // Unnamed nodes go by position.
+    assert_eq!(
+        debugger.breakpoints(),
+        [Breakpoint::on_status(3, Failure), Breakpoint::on_visit(1)]
+    );
+
+    bt.replace_behavior(Action("b").named("b"), ReloadPolicy::Restart);
+    assert_eq!(debugger.breakpoints(), [Breakpoint::on_status(0, Failure)]);
+}
@@ -0,0 +1,96 @@
+//! Tests for the Mermaid and PlantUML exporters.
+
+use std::collections::HashMap;
+
+use bonsai_bt::tracer::TickTrace;
+use bonsai_bt::{Action, AlwaysSucceed, Failure, If, Running, Select, Sequence, Success, Wait, While};
+
+fn tree() -> bonsai_bt::Behavior<&'static str> {
+    // ids: 0 While, 1 Wait, 2 Sequence, 3 If, 4 "ready", 5 AlwaysSucceed, 6 "go", 7 "stop", 8 Select
+    While(
+        Box::new(Wait(5.0)),
+        vec![
+            Sequence(vec![If(
+                Box::new(Action("ready")),
+                Box::new(AlwaysSucceed(Box::new(Action("go")))),
+                Box::new(Action("stop")),
+            )]),
+            Select(vec![]),
+        ],
+    )
+}
+
+#[test]
+fn mermaid_shapes_follow_node_types() {
+    let chart = tree().to_mermaid();
+    let nodes: Vec<&str> = chart.lines().skip(1).take(9).map(str::trim).collect();
+    assert_eq!(
+        nodes,
+        [
+            "n0{\"While\"}",
+            "n1([\"Wait(5.00s)\"])",
+            "n2[[\"Sequence\"]]",
+            "n3{\"If\"}",
+            "n4[\"#quot;ready#quot;\"]",
+            "n5{{\"AlwaysSucceed\"}}",
+            "n6[\"#quot;go#quot;\"]",
+            "n7[\"#quot;stop#quot;\"]",
+            "n8[[\"Selector\"]]",
+        ]
+    );
+}
+
+#[test]
+fn mermaid_labels_if_and_while_edges() {
+    let chart = tree().to_mermaid();
+    let edges: Vec<&str> = chart.lines().skip(10).map(str::trim).collect();
+    assert_eq!(
+        edges,
+        [
+            "n0 -->|cond| n1",
+            "n0 -->|body| n2",
+            "n2 --> n3",
+            "n3 -->|cond| n4",
+            "n3 -->|then| n5",
+            "n5 --> n6",
+            "n3 -->|else| n7",
+            "n0 -->|body| n8",
+        ]
+    );
+}
+
+#[test]
+fn mermaid_colors_nodes_from_a_trace() {
+    let trace = TickTrace {
+        tick_id: 3,
+        states: HashMap::from([(0, Running), (4, Failure), (7, Running), (3, Running), (2, Running)]),
+    };
+    let chart = tree().to_mermaid_with_trace(&trace);
+    assert!(
+        chart.contains("    classDef running fill:#d4a017,stroke:#f1c40f,color:#fff\n    class n0,n2,n3,n7 running\n")
+    );
+    assert!(chart.contains("    class n4 failure\n"));
+    assert!(
+        !chart.contains("success"),
+        "no class for statuses absent from the trace"
+    );
+    assert!(!tree().to_mermaid().contains("classDef"));
+}
+
+#[test]
+fn plantuml_declares_shapes_edges_and_colors() {
+    let trace = TickTrace {
+        tick_id: 1,
+        states: HashMap::from([(6, Success)]),
+    };
+    let uml = tree().to_plantuml_with_trace(&trace);
+    assert!(uml.starts_with("@startuml\n"));
+    assert!(uml.ends_with("@enduml\n"));
+    assert!(uml.contains("\nusecase \"While\" as n0\n"));
+    assert!(uml.contains("\nstorage \"Wait(5.00s)\" as n1\n"));
+    assert!(uml.contains("\nnode \"Sequence\" as n2\n"));
+    assert!(uml.contains("\nhexagon \"AlwaysSucceed\" as n5\n"));
+    assert!(uml.contains("\nrectangle \"<U+0022>go<U+0022>\" as n6 #28a745\n"));
+    assert!(uml.contains("\nn0 --> n1 : cond\nn0 --> n2 : body\nn2 --> n3\n"));
+    assert!(uml.contains("\nn3 --> n7 : else\n"));
+}
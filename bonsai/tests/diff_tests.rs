@@ -0,0 +1,148 @@
+//! Tests for the structural tree diff.
+
+use bonsai_bt::diff::{diff, Edit};
+use bonsai_bt::{Action, Behavior, If, Invert, Select, Sequence, Wait, WaitForever, While};
+
+fn patrol() -> Behavior<&'static str> {
+    While(
+        Box::new(WaitForever),
+        vec![
+            Sequence(vec![Action("walk"), Wait(1.0), Action("look")]),
+            If(
+                Box::new(Action("see enemy")),
+                Box::new(Action("shoot")),
+                Box::new(Action("idle")),
+            ),
+        ],
+    )
+}
+
+#[test]
+fn equal_trees_have_no_edits() {
+    let diff = diff(&patrol(), &patrol());
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn reports_modified_nodes_in_place() {
+    let new = While(
+        Box::new(WaitForever),
+        vec![
+            Sequence(vec![Action("run"), Wait(2.0), Action("look")]),
+            If(
+                Box::new(Action("see enemy")),
+                Box::new(Action("shoot")),
+                Box::new(Action("idle")),
+            ),
+        ],
+    );
+    let diff = diff(&patrol(), &new);
+    assert_eq!(
+        diff.edits(),
+        [
+            Edit::Modified {
+                from: vec![1, 0],
+                to: vec![1, 0],
+                old: "\"walk\"".to_string(),
+                new: "\"run\"".to_string(),
+            },
+            Edit::Modified {
+                from: vec![1, 1],
+                to: vec![1, 1],
+                old: "Wait(1.00s)".to_string(),
+                new: "Wait(2.00s)".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        diff.to_string(),
+        "~ /1/0 \"walk\" -> \"run\"\n~ /1/1 Wait(1.00s) -> Wait(2.00s)\n"
+    );
+}
+
+#[test]
+fn reports_inserted_removed_and_moved_subtrees() {
+    let new = While(
+        Box::new(WaitForever),
+        vec![
+            If(
+                Box::new(Action("see enemy")),
+                Box::new(Sequence(vec![Action("aim"), Action("shoot")])),
+                Box::new(Action("idle")),
+            ),
+            Sequence(vec![Action("walk"), Action("look")]),
+        ],
+    );
+    assert_eq!(
+        diff(&patrol(), &new).to_string(),
+        "\
++ /1/1 Sequence (3 nodes)
+- /2/1 \"shoot\"
+> /1 -> /2 Sequence
+- /1/1 Wait(1.00s)
+"
+    );
+}
+
+#[test]
+fn a_changed_node_type_keeps_its_children() {
+    let old = Sequence(vec![Action("a"), Invert(Box::new(Action("b")))]);
+    let new = Select(vec![Action("a"), Invert(Box::new(Action("b")))]);
+    assert_eq!(diff(&old, &new).to_string(), "~ / Sequence -> Selector\n");
+
+    let new = Sequence(vec![Action("a")]);
+    assert_eq!(diff(&old, &new).to_string(), "- /1 Inverter (2 nodes)\n");
+}
+
+#[test]
+fn graphs_highlight_every_edit() {
+    let old = Sequence(vec![Action("a"), Wait(1.0), Action("c")]);
+    let new = Sequence(vec![Action("c"), Action("a"), Action("b")]);
+    let diff = diff(&old, &new);
+    assert_eq!(diff.to_string(), "- /1 Wait(1.00s)\n> /0 -> /1 \"a\"\n+ /2 \"b\"\n");
+
+    let dot = diff.to_dot();
+    assert!(dot.starts_with("digraph {\n"), "{dot}");
+    assert!(dot.contains("    n0 [label=\"Sequence\"]\n"), "{dot}");
+    assert!(
+        dot.contains("    n2 [label=\"\\\"a\\\"\", fillcolor=\"#2f80ed\""),
+        "{dot}"
+    );
+    assert!(
+        dot.contains("    n3 [label=\"\\\"b\\\"\", fillcolor=\"#28a745\""),
+        "{dot}"
+    );
+    assert!(
+        dot.contains("    r2 [label=\"Wait(1.00s)\", fillcolor=\"#c0392b\""),
+        "{dot}"
+    );
+    assert!(dot.contains("    n0 -> r2 [style=dashed]\n"), "{dot}");
+
+    let chart = diff.to_mermaid();
+    assert!(chart.contains("    n0 -.-> r2\n"), "{chart}");
+    assert!(chart.contains("    class n3 inserted\n"), "{chart}");
+    assert!(chart.contains("    class r2 removed\n"), "{chart}");
+    assert!(chart.contains("    class n2 moved\n"), "{chart}");
+}
+
+#[test]
+fn renamed_nodes_are_modified() {
+    let old = Sequence(vec![Action("a").named("first"), Action("b")]);
+    let new = Sequence(vec![Action("a").named("start"), Action("b")]);
+    assert_eq!(diff(&old, &new).to_string(), "~ /0 first: \"a\" -> start: \"a\"\n");
+    // A name is part of the node, not a node of its own.
+    let new = Sequence(vec![Action("a"), Action("b").named("second")]);
+    assert_eq!(
+        diff(&old, &new).to_string(),
+        "~ /0 first: \"a\" -> \"a\"\n~ /1 \"b\" -> second: \"b\"\n"
+    );
+}
+
+#[test]
+fn changed_metadata_is_a_modification() {
+    let old = Sequence(vec![Action("a").meta("owner", "ai"), Action("b")]);
+    let new = Sequence(vec![Action("a").meta("owner", "design"), Action("b")]);
+    assert_eq!(diff(&old, &new).to_string(), "~ /0 \"a\" -> \"a\"\n");
+    assert!(diff(&old, &old.clone()).is_empty());
+}
@@ -0,0 +1,247 @@
+//! Tests for the text DSL parser and printer.
+
+use std::fmt;
+use std::str::FromStr;
+
+use bonsai_bt::dsl::{self, DslError};
+use bonsai_bt::{
+    Action, After, AlwaysSucceed, Behavior, If, Invert, Race, Select, Sequence, Wait, WaitForever, WhenAll, WhenAny,
+    While, WhileAll,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Act {
+    Circling,
+    Jump(u32),
+}
+
+impl FromStr for Act {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        if s == "Circling" {
+            return Ok(Act::Circling);
+        }
+        s.strip_prefix("Jump(")
+            .and_then(|rest| rest.strip_suffix(')'))
+            .and_then(|n| n.parse().ok())
+            .map(Act::Jump)
+            .ok_or_else(|| "expected Circling or Jump(n)".to_string())
+    }
+}
+
+impl fmt::Display for Act {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Act::Circling => write!(f, "Circling"),
+            Act::Jump(n) => write!(f, "Jump({n})"),
+        }
+    }
+}
+
+fn every_node() -> Behavior<Act> {
+    While(
+        Box::new(WaitForever),
+        vec![
+            Sequence(vec![
+                Action(Act::Circling),
+                Wait(0.25),
+                Invert(Box::new(Action(Act::Jump(2)))),
+                AlwaysSucceed(Box::new(Select(vec![Action(Act::Jump(1))]))),
+            ]),
+            If(
+                Box::new(Action(Act::Circling)),
+                Box::new(Sequence(vec![Action(Act::Jump(3))]).memory(false)),
+                Box::new(Select(vec![]).memory(false)),
+            ),
+            WhileAll(Box::new(Wait(1.0)), vec![WhenAll(vec![]), WhenAny(vec![])]),
+            After(vec![Race(vec![Action(Act::Circling)])]),
+        ],
+    )
+}
+
+#[test]
+fn prints_canonical_layout() {
+    let expected = "\
+while wait_forever {
+    sequence {
+        action Circling;
+        wait 0.25;
+        invert action Jump(2);
+        always_succeed select {
+            action Jump(1);
+        }
+    }
+    if action Circling {
+        reactive_sequence {
+            action Jump(3);
+        }
+    } else {
+        reactive_select {}
+    }
+    while_all wait 1 {
+        when_all {}
+        when_any {}
+    }
+    after {
+        race {
+            action Circling;
+        }
+    }
+}
+";
+    assert_eq!(dsl::to_string(&every_node()), expected);
+}
+
+#[test]
+fn round_trips_every_node_type() {
+    let text = dsl::to_string(&every_node());
+    assert_eq!(dsl::from_str::<Act>(&text), Ok(every_node()));
+}
+
+#[test]
+fn accepts_comments_and_optional_semicolons() {
+    let src = "
+        // patrol until told otherwise
+        sequence { action Circling
+            action Jump(4) ; ; wait 2 } // trailing
+    ";
+    assert_eq!(
+        dsl::from_str::<Act>(src),
+        Ok(Sequence(vec![Action(Act::Circling), Action(Act::Jump(4)), Wait(2.0)]))
+    );
+}
+
+#[test]
+fn quotes_action_text_that_would_not_read_back() {
+    let tree: Behavior<String> = Sequence(vec![
+        Action("open {door}; now".to_string()),
+        Action("say \"hi\"\n".to_string()),
+        Action("http://example.com".to_string()),
+        Action(" padded".to_string()),
+    ]);
+    let text = dsl::to_string(&tree);
+    assert!(text.contains("action \"open {door}; now\";"), "{text}");
+    assert!(text.contains(r#"action "say \"hi\"\n";"#), "{text}");
+    assert_eq!(dsl::from_str::<String>(&text), Ok(tree));
+}
+
+#[test]
+fn errors_point_at_the_offending_text() {
+    let err = |src: &str| dsl::from_str::<Act>(src).unwrap_err();
+
+    assert_eq!(
+        err("sequence {\n    action Circling;\n    sequense {}\n}"),
+        DslError {
+            line: 3,
+            column: 5,
+            message: "unknown node `sequense`".to_string(),
+        }
+    );
+    assert_eq!(
+        err("select {\n  action Fly;\n}"),
+        DslError {
+            line: 2,
+            column: 10,
+            message: "invalid action `Fly`: expected Circling or Jump(n)".to_string(),
+        }
+    );
+    assert_eq!(
+        err("sequence action Circling").to_string(),
+        "line 1, column 10: expected `{` after `sequence`, found `action`"
+    );
+    assert_eq!(
+        err("sequence {\n  wait 1;\n").to_string(),
+        "line 3, column 1: expected `}`, found end of input"
+    );
+    assert_eq!(
+        err("wait soon").to_string(),
+        "line 1, column 6: invalid wait `soon`: expected a non-negative number of seconds"
+    );
+    assert_eq!(
+        err("while wait 1 {}").to_string(),
+        "line 1, column 1: `while` needs at least one node in its body"
+    );
+    assert_eq!(
+        err("if action Circling { wait 1; wait 2 } else { wait 3 }").to_string(),
+        "line 1, column 20: an `if` branch holds exactly one node, found 2; wrap several in a `sequence`"
+    );
+    assert_eq!(
+        err("wait 1; wait 2").to_string(),
+        "line 1, column 9: expected end of input, found `wait`"
+    );
+    assert_eq!(
+        err("action \"Circling").to_string(),
+        "line 1, column 8: unterminated string"
+    );
+}
+
+#[test]
+fn custom_action_functions() {
+    let tree = dsl::from_str_with("race { action 3; action 4 }", |s| s.parse::<u8>()).unwrap();
+    assert_eq!(tree, Race(vec![Action(3), Action(4)]));
+    assert_eq!(
+        dsl::to_string_with(&tree, |n| format!("n{n}")),
+        "race {\n    action n3;\n    action n4;\n}\n"
+    );
+}
+
+#[test]
+fn names_prefix_any_node() {
+    let tree = Sequence(vec![
+        Action(Act::Circling).named("spin"),
+        Select(vec![Wait(0.5)]).named("two words"),
+    ])
+    .named("root");
+    let text = dsl::to_string(&tree);
+    assert_eq!(
+        text,
+        "@root sequence {\n    @spin action Circling;\n    @\"two words\" select {\n        wait 0.5;\n    }\n}\n"
+    );
+    assert_eq!(dsl::from_str::<Act>(&text).unwrap(), tree);
+    assert_eq!(
+        dsl::from_str::<Act>("invert @not_jumping action Jump(1)").unwrap(),
+        Invert(Box::new(Action(Act::Jump(1)).named("not_jumping")))
+    );
+    assert_eq!(
+        dsl::from_str::<Act>("@ action Circling").unwrap_err(),
+        DslError {
+            line: 1,
+            column: 2,
+            message: "expected a name after `@`, found ` `".to_string(),
+        }
+    );
+}
+
+#[test]
+fn metadata_prefixes_any_node() {
+    let tree = Sequence(vec![
+        Action(Act::Circling)
+            .meta("description", "Circle the target")
+            .meta("reads", "target")
+            .named("spin"),
+        Wait(0.5).meta("owner", "ai"),
+    ]);
+    let text = dsl::to_string(&tree);
+    assert_eq!(
+        text,
+        "sequence {\n    @spin #description=\"Circle the target\" #reads=target action Circling;\n    #owner=ai wait 0.5;\n}\n"
+    );
+    assert_eq!(dsl::from_str::<Act>(&text).unwrap(), tree);
+    assert_eq!(
+        dsl::from_str::<Act>("#a=1 #b=2 wait_forever").unwrap(),
+        WaitForever.meta("a", "1").meta("b", "2")
+    );
+    assert_eq!(
+        dsl::from_str::<Act>("#owner wait_forever").unwrap_err(),
+        DslError {
+            line: 1,
+            column: 7,
+            message: "expected `=` after the metadata key, found ` `".to_string(),
+        }
+    );
+    assert_eq!(
+        dsl::from_str::<Act>("#owner= wait_forever").unwrap_err().message,
+        "expected a metadata value after `=`, found ` `"
+    );
+}
@@ -0,0 +1,149 @@
+//! Tests for JSON Schema generation.
+
+use std::collections::BTreeSet;
+
+use bonsai_bt::json_schema::{behavior_schema, readable_schema};
+use bonsai_bt::readable::Readable;
+use bonsai_bt::{Action, After, If, Invert, Race, Select, Sequence, Wait, WaitForever, WhenAll, While, WhileAll};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug, PartialEq)]
+enum Act {
+    Circling,
+    Shoot { target: String },
+}
+
+/// The tag of each `oneOf` branch: the `type` const for the readable
+/// schema, the variant name for the derived one.
+fn branches(schema: &Value) -> BTreeSet<String> {
+    schema["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|branch| {
+            let tag = branch["properties"]["type"]["const"]
+                .as_str()
+                .or_else(|| branch["const"].as_str())
+                .or_else(|| branch["required"][0].as_str());
+            tag.unwrap().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn behavior_schema_covers_every_variant() {
+    let schema = behavior_schema::<Act>().to_value();
+    let expected = [
+        "Action",
+        "After",
+        "AlwaysSucceed",
+        "If",
+        "Invert",
+        "MemorylessSelector",
+        "MemorylessSequence",
+        "Meta",
+        "Named",
+        "Race",
+        "Select",
+        "Sequence",
+        "Wait",
+        "WaitForever",
+        "WhenAll",
+        "WhenAny",
+        "While",
+        "WhileAll",
+    ];
+    assert_eq!(branches(&schema), expected.iter().map(|s| s.to_string()).collect());
+
+    let branch = |name: &str| {
+        schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["required"][0] == name)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(
+        branch("Action")["properties"]["Action"],
+        json!({ "$ref": "#/$defs/Act" })
+    );
+    assert!(schema["$defs"]["Act"]["oneOf"].is_array());
+    // `BT::new` panics on empty loop bodies.
+    for name in ["While", "WhileAll"] {
+        assert_eq!(branch(name)["properties"][name]["prefixItems"][1]["minItems"], 1);
+    }
+    // Descriptions come from the variants' docs.
+    assert!(branch("Invert")["description"]
+        .as_str()
+        .unwrap()
+        .starts_with("Converts `Success`"));
+}
+
+#[test]
+fn readable_schema_describes_every_node_of_a_tree() {
+    let schema = readable_schema::<Act>().to_value();
+    assert_eq!(branches(&schema).len(), 16);
+
+    let tree = While(
+        Box::new(WaitForever),
+        vec![
+            Sequence(vec![
+                Action(Act::Circling),
+                Wait(0.5).meta("description", "Catch a breath"),
+                Invert(Box::new(Action(Act::Shoot {
+                    target: "player".to_string(),
+                }))),
+            ]),
+            If(
+                Box::new(Action(Act::Circling)),
+                Box::new(Select(vec![]).memory(false)),
+                Box::new(WhileAll(Box::new(Wait(1.0)), vec![WhenAll(vec![])])),
+            ),
+            After(vec![Race(vec![])]).named("last"),
+        ],
+    );
+    let value = serde_json::to_value(Readable(tree)).unwrap();
+
+    // Every node's keys are exactly those its branch allows, and required,
+    // but for the optional `name` and `meta`.
+    fn check(schema: &Value, node: &Value) {
+        let branch = schema["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|b| b["properties"]["type"]["const"] == node["type"])
+            .unwrap_or_else(|| panic!("no branch for {node}"));
+        assert_eq!(branch["additionalProperties"], false);
+        assert_eq!(branch["properties"]["name"], json!({ "type": "string" }));
+        assert_eq!(
+            branch["properties"]["meta"],
+            json!({ "type": "object", "additionalProperties": { "type": "string" } })
+        );
+        let without_optional = |keys: &serde_json::Map<String, Value>| -> BTreeSet<String> {
+            keys.keys().filter(|k| *k != "name" && *k != "meta").cloned().collect()
+        };
+        let allowed = without_optional(branch["properties"].as_object().unwrap());
+        let present = without_optional(node.as_object().unwrap());
+        assert_eq!(allowed, present, "{node}");
+        for optional in ["name", "meta"] {
+            assert!(!branch["required"].as_array().unwrap().contains(&json!(optional)));
+        }
+        assert_eq!(branch["required"].as_array().unwrap().len(), present.len());
+        for key in ["child", "condition", "then", "else"] {
+            if let Some(child) = node.get(key) {
+                assert_eq!(branch["properties"][key], json!({ "$ref": "#" }));
+                check(schema, child);
+            }
+        }
+        for key in ["children", "body"] {
+            if let Some(children) = node.get(key) {
+                assert_eq!(branch["properties"][key]["items"], json!({ "$ref": "#" }));
+                children.as_array().unwrap().iter().for_each(|c| check(schema, c));
+            }
+        }
+    }
+    check(&schema, &value);
+}
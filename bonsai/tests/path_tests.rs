@@ -0,0 +1,197 @@
+//! Tests for node names, `NodePath` and `NodeIndex`.
+
+use std::collections::HashMap;
+
+use bonsai_bt::path::{NodeIndex, NodePath};
+use bonsai_bt::tracer::{TickTrace, Tracer};
+use bonsai_bt::{
+    Action, ActionArgs, Behavior, Event, Failure, Float, If, Running, Sequence, Status, Success, UpdateArgs, Wait,
+    WaitForever, While, BT,
+};
+
+fn path(s: &str) -> NodePath {
+    s.parse().unwrap()
+}
+
+#[test]
+fn paths_parse_and_print() {
+    assert_eq!(path("/"), NodePath::root());
+    assert_eq!(NodePath::root().to_string(), "/");
+    assert_eq!(path("/0/2"), NodePath::root().child(0).child(2));
+    assert_eq!(path("attack/1"), NodePath::root().name("attack").child(1));
+    assert_eq!(path("attack/1").to_string(), "/attack/1");
+
+    for (bad, message) in [
+        ("", "empty path; the root is `/`"),
+        ("/a//b", "empty segment"),
+        ("a/", "empty segment"),
+        ("/99999999999999999999999", "child index out of range"),
+    ] {
+        let err = bad.parse::<NodePath>().unwrap_err();
+        assert_eq!(err.message, message, "{bad:?}");
+        assert_eq!(err.to_string(), format!("invalid node path `{bad}`: {message}"));
+    }
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn paths_serialize_as_strings() {
+    let json = serde_json::to_string(&path("/attack/1")).unwrap();
+    assert_eq!(json, "\"/attack/1\"");
+    assert_eq!(serde_json::from_str::<NodePath>(&json).unwrap(), path("/attack/1"));
+    assert!(serde_json::from_str::<NodePath>("\"a//b\"").is_err());
+}
+
+/// Records every `enter` and `exit`.
+#[derive(Default)]
+struct Log(Vec<(usize, Option<Status>)>);
+
+impl Tracer for Log {
+    const IS_RECORDING: bool = true;
+    fn enter(&mut self, id: usize) {
+        self.0.push((id, None));
+    }
+    fn exit(&mut self, id: usize, status: Status, _dt: Float) {
+        self.0.push((id, Some(status)));
+    }
+}
+
+fn run(tree: Behavior<&'static str>) -> Vec<(usize, Option<Status>)> {
+    let mut bt = BT::new(tree, ());
+    let mut log = Log::default();
+    let e: Event = UpdateArgs { dt: 0.5 }.into();
+    for _ in 0..3 {
+        bt.tick_with_tracer(
+            &e,
+            &mut |args: ActionArgs<Event, &'static str>, _| match *args.action {
+                "fail" => (Failure, args.dt),
+                _ => (Success, args.dt),
+            },
+            &mut log,
+        );
+    }
+    log.0
+}
+
+#[test]
+fn names_leave_ids_and_ticks_alone() {
+    let plain = Sequence(vec![
+        Wait(0.75),
+        If(Box::new(Action("fail")), Box::new(Action("a")), Box::new(Action("b"))),
+    ]);
+    let named = Sequence(vec![
+        Wait(0.75).named("pause"),
+        If(
+            Box::new(Action("fail").named("check")),
+            Box::new(Action("a")),
+            Box::new(Action("b").named("fallback").named("outer")),
+        )
+        .named("choose"),
+    ])
+    .named("root");
+    assert_eq!(run(named.clone()), run(plain.clone()));
+    assert_eq!(NodeIndex::new(&named).len(), NodeIndex::new(&plain).len());
+    assert_eq!(named.unnamed().children()[0].name(), Some("pause"));
+    // The outermost of several names counts.
+    assert_eq!(
+        named.unnamed().children()[1].unnamed().children()[2].name(),
+        Some("outer")
+    );
+}
+
+/// ```text
+/// 0 While
+/// 1   WaitForever
+/// 2   Sequence "attack"
+/// 3     "aim"
+/// 4     "shoot"
+/// 5   If
+/// 6     "see"
+/// 7     "chase" "move"
+/// 8     "roam"  "move"
+/// ```
+fn patrol() -> Behavior<&'static str> {
+    While(
+        Box::new(WaitForever),
+        vec![
+            Sequence(vec![Action("aim"), Action("shoot")]).named("attack"),
+            If(
+                Box::new(Action("see")),
+                Box::new(Action("chase").named("move")),
+                Box::new(Action("roam").named("move")),
+            ),
+        ],
+    )
+}
+
+#[test]
+fn index_resolves_paths_to_ids() {
+    let index = NodeIndex::new(&patrol());
+    assert_eq!(index.len(), 9);
+    for (p, id) in [
+        ("/", Some(0)),
+        ("/1/0", Some(3)),
+        ("/attack", Some(2)),
+        ("/attack/1", Some(4)),
+        // The first match in preorder, the node itself included.
+        ("/move", Some(7)),
+        ("/2/move", Some(7)),
+        ("/2/2/move", Some(8)),
+        ("/nope", None),
+        ("/9", None),
+        ("/attack/5", None),
+    ] {
+        assert_eq!(index.id(&path(p)), id, "{p}");
+    }
+    assert_eq!(index.name(2), Some("attack"));
+    assert_eq!(index.name(3), None);
+}
+
+#[test]
+fn index_gives_paths_through_the_nearest_name() {
+    let index = NodeIndex::new(&patrol());
+    assert_eq!(index.path(0), Some(path("/")));
+    assert_eq!(index.path(4), Some(path("/attack/1")));
+    assert_eq!(index.path(5), Some(path("/2")));
+    assert_eq!(index.path(7), Some(path("/move")));
+    // `/move` finds "chase" first, so "roam" goes by position.
+    assert_eq!(index.path(8), Some(path("/2/2")));
+    assert_eq!(index.path(9), None);
+    for id in 0..index.len() {
+        assert_eq!(index.id(&index.path(id).unwrap()), Some(id));
+    }
+}
+
+#[test]
+fn traces_follow_named_nodes_across_edits() {
+    let old = Sequence(vec![
+        Wait(1.0).named("pause"),
+        Sequence(vec![Action("aim"), Action("shoot")]).named("attack"),
+    ]);
+    let new = Sequence(vec![
+        Action("reload"),
+        Wait(1.0).named("pause"),
+        Sequence(vec![Action("aim"), Action("shoot")]).named("attack"),
+    ]);
+    let (from, to) = (NodeIndex::new(&old), NodeIndex::new(&new));
+    let trace = TickTrace {
+        tick_id: 7,
+        states: HashMap::from([(0, Running), (1, Success), (2, Running), (3, Success), (4, Running)]),
+    };
+    let moved = trace.remap(&from, &to);
+    assert_eq!(moved.tick_id, 7);
+    assert_eq!(
+        moved.states,
+        HashMap::from([(0, Running), (2, Success), (3, Running), (4, Success), (5, Running)])
+    );
+    assert_eq!(moved.status_at(&to, &path("/attack/1")), Some(Running));
+    assert_eq!(trace.status_at(&from, &path("/attack/1")), Some(Running));
+    assert_eq!(from.translate(4, &to), Some(5));
+
+    // Nodes that are gone are left out.
+    let shorter = NodeIndex::new(&Sequence(vec![Sequence(vec![Action("aim")]).named("attack")]));
+    assert_eq!(
+        trace.remap(&from, &shorter).states,
+        HashMap::from([(0, Running), (1, Running), (2, Success)])
+    );
+}
@@ -0,0 +1,123 @@
+//! Acceptance tests for `profiler::Profiler` driven through
+//! `BT::tick_with_tracer`. Timing assertions only use lower bounds (sleeps),
+//! which hold on any scheduler.
+
+use std::time::Duration;
+
+use bonsai_bt::profiler::Profiler;
+use bonsai_bt::{Action, ActionArgs, Event, Float, Running, Sequence, Success, UpdateArgs, While, BT};
+
+#[derive(Clone, Debug)]
+enum Act {
+    Fast,
+    Slow,
+    Forever,
+}
+
+const SLOW: Duration = Duration::from_millis(5);
+
+fn dt_event(dt: Float) -> Event {
+    UpdateArgs { dt }.into()
+}
+
+fn run(args: ActionArgs<Event, Act>) -> (bonsai_bt::Status, Float) {
+    match *args.action {
+        Act::Fast => (Success, args.dt),
+        Act::Slow => {
+            std::thread::sleep(SLOW);
+            (Success, args.dt)
+        }
+        Act::Forever => (Running, 0.0),
+    }
+}
+
+#[test]
+fn slow_leaf_is_attributed_exclusively() {
+    use Act::*;
+    // ids: 0 Sequence, 1 Fast, 2 Slow, 3 Forever
+    let behavior = Sequence(vec![Action(Fast), Action(Slow), Action(Forever)]);
+    let mut bt = BT::new(behavior.clone(), ());
+    let mut profiler = Profiler::new();
+
+    bt.tick_with_tracer(&dt_event(0.1), &mut |args, _| run(args), &mut profiler);
+    let report = profiler.report().with_labels(&behavior);
+
+    assert_eq!(report.ticks, 1);
+    assert_eq!(report.nodes.len(), 4);
+    let root = report.node(0).unwrap();
+    let slow = report.node(2).unwrap();
+    assert_eq!(slow.label.as_deref(), Some("Slow"));
+    assert!(slow.exclusive.max_ns >= SLOW.as_nanos() as u64, "{slow:?}");
+    assert!(root.inclusive.max_ns >= slow.inclusive.max_ns);
+    assert!(
+        root.exclusive.max_ns < SLOW.as_nanos() as u64,
+        "the root's self time excludes its children: {root:?}"
+    );
+    for n in &report.nodes {
+        assert!(n.inclusive.max_ns >= n.exclusive.max_ns, "{n:?}");
+    }
+}
+
+#[test]
+fn one_sample_per_visited_node_per_tick() {
+    use Act::*;
+    // ids: 0 Sequence, 1 Fast, 2 Forever — Fast only runs on the first tick.
+    let mut bt = BT::new(Sequence(vec![Action(Fast), Action(Forever)]), ());
+    let mut profiler = Profiler::new();
+    for _ in 0..3 {
+        bt.tick_with_tracer(&dt_event(0.1), &mut |args, _| run(args), &mut profiler);
+    }
+
+    let report = profiler.report();
+    assert_eq!(report.ticks, 3);
+    assert_eq!(report.node(0).unwrap().samples, 3);
+    assert_eq!(report.node(1).unwrap().samples, 1);
+    assert_eq!(report.node(2).unwrap().samples, 3);
+}
+
+#[test]
+fn repeated_visits_within_a_tick_are_summed() {
+    use Act::*;
+    // The While body succeeds with leftover dt, so it loops twice per tick
+    // before the condition (Forever) is re-checked on the next tick.
+    let behavior = While(Box::new(Action(Forever)), vec![Action(Slow)]);
+    let mut bt = BT::new(behavior, 0u32);
+    let mut profiler = Profiler::new();
+    bt.tick_with_tracer(
+        &dt_event(1.0),
+        &mut |args, n: &mut u32| {
+            if let Slow = args.action {
+                *n += 1;
+                if *n == 2 {
+                    // Second iteration keeps the body running.
+                    std::thread::sleep(SLOW);
+                    return (Running, 0.0);
+                }
+            }
+            run(args)
+        },
+        &mut profiler,
+    );
+
+    let body = profiler.report().node(2).cloned().unwrap();
+    assert_eq!(body.samples, 1, "two visits in one tick are one sample");
+    assert!(body.exclusive.max_ns >= 2 * SLOW.as_nanos() as u64, "{body:?}");
+}
+
+#[test]
+fn report_displays_a_row_per_node() {
+    use Act::*;
+    let behavior = Sequence(vec![Action(Fast), Action(Forever)]);
+    let mut bt = BT::new(behavior.clone(), ());
+    let mut profiler = Profiler::new();
+    bt.tick_with_tracer(&dt_event(0.1), &mut |args, _| run(args), &mut profiler);
+
+    let text = profiler.report().with_labels(&behavior).to_string();
+    assert!(text.starts_with("profile over 1 ticks"), "{text}");
+    assert!(text.contains("Sequence") && text.contains("Forever"), "{text}");
+    assert_eq!(text.lines().count(), 2 + 3);
+
+    profiler.reset();
+    assert_eq!(profiler.ticks(), 0);
+    assert!(profiler.report().nodes.is_empty());
+}
@@ -0,0 +1,208 @@
+//! Tests for the readable serde representation and its format loaders.
+
+use bonsai_bt::readable::Readable;
+use bonsai_bt::{
+    Action, After, AlwaysSucceed, Behavior, If, Invert, Race, Select, Sequence, Wait, WaitForever, WhenAll, WhenAny,
+    While, WhileAll,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+enum Act {
+    Circling,
+    Jump(u32),
+    Shoot { target: String },
+}
+
+fn every_node() -> Behavior<Act> {
+    While(
+        Box::new(WaitForever),
+        vec![
+            Sequence(vec![
+                Action(Act::Circling),
+                Wait(0.5).meta("description", "Catch a breath"),
+                Invert(Box::new(Action(Act::Jump(2)))),
+                AlwaysSucceed(Box::new(Select(vec![Action(Act::Shoot {
+                    target: "player".to_string(),
+                })]))),
+            ]),
+            If(
+                Box::new(Action(Act::Circling)),
+                Box::new(Sequence(vec![Action(Act::Jump(3))]).memory(false)),
+                Box::new(Select(vec![]).memory(false)),
+            ),
+            WhileAll(Box::new(Wait(1.0)), vec![WhenAll(vec![]), WhenAny(vec![])]),
+            After(vec![Race(vec![Action(Act::Circling)])]),
+        ],
+    )
+}
+
+#[test]
+fn tags_nodes_by_type() {
+    let tree = If(
+        Box::new(Action(Act::Circling)),
+        Box::new(Invert(Box::new(Wait(0.5)))),
+        Box::new(Sequence(vec![WaitForever]).memory(false)),
+    );
+    assert_eq!(
+        serde_json::to_value(Readable(tree.clone())).unwrap(),
+        json!({
+            "type": "if",
+            "condition": { "type": "action", "action": "Circling" },
+            "then": { "type": "invert", "child": { "type": "wait", "seconds": 0.5 } },
+            "else": { "type": "reactive_sequence", "children": [{ "type": "wait_forever" }] },
+        })
+    );
+    // The derived representation is unchanged.
+    assert_eq!(
+        serde_json::to_value(&tree).unwrap()["If"][0],
+        json!({ "Action": "Circling" })
+    );
+}
+
+#[test]
+fn json_round_trip_and_serde_with() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Enemy {
+        name: String,
+        #[serde(with = "bonsai_bt::readable")]
+        brain: Behavior<Act>,
+    }
+
+    let enemy = Enemy {
+        name: "bat".to_string(),
+        brain: every_node(),
+    };
+    let text = serde_json::to_string(&enemy).unwrap();
+    assert!(text.contains(r#""brain":{"type":"while""#), "{text}");
+    assert_eq!(serde_json::from_str::<Enemy>(&text).unwrap(), enemy);
+}
+
+#[test]
+fn rejects_unknown_types_and_fields() {
+    let err = serde_json::from_value::<Readable<Act>>(json!({ "type": "sequense", "children": [] })).unwrap_err();
+    assert!(err.to_string().contains("unknown variant `sequense`"), "{err}");
+    let err = serde_json::from_value::<Readable<Act>>(json!({ "type": "wait", "secs": 1.0 })).unwrap_err();
+    assert!(err.to_string().contains("unknown field `secs`"), "{err}");
+    let err = serde_json::from_value::<Readable<Act>>(json!({ "type": "wait_forever", "seconds": 1.0 })).unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("field `seconds` does not apply to type `wait_forever`"),
+        "{err}"
+    );
+}
+
+#[test]
+fn rejects_an_empty_while_body() {
+    for kind in ["while", "while_all"] {
+        let node = json!({ "type": kind, "condition": { "type": "wait_forever" }, "body": [] });
+        let err = serde_json::from_value::<Readable<Act>>(node).unwrap_err();
+        assert!(err.to_string().contains("expected at least one body node"), "{err}");
+    }
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn yaml() {
+    let text = bonsai_bt::readable::to_yaml(&every_node()).unwrap();
+    assert!(
+        text.starts_with("type: while\ncondition:\n  type: wait_forever\n"),
+        "{text}"
+    );
+    assert_eq!(bonsai_bt::readable::from_yaml::<Act>(&text).unwrap(), every_node());
+
+    let hand_written = "
+type: sequence
+children:
+  - { type: action, action: Circling }
+  - { type: wait, seconds: 0.5 }
+";
+    assert_eq!(
+        bonsai_bt::readable::from_yaml::<Act>(hand_written).unwrap(),
+        Sequence(vec![Action(Act::Circling), Wait(0.5)])
+    );
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml() {
+    let text = bonsai_bt::readable::to_toml(&every_node()).unwrap();
+    assert_eq!(bonsai_bt::readable::from_toml::<Act>(&text).unwrap(), every_node());
+
+    let hand_written = r#"
+type = "while"
+condition = { type = "wait_forever" }
+
+[[body]]
+type = "action"
+action = "Circling"
+
+[[body]]
+type = "wait"
+seconds = 0.5
+"#;
+    assert_eq!(
+        bonsai_bt::readable::from_toml::<Act>(hand_written).unwrap(),
+        While(Box::new(WaitForever), vec![Action(Act::Circling), Wait(0.5)])
+    );
+}
+
+#[cfg(feature = "ron")]
+#[test]
+fn ron() {
+    let text = bonsai_bt::readable::to_ron(&every_node()).unwrap();
+    assert_eq!(bonsai_bt::readable::from_ron::<Act>(&text).unwrap(), every_node());
+
+    let hand_written = r#"(type: "invert", child: (type: "action", action: Jump(4)))"#;
+    assert_eq!(
+        bonsai_bt::readable::from_ron::<Act>(hand_written).unwrap(),
+        Invert(Box::new(Action(Act::Jump(4))))
+    );
+}
+
+#[test]
+fn names_are_a_field_of_any_node() {
+    let tree = Sequence(vec![Action(Act::Circling).named("spin"), WaitForever.named("idle")]).named("root");
+    let value = serde_json::to_value(Readable(tree.clone())).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "type": "sequence",
+            "children": [
+                { "type": "action", "action": "Circling", "name": "spin" },
+                { "type": "wait_forever", "name": "idle" },
+            ],
+            "name": "root",
+        })
+    );
+    assert_eq!(serde_json::from_value::<Readable<Act>>(value).unwrap().0, tree);
+}
+
+#[test]
+fn metadata_is_a_map_on_any_node() {
+    let tree = Sequence(vec![Action(Act::Circling)
+        .meta("reads", "target")
+        .meta("owner", "ai team")
+        .named("spin")]);
+    let value = serde_json::to_value(Readable(tree.clone())).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "type": "sequence",
+            "children": [{
+                "type": "action",
+                "action": "Circling",
+                "name": "spin",
+                "meta": { "owner": "ai team", "reads": "target" },
+            }],
+        })
+    );
+    assert_eq!(serde_json::from_value::<Readable<Act>>(value).unwrap().0, tree);
+
+    // An empty map is the same as none.
+    let bare = json!({ "type": "wait_forever", "meta": {} });
+    assert_eq!(serde_json::from_value::<Readable<Act>>(bare).unwrap().0, WaitForever);
+    let err = serde_json::from_value::<Readable<Act>>(json!({ "type": "wait_forever", "meta": { "n": 1 } }));
+    assert!(err.is_err());
+}
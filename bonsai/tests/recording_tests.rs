@@ -0,0 +1,195 @@
+//! Tests for on-disk trace recording (`BT::with_recording`, `TraceRecorder`),
+//! the in-memory flight recorder (`FlightRecorder`, `BT::dump_recent`) and
+//! offline replay (`Recording`, `ReplayServer`).
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use bonsai_bt::recording::{FlightRecorder, Recording, ReplayServer, TraceRecorder};
+use bonsai_bt::telemetry::{TickTrace, TreeDefinition};
+use bonsai_bt::{Action, ActionArgs, Event, Failure, Running, Sequence, Success, UpdateArgs, BT};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bonsai-{}-{name}.jsonl", std::process::id()))
+}
+
+fn recorded_bytes(ticks: u64) -> Vec<u8> {
+    let behavior = Sequence(vec![Action("a"), Action("b")]);
+    let mut recorder = TraceRecorder::new(Vec::new(), &TreeDefinition::build(&behavior)).unwrap();
+    for tick_id in 1..=ticks {
+        let trace = TickTrace {
+            tick_id,
+            ..TickTrace::default()
+        };
+        recorder.record(&trace, Some(0.1)).unwrap();
+    }
+    recorder.finish().unwrap()
+}
+
+#[test]
+fn bt_records_every_tick_to_file() {
+    let path = temp_path("bt-records");
+    let mut bt = BT::new(Sequence(vec![Action("a"), Action("b")]), ())
+        .with_recording(&path)
+        .unwrap();
+    let e: Event = UpdateArgs { dt: 0.5 }.into();
+    for _ in 0..3 {
+        bt.tick(&e, &mut |_: ActionArgs<Event, &str>, _| (Running, 0.0));
+    }
+    bt.stop_recording().unwrap();
+
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(recording.tree["root"]["node_type"], "Sequence");
+    assert_eq!(recording.ticks.len(), 3);
+    assert!(recording.ticks.iter().all(|t| t.dt == Some(0.5)));
+    let ids: Vec<u64> = recording.ticks.iter().map(|t| t.trace.tick_id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+    assert!(recording.ticks.windows(2).all(|w| w[0].t <= w[1].t));
+    assert_eq!(recording.ticks[0].trace.states.len(), 2, "root and first child ran");
+}
+
+#[test]
+fn stop_recording_detaches_the_recorder() {
+    let path = temp_path("detach");
+    let mut bt = BT::new(Action("a"), ()).with_recording(&path).unwrap();
+    bt.stop_recording().unwrap();
+    bt.tick(&UpdateArgs { dt: 0.1 }.into(), &mut |_: ActionArgs<Event, &str>, _| {
+        (Success, 0.0)
+    });
+    assert!(bt.stop_recording().is_ok(), "stopping twice is a no-op");
+
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert!(recording.ticks.is_empty());
+}
+
+#[test]
+fn truncated_last_line_is_tolerated() {
+    let mut bytes = recorded_bytes(3);
+    bytes.truncate(bytes.len() - 10);
+
+    let recording = Recording::read(Cursor::new(bytes)).unwrap();
+    assert_eq!(recording.ticks.len(), 2);
+}
+
+#[test]
+fn corrupt_middle_line_is_an_error() {
+    let text = String::from_utf8(recorded_bytes(3)).unwrap();
+    let mut lines: Vec<&str> = text.lines().collect();
+    lines[2] = "{not json";
+    let err = Recording::read(Cursor::new(lines.join("\n"))).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().contains("line 3"), "{err}");
+}
+
+#[test]
+fn foreign_header_is_rejected() {
+    let err = Recording::read(Cursor::new(
+        "{\"format\":\"other\",\"version\":1,\"start_unix_ms\":0,\"tree\":{}}\n",
+    ))
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn replay_server_sends_tree_then_whole_recording() {
+    let recording = Recording::read(Cursor::new(recorded_bytes(4))).unwrap();
+    let server = ReplayServer::bind("127.0.0.1", 0, &recording).unwrap();
+
+    let port = server.local_addr().port();
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    let (mut ws, _) = tungstenite::client::client(format!("ws://127.0.0.1:{port}/"), stream).unwrap();
+
+    let mut next_json = || match ws.read().unwrap() {
+        tungstenite::Message::Text(s) => serde_json::from_str::<serde_json::Value>(&s).unwrap(),
+        other => panic!("unexpected ws frame: {other:?}"),
+    };
+    let tree = next_json();
+    assert_eq!(tree["root"]["id"], 0);
+    let replay = next_json();
+    let ticks = replay["replay"]["ticks"].as_array().unwrap();
+    assert_eq!(ticks.len(), 4);
+    assert_eq!(ticks[3]["trace"]["tick_id"], 4);
+}
+
+#[test]
+fn flight_recorder_keeps_only_the_most_recent_ticks() {
+    let mut bt = BT::new(Action("a"), ()).with_flight_recorder(FlightRecorder::new(3));
+    let e: Event = UpdateArgs { dt: 0.1 }.into();
+    for _ in 0..5 {
+        bt.tick(&e, &mut |_: ActionArgs<Event, &str>, _| (Running, 0.0));
+    }
+
+    let mut out = Vec::new();
+    bt.dump_recent(&mut out).unwrap();
+    let recording = Recording::read(Cursor::new(out)).unwrap();
+    let ids: Vec<u64> = recording.ticks.iter().map(|t| t.trace.tick_id).collect();
+    assert_eq!(ids, vec![3, 4, 5]);
+    assert_eq!(recording.tree["root"]["node_type"], "Action");
+}
+
+#[test]
+fn flight_recorder_dumps_when_root_fails() {
+    let path = temp_path("flight-failure");
+    std::fs::remove_file(&path).ok();
+    let recorder = FlightRecorder::new(10).dump_on_failure(&path);
+    let mut bt = BT::new(Action("a"), ()).with_flight_recorder(recorder.clone());
+    let e: Event = UpdateArgs { dt: 0.1 }.into();
+
+    bt.tick(&e, &mut |_: ActionArgs<Event, &str>, _| (Running, 0.0));
+    assert!(!path.exists(), "nothing is written while the tree runs");
+    bt.tick(&e, &mut |_: ActionArgs<Event, &str>, _| (Failure, 0.0));
+
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(recording.ticks.len(), 2);
+    assert_eq!(recorder.len(), 2, "the handle shares the tree's buffer");
+}
+
+#[test]
+fn dump_recent_without_flight_recorder_is_not_found() {
+    let bt = BT::new(Action("a"), ());
+    let err = bt.dump_recent(Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
+
+#[test]
+fn recordings_carry_blackboard_snapshots_when_enabled() {
+    let path = temp_path("blackboard");
+    let mut bt = BT::new(Action("count"), HashMap::<String, u32>::new())
+        .with_telemetry_blackboard()
+        .with_recording(&path)
+        .unwrap()
+        .with_flight_recorder(FlightRecorder::new(4));
+    let e: Event = UpdateArgs { dt: 0.1 }.into();
+    for _ in 0..2 {
+        bt.tick(&e, &mut |_: ActionArgs<Event, &str>, bb: &mut HashMap<String, u32>| {
+            *bb.entry("n".into()).or_default() += 1;
+            (Running, 0.0)
+        });
+    }
+    bt.stop_recording().unwrap();
+
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+    let counts: Vec<_> = recording.ticks.iter().map(|t| t.blackboard.clone()).collect();
+    assert_eq!(
+        counts,
+        [Some(serde_json::json!({"n": 1})), Some(serde_json::json!({"n": 2}))]
+    );
+    let flight = bt.flight_recorder().unwrap().snapshot();
+    assert_eq!(flight.ticks[1].blackboard, Some(serde_json::json!({"n": 2})));
+}
+
+#[test]
+fn recordings_omit_blackboard_by_default() {
+    let text = String::from_utf8(recorded_bytes(1)).unwrap();
+    assert!(!text.contains("blackboard"), "{text}");
+}
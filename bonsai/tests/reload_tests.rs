@@ -0,0 +1,130 @@
+//! Tests for hot reload (`BT::replace_behavior`) and `FileWatcher`.
+
+use std::fs;
+
+use bonsai_bt::reload::{FileWatcher, ReloadPolicy};
+use bonsai_bt::{Action, ActionArgs, Behavior, Event, Float, Running, Sequence, Success, UpdateArgs, Wait, BT};
+
+/// Tick once, running actions named in `done` to success and the rest to
+/// `Running`. Returns the actions called, in order.
+fn tick(bt: &mut BT<&'static str, ()>, dt: Float, done: &[&str]) -> Vec<&'static str> {
+    let mut called = Vec::new();
+    let e: Event = UpdateArgs { dt }.into();
+    bt.tick(&e, &mut |args: ActionArgs<Event, &'static str>, _| {
+        called.push(*args.action);
+        if done.contains(args.action) {
+            (Success, args.dt)
+        } else {
+            (Running, 0.0)
+        }
+    });
+    called
+}
+
+fn at_b() -> BT<&'static str, ()> {
+    let mut bt = BT::new(Sequence(vec![Action("a"), Action("b")]), ());
+    assert_eq!(tick(&mut bt, 0.1, &["a"]), ["a", "b"]);
+    bt
+}
+
+#[test]
+fn restart_starts_the_new_tree_over() {
+    let mut bt = at_b();
+    bt.replace_behavior(Sequence(vec![Action("a"), Action("c")]), ReloadPolicy::Restart);
+    assert_eq!(tick(&mut bt, 0.1, &[]), ["a"]);
+    assert_eq!(bt.tick_count(), 2);
+}
+
+#[test]
+fn preserve_keeps_the_place_in_an_edited_sequence() {
+    let mut bt = at_b();
+    bt.replace_behavior(
+        Sequence(vec![Action("a"), Action("b"), Action("c")]),
+        ReloadPolicy::PreserveMatching,
+    );
+    assert_eq!(tick(&mut bt, 0.1, &["b"]), ["b", "c"]);
+
+    // `reset_bt` returns to the new tree.
+    bt.reset_bt();
+    assert_eq!(tick(&mut bt, 0.1, &["a", "b"]), ["a", "b", "c"]);
+}
+
+#[test]
+fn preserve_restarts_when_the_path_so_far_changed() {
+    let mut bt = at_b();
+    bt.replace_behavior(Sequence(vec![Action("x"), Action("b")]), ReloadPolicy::PreserveMatching);
+    assert_eq!(tick(&mut bt, 0.1, &[]), ["x"]);
+
+    // A different node type at the root has nothing to keep either.
+    let mut bt = at_b();
+    bt.replace_behavior(Action("b"), ReloadPolicy::PreserveMatching);
+    assert_eq!(tick(&mut bt, 0.1, &[]), ["b"]);
+}
+
+#[test]
+fn preserve_keeps_elapsed_wait_time() {
+    let tree = |then| Sequence(vec![Wait(1.0), Action(then)]);
+    let mut bt = BT::new(tree("a"), ());
+    assert!(tick(&mut bt, 0.6, &[]).is_empty());
+    bt.replace_behavior(tree("b"), ReloadPolicy::PreserveMatching);
+    assert_eq!(tick(&mut bt, 0.5, &[]), ["b"]);
+
+    // A changed duration still counts the time already waited.
+    let mut bt = BT::new(tree("a"), ());
+    assert!(tick(&mut bt, 0.6, &[]).is_empty());
+    bt.replace_behavior(Sequence(vec![Wait(0.7), Action("a")]), ReloadPolicy::PreserveMatching);
+    assert_eq!(tick(&mut bt, 0.2, &[]), ["a"]);
+}
+
+#[test]
+fn finished_trees_run_again() {
+    let mut bt = BT::new(Action("a"), ());
+    tick(&mut bt, 0.1, &["a"]);
+    assert!(bt.is_finished());
+
+    let tree: Behavior<&str> = Sequence(vec![Action("a"), Action("b")]);
+    bt.replace_behavior(tree, ReloadPolicy::PreserveMatching);
+    assert!(!bt.is_finished());
+    assert_eq!(tick(&mut bt, 0.1, &[]), ["a"]);
+}
+
+#[test]
+fn preserve_walks_into_unchanged_parts_of_the_tree() {
+    use bonsai_bt::{Invert, WaitForever, WhenAll, While};
+
+    let tree = |last| {
+        While(
+            Box::new(WaitForever),
+            vec![WhenAll(vec![Action("a"), Invert(Box::new(Action("b")))]), Action(last)],
+        )
+    };
+    let mut bt = BT::new(tree("c"), ());
+    // `a` finishes, `b` keeps running.
+    assert_eq!(tick(&mut bt, 0.1, &["a"]), ["a", "b"]);
+    bt.replace_behavior(tree("d"), ReloadPolicy::PreserveMatching);
+    assert_eq!(tick(&mut bt, 0.1, &[]), ["b"]);
+}
+
+#[test]
+fn file_watcher_reports_each_change_once() {
+    let dir = std::env::temp_dir().join(format!("bonsai-reload-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("tree.bt");
+    fs::write(&path, "action a").unwrap();
+
+    let mut watcher = FileWatcher::new(&path);
+    assert_eq!(watcher.path(), path);
+    assert_eq!(watcher.poll().unwrap(), None);
+
+    fs::write(&path, "action abc").unwrap();
+    assert_eq!(watcher.poll().unwrap().as_deref(), Some("action abc"));
+    assert_eq!(watcher.poll().unwrap(), None);
+
+    // Mid-save, the file may briefly be missing.
+    fs::remove_file(&path).unwrap();
+    assert_eq!(watcher.poll().unwrap(), None);
+    fs::write(&path, "action abc").unwrap();
+    assert_eq!(watcher.poll().unwrap().as_deref(), Some("action abc"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
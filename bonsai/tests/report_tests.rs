@@ -0,0 +1,149 @@
+//! Tests for the static HTML/SVG report exporter.
+
+use std::collections::HashMap;
+
+use bonsai_bt::recording::Recording;
+use bonsai_bt::report::Report;
+use bonsai_bt::telemetry::{TickTrace, TreeDefinition};
+use bonsai_bt::{Action, Failure, Running, Sequence, Status, Success};
+
+// ids: 0 Sequence, 1 "a", 2 "b"
+fn definition() -> TreeDefinition {
+    TreeDefinition::build(&Sequence(vec![Action("a"), Action("</script><b>")]))
+}
+
+fn traces(ticks: Vec<Vec<(usize, Status)>>) -> Vec<TickTrace> {
+    ticks
+        .into_iter()
+        .enumerate()
+        .map(|(i, states)| TickTrace {
+            tick_id: i as u64 + 1,
+            states: states.into_iter().collect::<HashMap<_, _>>(),
+        })
+        .collect()
+}
+
+fn run() -> Report {
+    Report::new(
+        &definition(),
+        traces(vec![
+            vec![(0, Running), (1, Success), (2, Running)],
+            vec![(0, Running), (2, Running)],
+            vec![(0, Running), (2, Running)],
+            vec![(0, Failure), (2, Failure)],
+        ]),
+    )
+}
+
+/// The `<title>` of every status rect, in document order.
+fn spans(svg: &str) -> Vec<&str> {
+    svg.match_indices("<title>")
+        .map(|(at, _)| &svg[at + 7..at + svg[at..].find("</title>").unwrap()])
+        .filter(|t| t.contains(", ticks "))
+        .collect()
+}
+
+#[test]
+fn svg_merges_runs_of_equal_status_per_node() {
+    let svg = run().to_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    assert_eq!(
+        spans(&svg),
+        [
+            "Sequence: Running, ticks 1–3",
+            "Sequence: Failure, ticks 4–4",
+            "\"a\": Success, ticks 1–1",
+            "\"&lt;/script&gt;&lt;b&gt;\": Running, ticks 1–3",
+            "\"&lt;/script&gt;&lt;b&gt;\": Failure, ticks 4–4",
+        ]
+    );
+    assert!(svg.contains("4 ticks (1–4)"));
+}
+
+#[test]
+fn svg_of_no_ticks_still_lists_the_tree() {
+    let svg = Report::new(&definition(), Vec::new()).title("empty").to_svg();
+    assert!(svg.contains(">empty</text>"));
+    assert!(svg.contains("no ticks"));
+    assert!(svg.contains(">\"a\"</text>"));
+    assert!(spans(&svg).is_empty());
+}
+
+#[test]
+fn html_inlines_the_frames_and_needs_no_server() {
+    let html = run().title("docking <retry>").to_html();
+    assert!(html.contains("<title>docking &lt;retry&gt;</title>"));
+    assert!(
+        !html.contains("</script><b>"),
+        "labels must not close the inline script"
+    );
+
+    let start = html
+        .find("<script id=\"bonsai-report\" type=\"application/json\">")
+        .unwrap();
+    let body = &html[start..];
+    let json = &body[body.find('>').unwrap() + 1..body.find("</script>").unwrap()];
+    let frames: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(frames[0]["root"]["children"][1]["label"], "\"</script><b>\"");
+    let ticks = frames[1]["replay"]["ticks"].as_array().unwrap();
+    assert_eq!(ticks.len(), 4);
+    assert_eq!(ticks[3]["trace"]["states"]["2"], "failure");
+
+    // The frames precede the page script, which plays them instead of connecting.
+    assert!(start < html.find("function start()").unwrap());
+}
+
+#[test]
+fn html_loads_nothing_from_the_network() {
+    let html = run().to_html();
+    let sources: Vec<&str> = html
+        .match_indices("src=")
+        .map(|(at, _)| html[at + 4..].split(['>', ' ']).next().unwrap())
+        .collect();
+    assert!(
+        sources.iter().all(|src| !src.contains("//")),
+        "external sources: {sources:?}"
+    );
+    assert!(html.contains("window.d3 = "), "d3 stand-in inlined");
+    assert!(!html.contains("d3js.org"), "the CDN script is replaced");
+}
+
+#[test]
+fn d3_stand_in_exports_everything_the_page_uses() {
+    let page = include_str!("../src/index.html");
+    let stand_in = include_str!("../src/d3_lite.js");
+    let exports = stand_in
+        .split("window.d3 = {")
+        .nth(1)
+        .unwrap()
+        .split('}')
+        .next()
+        .unwrap();
+    let exports: Vec<&str> = exports.split(',').map(str::trim).collect();
+    // Calls, not the `/d3.v7.min.js` of the CDN script.
+    for (at, _) in page.match_indices("d3.").filter(|&(at, _)| !page[..at].ends_with('/')) {
+        let name: String = page[at + 3..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        assert!(exports.contains(&name.as_str()), "d3.{name} is not in the stand-in");
+    }
+}
+
+#[test]
+fn from_recording_keeps_timing_and_blackboards() {
+    let mut recording = Recording::read(std::io::Cursor::new(
+        b"{\"format\":\"bonsai-trace\",\"version\":1,\"start_unix_ms\":42,\"tree\":{\"root\":{\"id\":0,\"node_type\":\"Action\",\"label\":\"go\",\"children\":[]}}}\n".to_vec(),
+    ))
+    .unwrap();
+    recording.ticks.push(bonsai_bt::recording::RecordedTick {
+        t: 0.25,
+        dt: Some(0.25),
+        trace: traces(vec![vec![(0, Success)]]).remove(0),
+        blackboard: Some(serde_json::json!({"battery": 0.5})),
+    });
+    let html = Report::from_recording(&recording).to_html();
+    assert!(html.contains("\"start_unix_ms\":42"));
+    assert!(html.contains("\"t\":0.25"));
+    assert!(html.contains("\"battery\":0.5"));
+}
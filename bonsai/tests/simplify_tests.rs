@@ -0,0 +1,144 @@
+//! Tests for `Behavior::simplify`.
+
+use bonsai_bt::{
+    Action, ActionArgs, AlwaysSucceed, Behavior, Event, Failure, Float, If, Invert, Running, Select, Sequence, Status,
+    Success, UpdateArgs, Wait, WaitForever, WhenAll, While, BT,
+};
+
+#[test]
+fn removes_redundant_structure() {
+    let a = || Action("a");
+    assert_eq!(Sequence(vec![a()]).simplify(), a());
+    assert_eq!(Select(vec![Select(vec![a()])]).simplify(), a());
+    assert_eq!(
+        Select(vec![
+            a(),
+            Select(vec![Action("b"), Action("c")]),
+            Sequence(vec![Action("d"), a()])
+        ])
+        .simplify(),
+        Select(vec![a(), Action("b"), Action("c"), Sequence(vec![Action("d"), a()])])
+    );
+    assert_eq!(
+        Invert(Box::new(Invert(Box::new(Invert(Box::new(a())))))).simplify(),
+        Invert(Box::new(a()))
+    );
+    assert_eq!(
+        AlwaysSucceed(Box::new(Invert(Box::new(AlwaysSucceed(Box::new(a())))))).simplify(),
+        AlwaysSucceed(Box::new(a()))
+    );
+    // Deep inside other nodes too.
+    let tree: Behavior<&str> = While(Box::new(WaitForever), vec![Sequence(vec![Wait(1.0)])]);
+    assert_eq!(tree.simplify(), While(Box::new(WaitForever), vec![Wait(1.0)]));
+}
+
+#[test]
+fn keeps_memoryless_nodes_that_restart_their_child() {
+    let reactive: Behavior<&str> = Sequence(vec![Wait(1.0)]).memory(false);
+    assert_eq!(reactive.clone().simplify(), reactive);
+
+    let nested = Sequence(vec![Action("a"), Sequence(vec![Action("b"), Wait(1.0)]).memory(false)]).memory(false);
+    assert_eq!(
+        nested.simplify(),
+        Sequence(vec![Action("a"), Action("b"), Wait(1.0)]).memory(false)
+    );
+    // A memoryless node inside one with memory is not the same thing.
+    let mixed = Sequence(vec![
+        Action("a"),
+        Sequence(vec![Action("b"), Action("c")]).memory(false),
+    ]);
+    assert_eq!(mixed.clone().simplify(), mixed);
+}
+
+/// xorshift64*, enough randomness for generating trees and oracles.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// A random tree, biased towards the redundant shapes `simplify` rewrites.
+fn random_tree(rng: &mut Rng, depth: u32) -> Behavior<u8> {
+    let kind = if depth == 0 { rng.below(2) } else { rng.below(11) };
+    let children = |rng: &mut Rng| {
+        let n = 1 + rng.below(3);
+        (0..n).map(|_| random_tree(rng, depth - 1)).collect::<Vec<_>>()
+    };
+    match kind {
+        0 => Action(rng.below(4) as u8),
+        1 => Wait(0.1 * (1 + rng.below(4)) as Float),
+        2 => Sequence(children(rng)),
+        3 => Select(children(rng)),
+        4 => Sequence(children(rng)).memory(false),
+        5 => Select(children(rng)).memory(false),
+        6 => Invert(Box::new(random_tree(rng, depth - 1))),
+        7 => AlwaysSucceed(Box::new(random_tree(rng, depth - 1))),
+        8 => If(
+            Box::new(random_tree(rng, depth - 1)),
+            Box::new(random_tree(rng, depth - 1)),
+            Box::new(random_tree(rng, depth - 1)),
+        ),
+        9 => WhenAll(children(rng)),
+        _ => Sequence(vec![random_tree(rng, depth - 1)]),
+    }
+}
+
+/// One tick: its result, with the remaining time only once finished, and
+/// the actions it called.
+type Tick = (Option<(Status, Option<Float>)>, Vec<u8>);
+
+/// Tick `tree` once per entry of `oracle` and `dts`, with actions answering
+/// from `oracle`, a table of statuses by tick and action.
+fn run(tree: Behavior<u8>, oracle: &[[Status; 4]], dts: &[Float]) -> Vec<Tick> {
+    let mut bt = BT::new(tree, ());
+    let mut results = Vec::new();
+    for (answers, &dt) in oracle.iter().zip(dts) {
+        let mut calls = Vec::new();
+        let e: Event = UpdateArgs { dt }.into();
+        let result = bt.tick(&e, &mut |args: ActionArgs<Event, u8>, _| {
+            calls.push(*args.action);
+            match answers[*args.action as usize] {
+                // An arbitrary time, which `simplify` may not preserve.
+                Running => (Running, args.dt * 0.25),
+                status => (status, args.dt * 0.5),
+            }
+        });
+        let result = result.map(|(status, dt)| (status, (status != Running).then_some(dt)));
+        results.push((result, calls));
+    }
+    results
+}
+
+#[test]
+fn simplified_trees_tick_like_the_original() {
+    let mut rewritten = 0;
+    for seed in 1..=500 {
+        let mut rng = Rng(seed);
+        let tree = random_tree(&mut rng, 4);
+        let simplified = tree.clone().simplify();
+        if simplified != tree {
+            rewritten += 1;
+        }
+        let oracle: Vec<[Status; 4]> = (0..30)
+            .map(|_| [(); 4].map(|_| [Running, Success, Failure][rng.below(3) as usize]))
+            .collect();
+        let dts: Vec<Float> = (0..30).map(|_| [0.05, 0.1, 0.3][rng.below(3) as usize]).collect();
+        assert_eq!(
+            run(simplified.clone(), &oracle, &dts),
+            run(tree.clone(), &oracle, &dts),
+            "seed {seed}: {tree:?} simplified to {simplified:?}"
+        );
+        // Simplifying is idempotent.
+        assert_eq!(simplified.clone().simplify(), simplified);
+    }
+    assert!(rewritten > 250, "only {rewritten} trees had anything to simplify");
+}
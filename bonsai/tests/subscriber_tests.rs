@@ -0,0 +1,88 @@
+//! Tests for in-process tick subscribers (`BT::subscribe`, `BT::on_tick`).
+
+use std::sync::{Arc, Mutex};
+
+use bonsai_bt::{Action, ActionArgs, Event, Running, Sequence, Status, Success, UpdateArgs, BT};
+
+fn tree() -> BT<&'static str, ()> {
+    BT::new(Sequence(vec![Action("a"), Action("b")]), ())
+}
+
+fn tick(bt: &mut BT<&'static str, ()>, b: Status) {
+    let e: Event = UpdateArgs { dt: 0.1 }.into();
+    bt.tick(&e, &mut |args: ActionArgs<Event, &str>, _| match *args.action {
+        "a" => (Success, 0.0),
+        _ => (b, 0.0),
+    });
+}
+
+#[test]
+fn every_subscriber_gets_every_trace() {
+    let mut bt = tree();
+    let first = bt.subscribe();
+    let second = bt.subscribe();
+    tick(&mut bt, Running);
+    tick(&mut bt, Success);
+
+    for rx in [first, second] {
+        let traces: Vec<_> = rx.try_iter().collect();
+        assert_eq!(traces.iter().map(|t| t.tick_id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(traces[0].states.get(&2), Some(&Running));
+        assert_eq!(traces[1].states.get(&0), Some(&Success));
+    }
+}
+
+#[test]
+fn full_channels_drop_and_count_without_blocking() {
+    let mut bt = tree();
+    let rx = bt.subscribe_with_capacity(1);
+    for _ in 0..3 {
+        tick(&mut bt, Running);
+    }
+    assert_eq!(bt.dropped_subscriber_traces(), 2);
+    assert_eq!(rx.try_iter().map(|t| t.tick_id).collect::<Vec<_>>(), [1]);
+}
+
+#[test]
+fn dropped_receivers_are_unsubscribed() {
+    let mut bt = tree();
+    drop(bt.subscribe_with_capacity(1));
+    tick(&mut bt, Running);
+    tick(&mut bt, Running);
+    assert_eq!(bt.dropped_subscriber_traces(), 0);
+}
+
+#[test]
+fn callbacks_run_after_each_tick() {
+    let mut bt = tree();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    bt.on_tick(move |trace| sink.lock().unwrap().push((trace.tick_id, trace.states.len())));
+    tick(&mut bt, Running);
+    tick(&mut bt, Success);
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [(1, 3), (2, 2)],
+        "the second tick resumes at \"b\""
+    );
+}
+
+#[test]
+fn clones_do_not_inherit_subscribers() {
+    let mut bt = tree();
+    let rx = bt.subscribe();
+    let mut clone = bt.clone();
+    tick(&mut clone, Running);
+    assert!(rx.try_recv().is_err());
+}
+
+#[cfg(feature = "visualize")]
+#[test]
+fn subscribers_share_the_telemetry_tick() {
+    use bonsai_bt::recording::FlightRecorder;
+    let mut bt = tree().with_flight_recorder(FlightRecorder::new(8));
+    let rx = bt.subscribe();
+    tick(&mut bt, Running);
+    assert_eq!(rx.try_recv().unwrap().states.len(), 3);
+    assert_eq!(bt.flight_recorder().unwrap().len(), 1);
+}
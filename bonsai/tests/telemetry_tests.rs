@@ -139,3 +139,65 @@ fn memoryless_variants_appear_in_tree_definition() {
     assert_eq!(def.root.children[1].node_type, "MemorylessSelector");
     assert_eq!(def.root.children[1].children.len(), 2);
 }
+
+/// Names add no nodes: the ids stay those of the unnamed tree, and each
+/// `TreeNode` carries its node's name.
+#[test]
+fn named_nodes_keep_their_ids_in_tree_definition() {
+    use bonsai_bt::path::NodeIndex;
+    use bonsai_bt::telemetry::{build_node_metas, TreeDefinition};
+    use bonsai_bt::Action;
+    use Act::*;
+
+    let plain = Sequence(vec![Action(A), Sequence(vec![Action(B), Action(C)])]);
+    let named = Sequence(vec![
+        Action(A),
+        Sequence(vec![Action(B), Action(C).named("c")]).named("inner"),
+    ])
+    .named("root");
+    let sizes = |b: &Behavior<Act>| build_node_metas(b).iter().map(|m| m.subtree_size).collect::<Vec<_>>();
+    assert_eq!(sizes(&named), sizes(&plain));
+
+    let def = TreeDefinition::build(&named);
+    assert_eq!(def.root.name.as_deref(), Some("root"));
+    assert_eq!(def.root.node_type, "Sequence");
+    assert_eq!(def.root.children[1].id, 2);
+    assert_eq!(def.root.children[1].name.as_deref(), Some("inner"));
+    assert_eq!(def.root.children[1].children[1].label, "C");
+
+    let json = serde_json::to_value(&def).unwrap();
+    assert_eq!(json["root"]["name"], "root");
+    assert!(json["root"]["children"][0].get("name").is_none(), "{json}");
+
+    let index = def.node_index();
+    assert_eq!(index.id(&"/inner/c".parse().unwrap()), Some(4));
+    assert_eq!(index.path(4), Some("/c".parse().unwrap()));
+    assert_eq!(index.path(3), NodeIndex::new(&named).path(3));
+}
+
+#[test]
+fn metadata_reaches_tree_definition() {
+    use bonsai_bt::telemetry::TreeDefinition;
+    use bonsai_bt::Action;
+    use Act::*;
+
+    let tree = Sequence(vec![
+        Action(A).meta("reads", "target").named("a").meta("owner", "ai"),
+        Action(B),
+    ]);
+    let def = TreeDefinition::build(&tree);
+    let a = &def.root.children[0];
+    assert_eq!((a.id, a.name.as_deref(), a.label.as_str()), (1, Some("a"), "A"));
+    assert_eq!(
+        a.meta.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect::<Vec<_>>(),
+        [("owner", "ai"), ("reads", "target")]
+    );
+    assert_eq!(def.root.children[1].id, 2);
+
+    let json = serde_json::to_value(&def).unwrap();
+    assert_eq!(
+        json["root"]["children"][0]["meta"],
+        serde_json::json!({ "owner": "ai", "reads": "target" })
+    );
+    assert!(json["root"].get("meta").is_none(), "{json}");
+}
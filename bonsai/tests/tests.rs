@@ -1,8 +1,36 @@
 mod behavior_tests;
 mod blackboard_tests;
 mod bt_tests;
+mod diagram_tests;
+mod diff_tests;
+mod dsl_tests;
 mod dynamic_behavior_tests;
 mod memoryless_allocations;
+mod path_tests;
+mod profiler_tests;
+mod reload_tests;
+mod simplify_tests;
+mod subscriber_tests;
+mod tracer_tests;
+mod visit_tests;
+
+#[cfg(feature = "visualize")]
+mod chrome_trace_tests;
+
+#[cfg(feature = "visualize")]
+mod debugger_tests;
+
+#[cfg(feature = "schemars")]
+mod json_schema_tests;
+
+#[cfg(feature = "serde")]
+mod readable_tests;
+
+#[cfg(feature = "visualize")]
+mod recording_tests;
+
+#[cfg(feature = "visualize")]
+mod report_tests;
 
 #[cfg(feature = "visualize")]
 mod telemetry_tests;
@@ -12,3 +40,6 @@ mod telemetry_tracer_tests;
 
 #[cfg(feature = "visualize")]
 mod visualizer_server_tests;
+
+#[cfg(feature = "xml")]
+mod xml_tests;
@@ -0,0 +1,272 @@
+//! Acceptance tests for user-supplied tracers through `BT::tick_with_tracer`.
+//! Runs without the `visualize` feature: the `Tracer` hooks are always on.
+
+use bonsai_bt::tracer::Tracer;
+use bonsai_bt::{
+    Action, ActionArgs, After, Event, Failure, Float, Race, Running, Sequence, Status, Success, UpdateArgs, WhenAll,
+    While, BT,
+};
+
+#[derive(Clone, Debug)]
+enum Act {
+    A,
+    B,
+    C,
+}
+
+#[derive(Debug, PartialEq)]
+enum Hook {
+    Enter(usize),
+    Exit(usize, Status),
+    Halt(usize),
+}
+
+#[derive(Default)]
+struct EventLog {
+    hooks: Vec<Hook>,
+    dts: Vec<(usize, Float)>,
+}
+
+impl Tracer for EventLog {
+    const IS_RECORDING: bool = true;
+    fn enter(&mut self, id: usize) {
+        self.hooks.push(Hook::Enter(id));
+    }
+    fn exit(&mut self, id: usize, status: Status, dt: Float) {
+        self.hooks.push(Hook::Exit(id, status));
+        self.dts.push((id, dt));
+    }
+    fn halt(&mut self, id: usize) {
+        self.hooks.push(Hook::Halt(id));
+    }
+}
+
+fn dt_event(dt: Float) -> Event {
+    UpdateArgs { dt }.into()
+}
+
+#[test]
+fn enter_exit_nest_in_preorder() {
+    use Act::*;
+    use Hook::*;
+    let mut bt = BT::new(Sequence(vec![Action(A), Action(B)]), ());
+    let mut log = EventLog::default();
+
+    let result = bt.tick_with_tracer(
+        &dt_event(1.0),
+        &mut |args: ActionArgs<Event, Act>, _| match *args.action {
+            A => (Success, 0.25),
+            B => (Running, 0.0),
+            C => unreachable!(),
+        },
+        &mut log,
+    );
+
+    assert_eq!(result, Some((Running, 0.0)));
+    assert_eq!(
+        log.hooks,
+        vec![
+            Enter(0),
+            Enter(1),
+            Exit(1, Success),
+            Enter(2),
+            Exit(2, Running),
+            Exit(0, Running)
+        ]
+    );
+    assert_eq!(log.dts[0], (1, 0.25), "exit reports the remaining dt of the node");
+}
+
+#[test]
+fn tick_with_tracer_mirrors_tick_bookkeeping() {
+    let mut bt = BT::new(Action(Act::A), ());
+    let mut log = EventLog::default();
+    let e = dt_event(1.0);
+
+    assert!(bt.tick_with_tracer(&e, &mut |_, _| (Success, 0.0), &mut log).is_some());
+    assert_eq!(bt.tick_count(), 1);
+    assert!(bt.is_finished());
+    assert!(bt.tick_with_tracer(&e, &mut |_, _| (Success, 0.0), &mut log).is_none());
+}
+
+#[test]
+fn race_halts_losing_branches() {
+    use Act::*;
+    use Hook::*;
+    let mut bt = BT::new(Race(vec![Action(A), Action(B), Action(C)]), 0u32);
+    let tick = |bt: &mut BT<Act, u32>, log: &mut EventLog| {
+        bt.tick_with_tracer(
+            &dt_event(1.0),
+            &mut |args: ActionArgs<Event, Act>, n: &mut u32| match *args.action {
+                B if *n > 0 => (Failure, 0.0),
+                B => {
+                    *n += 1;
+                    (Running, 0.0)
+                }
+                _ => (Running, 0.0),
+            },
+            log,
+        )
+    };
+
+    // All three start, then B finishes first.
+    tick(&mut bt, &mut EventLog::default());
+    let mut log = EventLog::default();
+    tick(&mut bt, &mut log);
+
+    assert!(log.hooks.contains(&Halt(1)), "A lost the race: {:?}", log.hooks);
+    assert!(log.hooks.contains(&Halt(3)), "C lost the race: {:?}", log.hooks);
+    assert!(!log.hooks.contains(&Halt(2)), "the winner is not halted");
+    assert_eq!(log.hooks.last(), Some(&Exit(0, Failure)));
+}
+
+#[test]
+fn when_all_halts_remaining_children_on_failure() {
+    use Act::*;
+    use Hook::*;
+    let mut bt = BT::new(WhenAll(vec![Action(A), Action(B), Action(C)]), 0u32);
+    let tick = |bt: &mut BT<Act, u32>, log: &mut EventLog| {
+        bt.tick_with_tracer(
+            &dt_event(1.0),
+            &mut |args: ActionArgs<Event, Act>, n: &mut u32| match *args.action {
+                A => (Success, 0.0),
+                B if *n > 0 => (Failure, 0.0),
+                B => {
+                    *n += 1;
+                    (Running, 0.0)
+                }
+                C => (Running, 0.0),
+            },
+            log,
+        )
+    };
+
+    // A succeeds and B and C start, then B fails.
+    tick(&mut bt, &mut EventLog::default());
+    let mut log = EventLog::default();
+    tick(&mut bt, &mut log);
+
+    let halts: Vec<_> = log.hooks.iter().filter(|h| matches!(h, Halt(_))).collect();
+    assert_eq!(halts, vec![&Halt(3)], "only the unfinished child is halted");
+}
+
+#[test]
+fn parallel_nodes_do_not_halt_children_that_never_started() {
+    use Act::*;
+    use Hook::*;
+    // B finishes on the first tick, before C is ever ticked.
+    let run = |behavior| {
+        let mut bt = BT::new(behavior, ());
+        let mut log = EventLog::default();
+        bt.tick_with_tracer(
+            &dt_event(1.0),
+            &mut |args: ActionArgs<Event, Act>, _| match *args.action {
+                B => (Failure, 0.0),
+                _ => (Running, 0.0),
+            },
+            &mut log,
+        );
+        log.hooks
+            .into_iter()
+            .filter(|h| matches!(h, Halt(_)))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(run(Race(vec![Action(A), Action(B), Action(C)])), vec![Halt(1)]);
+    assert_eq!(run(WhenAll(vec![Action(A), Action(B), Action(C)])), vec![Halt(1)]);
+    assert_eq!(run(After(vec![Action(A), Action(B), Action(C)])), vec![Halt(1)]);
+}
+
+#[test]
+fn after_halts_children_not_yet_succeeded() {
+    use Act::*;
+    use Hook::*;
+    let mut bt = BT::new(After(vec![Action(A), Action(B), Action(C)]), ());
+    let mut log = EventLog::default();
+
+    bt.tick_with_tracer(
+        &dt_event(1.0),
+        &mut |args: ActionArgs<Event, Act>, _| match *args.action {
+            A => (Success, 0.5),
+            B => (Running, 0.0),
+            C => (Failure, 0.0),
+        },
+        &mut log,
+    );
+
+    let halts: Vec<_> = log.hooks.iter().filter(|h| matches!(h, Halt(_))).collect();
+    assert_eq!(halts, vec![&Halt(2)]);
+    assert_eq!(log.hooks.last(), Some(&Exit(0, Failure)));
+}
+
+#[test]
+fn while_halts_body_when_condition_terminates() {
+    use Act::*;
+    use Hook::*;
+    // ids: 0 While, 1 cond A, 2 body B, 3 body C
+    let mut bt = BT::new(While(Box::new(Action(A)), vec![Action(B), Action(C)]), 0u32);
+    let e = dt_event(1.0);
+
+    let tick = |bt: &mut BT<Act, u32>, log: &mut EventLog| {
+        bt.tick_with_tracer(
+            &e,
+            &mut |args: ActionArgs<Event, Act>, n: &mut u32| match *args.action {
+                A => {
+                    *n += 1;
+                    if *n > 1 {
+                        (Success, 0.0)
+                    } else {
+                        (Running, 0.0)
+                    }
+                }
+                B => (Success, args.dt),
+                C => (Running, 0.0),
+            },
+            log,
+        )
+    };
+
+    let mut first = EventLog::default();
+    tick(&mut bt, &mut first);
+    assert!(!first.hooks.iter().any(|h| matches!(h, Halt(_))));
+
+    let mut second = EventLog::default();
+    tick(&mut bt, &mut second);
+    assert_eq!(
+        second.hooks,
+        vec![Enter(0), Enter(1), Exit(1, Success), Halt(3), Exit(0, Success)]
+    );
+}
+
+#[test]
+fn while_does_not_halt_a_body_that_never_started() {
+    use Act::*;
+    use Hook::*;
+    // ids: 0 While, 1 cond A, 2 body B, 3 body C
+    let mut bt = BT::new(While(Box::new(Action(A)), vec![Action(B), Action(C)]), ());
+    let mut log = EventLog::default();
+
+    // The condition ends on the first tick, before the body ran at all.
+    bt.tick_with_tracer(&dt_event(1.0), &mut |_, _| (Success, 0.0), &mut log);
+    assert_eq!(log.hooks, vec![Enter(0), Enter(1), Exit(1, Success), Exit(0, Success)]);
+}
+
+#[test]
+fn noop_tracer_ids_are_sentinels() {
+    struct Ids(Vec<usize>);
+    impl Tracer for Ids {
+        const IS_RECORDING: bool = false;
+        fn enter(&mut self, id: usize) {
+            self.0.push(id);
+        }
+    }
+
+    let mut bt = BT::new(Sequence(vec![Action(Act::A)]), ());
+    let mut ids = Ids(Vec::new());
+    bt.tick_with_tracer(&dt_event(1.0), &mut |_, _| (Success, 0.0), &mut ids);
+    assert_eq!(
+        ids.0,
+        vec![0, usize::MAX],
+        "only the root id is known without recording"
+    );
+}
@@ -0,0 +1,165 @@
+//! Tests for the traversal API: `children`, `visit`, `fold`, `map_actions`.
+
+use bonsai_bt::visit::Visitor;
+use bonsai_bt::{Action, Behavior, If, Invert, Select, Sequence, Wait, WaitForever, WhenAny, While, WhileAll};
+
+fn tree() -> Behavior<&'static str> {
+    While(
+        Box::new(WaitForever),
+        vec![
+            If(
+                Box::new(Action("see")),
+                Box::new(Invert(Box::new(Action("flee")))),
+                Box::new(Sequence(vec![Action("walk"), Wait(1.0)]).memory(false)),
+            ),
+            WhenAny(vec![Action("rest")]),
+        ],
+    )
+}
+
+#[test]
+fn children_follow_preorder_id_order() {
+    let tree = tree();
+    let children = tree.children();
+    assert_eq!(children.len(), 3, "condition, then the body");
+    assert_eq!(*children[0], WaitForever);
+    let branches = children[1].children();
+    assert_eq!(*branches[0], Action("see"));
+    assert_eq!(branches[1].children(), [&Action("flee")]);
+    assert_eq!(branches[2].children(), [&Action("walk"), &Wait(1.0)]);
+    assert!(Action("x").children().is_empty());
+}
+
+#[test]
+fn children_mut_edits_in_place() {
+    fn slow_down(node: &mut Behavior<&str>) {
+        if let Behavior::Wait(dt) = node.unnamed_mut() {
+            *dt *= 2.0;
+        }
+        node.children_mut().into_iter().for_each(slow_down);
+    }
+    let mut tree = WhileAll(
+        Box::new(Wait(1.0)),
+        vec![Select(vec![Wait(0.5).named("nap"), Action("a")])],
+    );
+    slow_down(&mut tree);
+    assert_eq!(
+        tree,
+        WhileAll(
+            Box::new(Wait(2.0)),
+            vec![Select(vec![Wait(1.0).named("nap"), Action("a")])]
+        )
+    );
+}
+
+#[test]
+fn visitors_get_paths_and_can_skip_subtrees() {
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl Visitor<&'static str> for Log {
+        fn enter(&mut self, path: &[usize], node: &Behavior<&'static str>) -> bool {
+            self.0.push(format!("enter {path:?}"));
+            // Don't look inside `If`s.
+            !matches!(node, Behavior::If(..))
+        }
+
+        fn leave(&mut self, path: &[usize], _: &Behavior<&'static str>) {
+            self.0.push(format!("leave {path:?}"));
+        }
+    }
+
+    let mut log = Log::default();
+    tree().visit(&mut log);
+    assert_eq!(
+        log.0,
+        [
+            "enter []",
+            "enter [0]",
+            "leave [0]",
+            "enter [1]",
+            "leave [1]",
+            "enter [2]",
+            "enter [2, 0]",
+            "leave [2, 0]",
+            "leave [2]",
+            "leave []",
+        ]
+    );
+}
+
+#[test]
+fn fold_walks_in_preorder() {
+    let actions = tree().fold(Vec::new(), |mut out, path, node| {
+        if let Behavior::Action(action) = node {
+            out.push((path.to_vec(), *action));
+        }
+        out
+    });
+    assert_eq!(
+        actions,
+        [
+            (vec![1, 0], "see"),
+            (vec![1, 1, 0], "flee"),
+            (vec![1, 2, 0], "walk"),
+            (vec![2, 0], "rest"),
+        ]
+    );
+    assert_eq!(tree().fold(0, |count, _, _| count + 1), 11);
+}
+
+#[test]
+fn wrappers_are_looked_through_like_node_paths() {
+    use bonsai_bt::path::{NodeIndex, NodePath};
+
+    // ids: 0 Sequence, 1 "aim", 2 Invert, 3 "miss"
+    let tree = Sequence(vec![
+        Action("aim").named("aim").meta("reads", "target"),
+        Invert(Box::new(Action("miss"))).named("dodge"),
+    ])
+    .meta("owner", "nav");
+    assert_eq!(tree.children().len(), 2, "the Meta's children are the Sequence's");
+
+    let nodes = tree.fold(Vec::new(), |mut out, path, node| {
+        out.push((path.to_vec(), node.name().map(str::to_string), node.metadata()));
+        out
+    });
+    let index = NodeIndex::new(&tree);
+    for (id, (path, _, _)) in nodes.iter().enumerate() {
+        let node_path = path.iter().fold(NodePath::root(), |p, &i| p.child(i));
+        assert_eq!(index.id(&node_path), Some(id), "{node_path}");
+    }
+    let names: Vec<_> = nodes.iter().map(|(_, name, _)| name.as_deref()).collect();
+    assert_eq!(names, [None, Some("aim"), Some("dodge"), None]);
+    assert_eq!(nodes[0].2["owner"], "nav");
+    assert_eq!(nodes[1].2["reads"], "target");
+}
+
+#[test]
+fn map_actions_keeps_the_shape() {
+    #[derive(Clone, Debug, PartialEq)]
+    enum Act {
+        Named(String),
+    }
+
+    let mut seen = Vec::new();
+    let mapped = tree().map_actions(|name| {
+        seen.push(name);
+        Act::Named(name.to_uppercase())
+    });
+    assert_eq!(seen, ["see", "flee", "walk", "rest"]);
+    assert_eq!(
+        mapped,
+        While(
+            Box::new(WaitForever),
+            vec![
+                If(
+                    Box::new(Action(Act::Named("SEE".to_string()))),
+                    Box::new(Invert(Box::new(Action(Act::Named("FLEE".to_string()))))),
+                    Box::new(Sequence(vec![Action(Act::Named("WALK".to_string())), Wait(1.0)]).memory(false)),
+                ),
+                WhenAny(vec![Action(Act::Named("REST".to_string()))]),
+            ],
+        )
+    );
+}
@@ -41,10 +41,14 @@ fn start_server() -> (u16, SyncSender<TickTrace>) {
 /// `WebSocket<TcpStream>` (not `WebSocket<MaybeTlsStream<_>>` like
 /// `tungstenite::connect` would) — simpler to set TCP-level timeouts on.
 fn ws_connect(port: u16) -> tungstenite::WebSocket<TcpStream> {
+    ws_connect_path(port, "/")
+}
+
+fn ws_connect_path(port: u16, path: &str) -> tungstenite::WebSocket<TcpStream> {
     let stream = TcpStream::connect(("127.0.0.1", port)).expect("tcp connect");
     stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
     stream.set_write_timeout(Some(Duration::from_secs(2))).unwrap();
-    let url = format!("ws://127.0.0.1:{port}/");
+    let url = format!("ws://127.0.0.1:{port}{path}");
     let (ws, _resp) = tungstenite::client::client(url, stream).expect("ws handshake");
     ws
 }
@@ -298,3 +302,477 @@ fn with_telemetry_at_re_attaches_on_same_port() {
         .with_telemetry_at("127.0.0.1", port)
         .expect("second attach on same port");
 }
+
+#[test]
+fn ws_tick_and_profile_frames_are_distinguishable() {
+    use bonsai_bt::profiler::{NodeProfile, ProfileReport, TimingStats};
+    use bonsai_bt::telemetry::TelemetryFrame;
+
+    let (listener, port) = bind_localhost_random();
+    let (tx, rx) = sync_channel::<TelemetryFrame>(16);
+    let _ = bonsai_bt::spawn_server(listener, fixture_tree_json(), rx).expect("spawn_server");
+    let mut ws = ws_connect(port);
+    let _ = read_text(&mut ws);
+
+    let mut states = HashMap::new();
+    states.insert(0, Status::Running);
+    tx.send(TelemetryFrame::Tick(TickTrace { tick_id: 3, states })).unwrap();
+    tx.send(TelemetryFrame::Profile {
+        profile: ProfileReport {
+            ticks: 3,
+            nodes: vec![NodeProfile {
+                id: 0,
+                label: Some("a".into()),
+                samples: 3,
+                inclusive: TimingStats::default(),
+                exclusive: TimingStats {
+                    p99_ns: 42,
+                    ..TimingStats::default()
+                },
+            }],
+        },
+    })
+    .unwrap();
+
+    // Tick frames stay wire-compatible with a bare `TickTrace`.
+    let trace: TickTrace = serde_json::from_str(&read_text(&mut ws)).expect("tick frame parses");
+    assert_eq!(trace.tick_id, 3);
+
+    let profile: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(profile["profile"]["ticks"], 3);
+    assert_eq!(profile["profile"]["nodes"][0]["exclusive"]["p99_ns"], 42);
+}
+
+/// Send a `GET {path}` and read the whole response, tolerating the RST that
+/// follows it (see `http_get_root_returns_200`).
+fn http_get(port: u16, path: &str) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).expect("tcp connect");
+    stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    stream
+        .write_all(format!("GET {path} HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n").as_bytes())
+        .unwrap();
+    let mut response = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => response.extend_from_slice(&buf[..n]),
+        }
+    }
+    String::from_utf8(response).expect("response is utf-8")
+}
+
+#[test]
+fn metrics_endpoint_reports_tick_counters() {
+    use bonsai_bt::{ActionArgs, Event, Sequence, UpdateArgs};
+
+    let port = reserve_free_port();
+    let behavior = Sequence(vec![Action("a"), Action("b")]);
+    let mut bt = BT::new(behavior, ()).with_telemetry_at("127.0.0.1", port).unwrap();
+    let e: Event = UpdateArgs { dt: 0.1 }.into();
+    for _ in 0..3 {
+        bt.tick(&e, &mut |_: ActionArgs<Event, &str>, _| (Status::Running, 0.0));
+    }
+
+    let response = http_get(port, "/metrics");
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{response}");
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+    let body = response.split_once("\r\n\r\n").unwrap().1;
+    assert!(
+        body.contains("# TYPE bonsai_ticks_total counter\nbonsai_ticks_total 3\n"),
+        "{body}"
+    );
+    assert!(body.contains("bonsai_ws_clients 0\n"), "{body}");
+    assert!(body.contains("bonsai_dropped_traces_total 0\n"), "{body}");
+    assert!(
+        body.contains(
+            "bonsai_node_status_total{node_id=\"1\",node_type=\"Action\",label=\"\\\"a\\\"\",status=\"running\"} 3\n"
+        ),
+        "{body}"
+    );
+    assert!(body.contains("node_id=\"2\",node_type=\"Action\",label=\"\\\"b\\\"\",status=\"running\"} 0\n"));
+    let running = body
+        .lines()
+        .find_map(|l| l.strip_prefix("bonsai_root_running_seconds "))
+        .and_then(|v| v.parse::<f64>().ok())
+        .expect("root running gauge");
+    assert!(running >= 0.0);
+}
+
+#[test]
+fn metrics_endpoint_is_absent_without_a_tree() {
+    let (port, _tx) = start_server();
+    assert!(http_get(port, "/metrics").starts_with("HTTP/1.1 404"));
+}
+
+#[test]
+fn blackboard_streams_keyframe_then_patches() {
+    use bonsai_bt::{ActionArgs, Event, UpdateArgs};
+
+    let port = reserve_free_port();
+    let mut bt = BT::new(
+        Action("step"),
+        HashMap::<String, i32>::from([("a".into(), 0), ("b".into(), 0)]),
+    )
+    .with_telemetry_at("127.0.0.1", port)
+    .unwrap()
+    .with_telemetry_blackboard();
+    let mut ws = ws_connect(port);
+    read_viewer_greeting(&mut ws);
+    // The acceptor registers the client right after the greeting.
+    std::thread::sleep(Duration::from_millis(100));
+
+    let e: Event = UpdateArgs { dt: 0.1 }.into();
+    let tick = |bt: &mut BT<&str, HashMap<String, i32>>| {
+        bt.tick(&e, &mut |_: ActionArgs<Event, &str>, bb: &mut HashMap<String, i32>| {
+            *bb.get_mut("a").unwrap() += 1;
+            (Status::Running, 0.0)
+        });
+    };
+    tick(&mut bt);
+    tick(&mut bt);
+
+    let first: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(first["tick_id"], 1);
+    assert_eq!(first["blackboard"], serde_json::json!({"snapshot": {"a": 1, "b": 0}}));
+    let second: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(second["tick_id"], 2);
+    assert_eq!(second["blackboard"], serde_json::json!({"patch": {"a": 2}}));
+    let trace: TickTrace = serde_json::from_value(second).expect("still parses as a TickTrace");
+    assert_eq!(trace.states.get(&0), Some(&Status::Running));
+}
+
+/// A running BT with telemetry on a fresh port, and a client connected with
+/// `path` that has drained the greeting and been registered.
+fn bt_with_client(path: &str) -> (BT<&'static str, ()>, tungstenite::WebSocket<TcpStream>) {
+    use bonsai_bt::Sequence;
+    let port = reserve_free_port();
+    let bt = BT::new(Sequence(vec![Action("a"), Action("b")]), ())
+        .with_telemetry_at("127.0.0.1", port)
+        .unwrap();
+    let mut ws = ws_connect_path(port, path);
+    read_viewer_greeting(&mut ws);
+    std::thread::sleep(Duration::from_millis(100));
+    (bt, ws)
+}
+
+/// Drain what a `BT`'s server greets every client with: the tree definition,
+/// then the debugger state.
+fn read_bt_greeting(ws: &mut tungstenite::WebSocket<TcpStream>) {
+    let definition: serde_json::Value = serde_json::from_str(&read_text(ws)).unwrap();
+    assert!(definition.get("root").is_some());
+    let debug: serde_json::Value = serde_json::from_str(&read_text(ws)).unwrap();
+    assert_eq!(debug["debug"]["paused"], false);
+}
+
+/// Drain the greeting of a client without [`Role::Control`], which ends by
+/// telling it so.
+///
+/// [`Role::Control`]: bonsai_bt::access::Role::Control
+fn read_viewer_greeting(ws: &mut tungstenite::WebSocket<TcpStream>) {
+    read_bt_greeting(ws);
+    assert_eq!(read_text(ws), r#"{"role":"viewer"}"#);
+}
+
+/// Tick `bt` with "a" → `a`, "b" → `b`.
+fn tick_ab(bt: &mut BT<&'static str, ()>, a: Status, b: Status) {
+    use bonsai_bt::{ActionArgs, Event, UpdateArgs};
+    let e: Event = UpdateArgs { dt: 0.1 }.into();
+    bt.tick(&e, &mut |args: ActionArgs<Event, &str>, _| match *args.action {
+        "a" => (a, 0.0),
+        _ => (b, 0.0),
+    });
+}
+
+#[test]
+fn delta_clients_get_keyframe_then_changes_only() {
+    let (mut bt, mut ws) = bt_with_client("/?proto=delta");
+    tick_ab(&mut bt, Status::Running, Status::Running);
+    tick_ab(&mut bt, Status::Success, Status::Running);
+
+    let key: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(key["tick_id"], 1);
+    assert_eq!(key["key"], true);
+    assert_eq!(key["delta"], serde_json::json!([[0, 1], [1, 1]]), "only visited nodes");
+
+    let delta: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(delta["tick_id"], 2);
+    assert_eq!(delta["key"], false);
+    // Root stays Running and is omitted; a succeeds, b is visited for the first time.
+    assert_eq!(delta["delta"], serde_json::json!([[1, 2], [2, 1]]));
+}
+
+#[test]
+fn binary_clients_get_packed_frames() {
+    let (mut bt, mut ws) = bt_with_client("/?proto=binary");
+    tick_ab(&mut bt, Status::Running, Status::Running);
+    tick_ab(&mut bt, Status::Success, Status::Running);
+
+    let mut read_binary = || match ws.read().expect("ws read") {
+        tungstenite::Message::Binary(b) => b,
+        other => panic!("expected a binary frame, got {other:?}"),
+    };
+    let key = read_binary();
+    assert_eq!(key[0], 1, "keyframe");
+    assert_eq!(u64::from_le_bytes(key[1..9].try_into().unwrap()), 1);
+    assert_eq!(
+        u32::from_le_bytes(key[9..13].try_into().unwrap()),
+        3,
+        "one code per node"
+    );
+    assert_eq!(key[13..], [0b0101], "root and a running, b unvisited");
+
+    let delta = read_binary();
+    assert_eq!(delta[0], 2, "delta");
+    assert_eq!(u64::from_le_bytes(delta[1..9].try_into().unwrap()), 2);
+    assert_eq!(u32::from_le_bytes(delta[9..13].try_into().unwrap()), 2);
+    assert_eq!(delta[13..], [1, 0, 0, 0, 2, 2, 0, 0, 0, 1]);
+}
+
+#[test]
+fn json_clients_still_get_full_tick_traces() {
+    let (mut bt, mut ws) = bt_with_client("/");
+    tick_ab(&mut bt, Status::Running, Status::Running);
+    tick_ab(&mut bt, Status::Success, Status::Running);
+
+    let _ = read_text(&mut ws);
+    let trace: TickTrace = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(trace.tick_id, 2);
+    assert_eq!(trace.states.len(), 3);
+}
+
+fn hub_with_trees() -> (bonsai_bt::hub::TelemetryHub, BT<&'static str, ()>, BT<&'static str, ()>) {
+    use bonsai_bt::hub::TelemetryHub;
+    use bonsai_bt::Sequence;
+    let hub = TelemetryHub::bind("127.0.0.1", 0).unwrap();
+    let nav = BT::new(Sequence(vec![Action("a"), Action("b")]), ())
+        .with_telemetry_hub(&hub, "nav")
+        .unwrap();
+    let arm = BT::new(Action("grasp"), ()).with_telemetry_hub(&hub, "arm").unwrap();
+    (hub, nav, arm)
+}
+
+#[test]
+fn hub_serves_each_tree_under_its_name() {
+    let (hub, mut nav, mut arm) = hub_with_trees();
+    let port = hub.local_addr().port();
+    assert_eq!(hub.trees(), ["arm", "nav"]);
+
+    let listing = http_get(port, "/trees");
+    assert!(listing.starts_with("HTTP/1.1 200"), "{listing}");
+    assert!(listing.ends_with(r#"{"trees":["arm","nav"]}"#), "{listing}");
+    assert!(http_get(port, "/").starts_with("HTTP/1.1 200"));
+    assert!(http_get(port, "/tree/nav").starts_with("HTTP/1.1 200"));
+    assert!(http_get(port, "/tree/nope").starts_with("HTTP/1.1 404"));
+    assert!(http_get(port, "/metrics").starts_with("HTTP/1.1 404"));
+
+    let mut ws = ws_connect_path(port, "/tree/nav");
+    let definition: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(definition["root"]["children"].as_array().map(Vec::len), Some(2));
+    let _debug = read_text(&mut ws);
+    let _role = read_text(&mut ws);
+    std::thread::sleep(Duration::from_millis(100));
+
+    // Only nav's ticks reach nav's clients.
+    tick_ab(&mut arm, Status::Running, Status::Running);
+    tick_ab(&mut nav, Status::Running, Status::Running);
+    let trace: TickTrace = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(trace.tick_id, 1);
+    assert_eq!(trace.states.len(), 2, "nav's root and first child");
+
+    let metrics = http_get(port, "/tree/arm/metrics");
+    assert!(metrics.contains("bonsai_ticks_total 1"), "{metrics}");
+}
+
+#[test]
+fn hub_rejects_unknown_trees_and_bad_names() {
+    let (hub, _nav, _arm) = hub_with_trees();
+    let port = hub.local_addr().port();
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    match tungstenite::client::client(format!("ws://127.0.0.1:{port}/tree/nope"), stream) {
+        Err(tungstenite::HandshakeError::Failure(tungstenite::Error::Http(response))) => {
+            assert_eq!(response.status(), 404)
+        }
+        other => panic!("expected a 404 handshake, got {:?}", other.map(|_| ())),
+    }
+
+    let err = BT::new(Action("x"), ())
+        .with_telemetry_hub(&hub, "no/slashes")
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn hub_unlists_dropped_trees() {
+    let (hub, nav, _arm) = hub_with_trees();
+    drop(nav);
+    let deadline = std::time::Instant::now() + Duration::from_secs(2);
+    while hub.trees() != ["arm"] {
+        assert!(
+            std::time::Instant::now() < deadline,
+            "nav still listed: {:?}",
+            hub.trees()
+        );
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+fn bt_with_access(policy: bonsai_bt::access::AccessPolicy) -> (BT<&'static str, ()>, u16) {
+    let port = reserve_free_port();
+    let bt = BT::new(Action("a"), ())
+        .with_telemetry_access("127.0.0.1", port, policy)
+        .unwrap();
+    (bt, port)
+}
+
+#[test]
+fn token_is_required_for_http_and_websocket() {
+    use bonsai_bt::access::{AccessPolicy, Role};
+    let (_bt, port) = bt_with_access(AccessPolicy::new().token("s3cret", Role::Control));
+
+    let denied = http_get(port, "/");
+    assert!(denied.starts_with("HTTP/1.1 401"), "{denied}");
+    assert!(denied.contains("WWW-Authenticate: Bearer"));
+    assert!(http_get(port, "/?token=wrong").starts_with("HTTP/1.1 401"));
+    assert!(http_get(port, "/?token=s3cret").starts_with("HTTP/1.1 200"));
+    assert!(http_get(port, "/metrics?token=s3cret").starts_with("HTTP/1.1 200"));
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: x\r\nAuthorization: Bearer s3cret\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    let _ = stream.read_to_string(&mut response);
+    assert!(response.starts_with("HTTP/1.1 200"), "bearer header accepted");
+
+    let stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+    match tungstenite::client::client(format!("ws://127.0.0.1:{port}/"), stream) {
+        Err(tungstenite::HandshakeError::Failure(tungstenite::Error::Http(response))) => {
+            assert_eq!(response.status(), 401)
+        }
+        other => panic!("expected a 401 handshake, got {:?}", other.map(|_| ())),
+    }
+    let mut ws = ws_connect_path(port, "/?proto=delta&token=s3cret");
+    read_bt_greeting(&mut ws);
+}
+
+#[test]
+fn clients_outside_the_allow_list_are_forbidden() {
+    use bonsai_bt::access::AccessPolicy;
+    let (_bt, port) = bt_with_access(AccessPolicy::new().allow("10.0.0.0/8".parse().unwrap()));
+    assert!(http_get(port, "/").starts_with("HTTP/1.1 403"));
+
+    let (_bt, port) = bt_with_access(AccessPolicy::new().allow("127.0.0.0/8".parse().unwrap()));
+    assert!(http_get(port, "/").starts_with("HTTP/1.1 200"));
+}
+
+#[test]
+fn viewers_cannot_drive_the_debugger() {
+    use bonsai_bt::access::{AccessPolicy, Role};
+    let (mut bt, port) = bt_with_access(AccessPolicy::new().token("op", Role::Control).read_only());
+    let debugger = bt.debugger();
+
+    let mut viewer = ws_connect_path(port, "/");
+    read_viewer_greeting(&mut viewer);
+    viewer
+        .send(tungstenite::Message::Text(r#"{"cmd":"pause"}"#.into()))
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(!debugger.is_paused(), "viewer commands are ignored");
+
+    let mut operator = ws_connect_path(port, "/?token=op");
+    read_bt_greeting(&mut operator);
+    operator
+        .send(tungstenite::Message::Text(r#"{"cmd":"pause"}"#.into()))
+        .unwrap();
+    let deadline = std::time::Instant::now() + Duration::from_secs(2);
+    while !debugger.is_paused() {
+        assert!(std::time::Instant::now() < deadline, "operator pause never applied");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn operator_replies_share_the_stream_with_frames() {
+    use bonsai_bt::access::AccessPolicy;
+    let (mut bt, port) = bt_with_access(AccessPolicy::open());
+    let debugger = bt.debugger();
+    let mut ws = ws_connect_path(port, "/");
+    read_bt_greeting(&mut ws);
+    std::thread::sleep(Duration::from_millis(100));
+
+    ws.send(tungstenite::Message::Ping(b"hi".to_vec())).unwrap();
+    for _ in 0..20 {
+        tick_ab(&mut bt, Status::Running, Status::Running);
+    }
+    // The pong is interleaved with whole frames, never inside one.
+    let mut ponged = false;
+    let mut ticks = 0;
+    while !ponged || ticks < 20 {
+        match ws.read().expect("ws read") {
+            tungstenite::Message::Pong(payload) => {
+                assert_eq!(payload, b"hi");
+                ponged = true;
+            }
+            tungstenite::Message::Text(text) => {
+                let frame: serde_json::Value = serde_json::from_str(&text).expect("whole JSON frame");
+                ticks += usize::from(frame.get("tick_id").is_some());
+            }
+            other => panic!("unexpected frame {other:?}"),
+        }
+    }
+
+    ws.send(tungstenite::Message::Text(r#"{"cmd":"pause"}"#.into()))
+        .unwrap();
+    let deadline = std::time::Instant::now() + Duration::from_secs(2);
+    while !debugger.is_paused() {
+        assert!(std::time::Instant::now() < deadline, "pause never applied");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn replaced_trees_are_rebroadcast_and_greet_late_clients() {
+    use bonsai_bt::reload::ReloadPolicy;
+    use bonsai_bt::Sequence;
+
+    let port = reserve_free_port();
+    let mut bt = BT::new(Sequence(vec![Action("a"), Action("b")]), ())
+        .with_telemetry_at("127.0.0.1", port)
+        .unwrap();
+    let mut ws = ws_connect_path(port, "/?proto=delta");
+    read_viewer_greeting(&mut ws);
+    std::thread::sleep(Duration::from_millis(100));
+    tick_ab(&mut bt, Status::Success, Status::Running);
+    bt.replace_behavior(
+        Sequence(vec![Action("a"), Action("b"), Action("c")]),
+        ReloadPolicy::PreserveMatching,
+    );
+    tick_ab(&mut bt, Status::Success, Status::Running);
+
+    let key: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(key["tick_id"], 1);
+    let tree: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(tree["root"]["children"].as_array().unwrap().len(), 3);
+    assert_eq!(tree["root"]["children"][2]["label"], "\"c\"");
+    // Deltas against the old tree would be meaningless: a keyframe follows.
+    let key: serde_json::Value = serde_json::from_str(&read_text(&mut ws)).unwrap();
+    assert_eq!(key["tick_id"], 2);
+    assert_eq!(key["key"], true);
+    assert_eq!(key["delta"], serde_json::json!([[0, 1], [2, 1]]), "b still running");
+
+    let mut late = ws_connect(port);
+    let tree: serde_json::Value = serde_json::from_str(&read_text(&mut late)).unwrap();
+    assert_eq!(tree["root"]["children"].as_array().unwrap().len(), 3);
+
+    let body = http_get(port, "/metrics");
+    assert!(
+        body.contains("node_id=\"3\",node_type=\"Action\",label=\"\\\"c\\\"\",status=\"running\"} 0\n"),
+        "{body}"
+    );
+    assert!(body.contains("bonsai_ticks_total 2\n"), "{body}");
+}
@@ -0,0 +1,217 @@
+//! Tests for BehaviorTree.CPP XML import and export.
+
+use bonsai_bt::xml::{self, XmlAction, XmlIssue, XmlIssueKind};
+use bonsai_bt::{
+    Action, After, AlwaysSucceed, Behavior, If, Invert, Race, Select, Sequence, Wait, WaitForever, WhenAll, WhenAny,
+    While, WhileAll,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Act {
+    Check(String),
+    Move {
+        x: i32,
+    },
+    /// Named like a control node.
+    Sequence,
+}
+
+fn from_xml(node: &XmlAction) -> Option<Act> {
+    match node.name.as_str() {
+        "Check" => Some(Act::Check(node.get("what")?.to_string())),
+        "Move" => Some(Act::Move {
+            x: node.get("x")?.parse().ok()?,
+        }),
+        "Sequence" => Some(Act::Sequence),
+        _ => None,
+    }
+}
+
+fn to_xml(act: &Act) -> XmlAction {
+    match act {
+        Act::Check(what) => XmlAction::new("Check").port("what", what),
+        Act::Move { x } => XmlAction::new("Move").port("x", x),
+        Act::Sequence => XmlAction::new("Sequence"),
+    }
+}
+
+fn check(what: &str) -> Behavior<Act> {
+    Action(Act::Check(what.to_string()))
+}
+
+fn issues(xml: &str) -> Vec<XmlIssue> {
+    xml::from_str(xml, from_xml).unwrap_err().issues
+}
+
+#[test]
+fn parses_a_groot_document() {
+    let doc = r#"<?xml version="1.0" encoding="UTF-8"?>
+<root BTCPP_format="4" main_tree_to_execute="Main">
+  <BehaviorTree ID="Main">
+    <ReactiveSequence name="top">
+      <!-- v3-style wrappers still work -->
+      <Condition ID="Check" what="battery"/>
+      <Fallback>
+        <SubTree ID="Dock"/>
+        <RetryUntilSuccessful num_attempts="2">
+          <Move x="1"/>
+        </RetryUntilSuccessful>
+      </Fallback>
+      <Parallel success_count="1" failure_count="-1">
+        <ForceFailure><Check what="a"/></ForceFailure>
+        <Delay delay_msec="250"><Action ID="Move" x="-2"/></Delay>
+      </Parallel>
+    </ReactiveSequence>
+  </BehaviorTree>
+  <BehaviorTree ID="Dock">
+    <IfThenElse>
+      <Inverter><Check what="docked"/></Inverter>
+      <Repeat num_cycles="1"><Sleep msec="1500"/></Repeat>
+      <ForceSuccess><Move x="0"/></ForceSuccess>
+    </IfThenElse>
+  </BehaviorTree>
+  <TreeNodesModel>
+    <Action ID="Move"><input_port name="x"/></Action>
+  </TreeNodesModel>
+</root>"#;
+    let moves = |x| Action(Act::Move { x });
+    let expected = Sequence(vec![
+        check("battery"),
+        Select(vec![
+            If(
+                Box::new(Invert(Box::new(check("docked")))),
+                Box::new(Wait(1.5)),
+                Box::new(AlwaysSucceed(Box::new(moves(0)))),
+            ),
+            Select(vec![moves(1), moves(1)]),
+        ]),
+        WhenAny(vec![
+            Invert(Box::new(AlwaysSucceed(Box::new(check("a"))))),
+            Sequence(vec![Wait(0.25), moves(-2)]),
+        ]),
+    ])
+    .memory(false);
+    assert_eq!(xml::from_str(doc, from_xml).unwrap(), expected);
+}
+
+#[test]
+fn actions_see_every_port_but_the_wrapper_id() {
+    let mut seen = Vec::new();
+    let behavior = xml::from_str(
+        r#"<BehaviorTree ID="T"><Action ID="Check" what="x" name="first"/></BehaviorTree>"#,
+        |node: &XmlAction| {
+            seen.push(node.clone());
+            from_xml(node)
+        },
+    )
+    .unwrap();
+    assert_eq!(behavior, check("x"));
+    assert_eq!(seen, [XmlAction::new("Check").port("name", "first").port("what", "x")]);
+}
+
+#[test]
+fn behaviors_round_trip() {
+    let behavior = Sequence(vec![
+        While(
+            Box::new(WaitForever),
+            vec![Wait(0.125), Action(Act::Sequence), check("quote \" & <angle>")],
+        ),
+        WhileAll(Box::new(check("c")), vec![Select(vec![]).memory(false)]),
+        WhenAll(vec![After(vec![check("a"), check("b")]), Race(vec![check("r")])]),
+        WhenAny(vec![Invert(Box::new(check("i")))]),
+        Invert(Box::new(AlwaysSucceed(Box::new(Action(Act::Move { x: 3 }))))),
+        If(
+            Box::new(check("if")),
+            Box::new(AlwaysSucceed(Box::new(check("then")))),
+            Box::new(Sequence(vec![]).memory(false)),
+        ),
+    ]);
+    let out = xml::to_string(&behavior, to_xml);
+    assert!(out.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<root BTCPP_format=\"4\""));
+    assert!(out.contains("<Action ID=\"Sequence\"/>"), "{out}");
+    assert!(
+        out.contains("<Check what=\"quote &quot; &amp; &lt;angle&gt;\"/>"),
+        "{out}"
+    );
+    assert!(out.contains("<Sleep msec=\"125\"/>"), "{out}");
+    assert!(out.contains("<ForceFailure>"), "{out}");
+    assert_eq!(xml::from_str(&out, from_xml).unwrap(), behavior);
+}
+
+#[test]
+fn wait_round_trips_in_whole_milliseconds() {
+    let out = xml::to_string(&Sequence(vec![Wait(0.0004), Wait(0.0126)]), to_xml);
+    assert!(out.contains("<Sleep msec=\"0\"/>"), "{out}");
+    assert!(out.contains("<Sleep msec=\"13\"/>"), "{out}");
+    assert_eq!(
+        xml::from_str(&out, from_xml).unwrap(),
+        Sequence(vec![Wait(0.0), Wait(0.013)])
+    );
+}
+
+#[test]
+fn reports_every_unsupported_node_with_its_position() {
+    let doc = "<root main_tree_to_execute=\"T\">\n\
+               <BehaviorTree ID=\"T\">\n\
+               <Sequence>\n\
+               <Timeout msec=\"5\">\n\
+               <Fly/>\n\
+               </Timeout>\n\
+               <Parallel success_count=\"2\"><Move x=\"1\"/><Move x=\"2\"/><Move x=\"3\"/></Parallel>\n\
+               <Repeat num_cycles=\"-1\"><Move x=\"1\"/></Repeat>\n\
+               <Inverter/>\n\
+               </Sequence>\n\
+               </BehaviorTree>\n\
+               </root>";
+    let found: Vec<(u64, XmlIssueKind)> = issues(doc).into_iter().map(|i| (i.line, i.kind)).collect();
+    assert_eq!(
+        found,
+        [
+            (4, XmlIssueKind::Unsupported("Timeout".into())),
+            (5, XmlIssueKind::UnknownAction("Fly".into())),
+            (
+                7,
+                XmlIssueKind::Unsupported("Parallel success_count=2 failure_count=1".into())
+            ),
+            (
+                8,
+                XmlIssueKind::BadAttribute {
+                    element: "Repeat".into(),
+                    attribute: "num_cycles".into(),
+                    value: "-1".into(),
+                    expected: "a count from 1 to 100",
+                }
+            ),
+            (
+                9,
+                XmlIssueKind::ChildCount {
+                    element: "Inverter".into(),
+                    expected: "exactly 1",
+                    found: 0,
+                }
+            ),
+        ]
+    );
+}
+
+#[test]
+fn reports_syntax_errors_and_tree_lookup_problems() {
+    let syntax = issues("<root>\n  <BehaviorTree ID=\"T\">\n</root>");
+    assert_eq!(syntax.len(), 1);
+    assert!(matches!(syntax[0].kind, XmlIssueKind::Syntax(_)));
+    assert_eq!(syntax[0].line, 3);
+
+    let recursive = issues(r#"<root><BehaviorTree ID="T"><SubTree ID="T"/></BehaviorTree></root>"#);
+    assert_eq!(recursive[0].kind, XmlIssueKind::RecursiveSubTree("T".into()));
+
+    let missing = issues(r#"<root><BehaviorTree ID="T"><SubTree ID="U"/></BehaviorTree></root>"#);
+    assert_eq!(missing[0].kind, XmlIssueKind::UnknownTree("U".into()));
+
+    let ambiguous = issues(
+        r#"<root><BehaviorTree ID="T"><Move x="1"/></BehaviorTree><BehaviorTree ID="U"><Move x="1"/></BehaviorTree></root>"#,
+    );
+    assert_eq!(ambiguous[0].kind, XmlIssueKind::NoMainTree);
+
+    let err = xml::from_str("<root><BehaviorTree ID=\"T\"><Fly/></BehaviorTree></root>", from_xml).unwrap_err();
+    assert_eq!(err.to_string(), "line 1, column 28: no action mapped for <Fly>");
+}